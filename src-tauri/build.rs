@@ -1,3 +1,12 @@
 fn main() {
+    // 发布时可通过环境变量 STARRANDOM_EXPECTED_SHA256 提供可执行文件的预期哈希，
+    // 编译期嵌入后由 verify_self_integrity 在启动时自检
+    println!("cargo:rerun-if-env-changed=STARRANDOM_EXPECTED_SHA256");
+    let expected = std::env::var("STARRANDOM_EXPECTED_SHA256").unwrap_or_default();
+    println!(
+        "cargo:rustc-env=STARRANDOM_EXPECTED_SHA256={}",
+        expected.trim().to_lowercase()
+    );
+
     tauri_build::build()
-} 
\ No newline at end of file
+}
@@ -0,0 +1,211 @@
+// 应用锁（主密码）
+//
+// 用 set_master_password 设置主密码后，每次启动时应用处于锁定状态：读取或修改数据的命令（历史记录、名单、抽取、
+// 导入导出、备份恢复、统计、审计、日志以及通用文件 API）都会返回 PERMISSION_DENIED，直到 verify_master_password 验证通过；
+// lock_app 可随时重新锁定。读取设置、窗口与界面状态等不涉及数据的命令不受限制，定时抽取在锁定时也照常执行。
+// 主密码只以 Argon2id 哈希（PHC 字符串，见 password.rs）保存在 coredata/app_lock.json 中。
+// 连续输错 MAX_FREE_ATTEMPTS 次后需等待一段时间才能再次尝试，每多错一次等待时间翻倍（最长 MAX_LOCKOUT）；
+// 失败次数与等待截止时间同样保存在 app_lock.json 中，重启程序不会清除。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::{encryption, history, storage};
+
+// 发给前端的事件名，锁定状态变化时发送
+const APP_LOCK_EVENT: &str = "app-lock-changed";
+
+// 无需等待即可尝试的连续失败次数
+const MAX_FREE_ATTEMPTS: u64 = 5;
+
+// 超出后第一次的等待时间（秒）
+const BASE_LOCKOUT_SECS: i64 = 30;
+
+// 最长等待时间（秒）
+const MAX_LOCKOUT_SECS: i64 = 15 * 60;
+
+// 本次运行中是否已通过主密码验证
+static UNLOCKED: AtomicBool = AtomicBool::new(false);
+
+// 串行化对 app_lock.json 的读改写，避免并发验证时丢失失败计数
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+// 应用锁配置文件 coredata/app_lock.json，设置过主密码后才存在
+fn config_path() -> Result<std::path::PathBuf, String> {
+    Ok(history::coredata_dir()?.join("app_lock.json"))
+}
+
+fn load_config() -> Result<Option<serde_json::Value>, AppError> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| AppError::io("读取应用锁配置失败", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| AppError::Parse(format!("解析应用锁配置失败: {}", e)))
+}
+
+fn save_config(config: &serde_json::Value) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config).map_err(|e| format!("序列化应用锁配置失败: {}", e))?;
+    storage::write_atomic(&config_path()?, content.as_bytes()).map_err(|e| format!("保存应用锁配置失败: {}", e))
+}
+
+fn password_matches(config: &serde_json::Value, password: &str) -> Result<bool, String> {
+    let stored = config
+        .get("hash")
+        .and_then(|v| v.as_str())
+        .ok_or("应用锁配置缺少密码哈希")?;
+    crate::password::verify(password, stored).map_err(|e| format!("应用锁配置中的{}", e))
+}
+
+fn failed_attempts(config: &serde_json::Value) -> u64 {
+    config.get("failedAttempts").and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+// 距离可以再次尝试还要等待的秒数，0 表示可以立即尝试
+fn retry_after_secs(config: &serde_json::Value) -> i64 {
+    config
+        .get("lockedUntil")
+        .and_then(|v| v.as_str())
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|until| (until.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds().max(0))
+        .unwrap_or(0)
+}
+
+// 第 failures 次连续失败后需要等待的秒数
+fn lockout_secs(failures: u64) -> i64 {
+    if failures < MAX_FREE_ATTEMPTS {
+        return 0;
+    }
+    let doublings = (failures - MAX_FREE_ATTEMPTS).min(16) as u32;
+    BASE_LOCKOUT_SECS.saturating_mul(1 << doublings).min(MAX_LOCKOUT_SECS)
+}
+
+// 校验主密码并更新失败计数：等待期内直接拒绝，密码错误时累计失败次数，正确时清零
+fn check_password(config: &mut serde_json::Value, password: &str) -> Result<bool, AppError> {
+    let wait = retry_after_secs(config);
+    if wait > 0 {
+        return Err(AppError::PermissionDenied(format!("密码错误次数过多，请 {} 秒后再试", wait)));
+    }
+
+    let matches = password_matches(config, password)?;
+    let failures = if matches { 0 } else { failed_attempts(config) + 1 };
+    let locked_until = match lockout_secs(failures) {
+        0 => serde_json::Value::Null,
+        secs => serde_json::Value::String((chrono::Utc::now() + chrono::Duration::seconds(secs)).to_rfc3339()),
+    };
+    config["failedAttempts"] = serde_json::json!(failures);
+    config["lockedUntil"] = locked_until;
+    save_config(config)?;
+
+    if !matches {
+        log::warn!("主密码验证失败（连续 {} 次）", failures);
+    }
+    Ok(matches)
+}
+
+// 是否设置了主密码
+pub fn is_enabled() -> bool {
+    config_path().is_ok_and(|path| path.exists())
+}
+
+// 受应用锁保护的命令开头调用：设置了主密码但本次运行尚未验证时返回 PermissionDenied
+pub fn ensure_unlocked() -> Result<(), AppError> {
+    if !UNLOCKED.load(Ordering::SeqCst) && is_enabled() {
+        return Err(AppError::PermissionDenied("应用已锁定，请先输入主密码".to_string()));
+    }
+    Ok(())
+}
+
+fn emit_lock_changed(app_handle: &tauri::AppHandle, locked: bool) {
+    if let Err(e) = app_handle.emit(APP_LOCK_EVENT, serde_json::json!({ "locked": locked })) {
+        log::error!("发送 {} 事件失败: {}", APP_LOCK_EVENT, e);
+    }
+}
+
+// 设置、修改或移除主密码
+//
+// 已设置主密码时须提供正确的 current_password（受失败等待限制）；new_password 为空表示移除主密码。
+// 设置成功后本次运行保持解锁状态。
+#[tauri::command]
+pub async fn set_master_password(
+    app_handle: tauri::AppHandle,
+    current_password: Option<String>,
+    new_password: String,
+) -> Result<(), AppError> {
+    let _state_guard = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(mut config) = load_config()? {
+        let current = current_password.unwrap_or_default();
+        if !check_password(&mut config, &current)? {
+            return Err(AppError::PermissionDenied("当前主密码不正确".to_string()));
+        }
+    }
+
+    if new_password.is_empty() {
+        let path = config_path()?;
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| AppError::io("移除应用锁配置失败", e))?;
+            log::info!("已移除主密码");
+        }
+    } else {
+        save_config(&serde_json::json!({
+            "version": 1,
+            "hash": crate::password::hash(&new_password)?,
+            "failedAttempts": 0,
+            "lockedUntil": null,
+        }))?;
+        log::info!("已设置主密码");
+    }
+
+    UNLOCKED.store(true, Ordering::SeqCst);
+    emit_lock_changed(&app_handle, false);
+    Ok(())
+}
+
+// 验证主密码，正确时解锁应用并返回 true，错误时返回 false；等待期内返回 PermissionDenied
+#[tauri::command]
+pub async fn verify_master_password(app_handle: tauri::AppHandle, password: String) -> Result<bool, AppError> {
+    let _state_guard = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let Some(mut config) = load_config()? else {
+        return Err(AppError::NotFound("尚未设置主密码".to_string()));
+    };
+    let matches = check_password(&mut config, &password)?;
+    if matches {
+        UNLOCKED.store(true, Ordering::SeqCst);
+        log::info!("主密码验证通过，应用已解锁");
+        emit_lock_changed(&app_handle, false);
+    }
+    Ok(matches)
+}
+
+// 锁定应用，同时清除本次运行中已解锁的任务密码；未设置主密码时无效
+#[tauri::command]
+pub async fn lock_app(app_handle: tauri::AppHandle) -> Result<(), AppError> {
+    if !is_enabled() {
+        return Err(AppError::InvalidInput("尚未设置主密码，无法锁定应用".to_string()));
+    }
+    UNLOCKED.store(false, Ordering::SeqCst);
+    encryption::forget_task_keys();
+    log::info!("应用已锁定");
+    emit_lock_changed(&app_handle, true);
+    Ok(())
+}
+
+// 应用锁状态：是否设置了主密码、当前是否锁定、连续失败次数以及还需等待的秒数
+#[tauri::command]
+pub async fn get_app_lock_status() -> Result<serde_json::Value, AppError> {
+    let config = load_config()?;
+    let enabled = config.is_some();
+    Ok(serde_json::json!({
+        "enabled": enabled,
+        "locked": enabled && !UNLOCKED.load(Ordering::SeqCst),
+        "failedAttempts": config.as_ref().map(failed_attempts).unwrap_or(0),
+        "retryAfterSeconds": config.as_ref().map(retry_after_secs).unwrap_or(0),
+    }))
+}
@@ -0,0 +1,354 @@
+// 审计与完整性校验
+//
+// 集中放置哈希计算等与可验证性相关的逻辑。
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::{export, history, storage};
+
+// 编译期嵌入的可执行文件预期哈希（未配置时为空字符串）
+const EXPECTED_EXE_SHA256: &str = env!("STARRANDOM_EXPECTED_SHA256");
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 计算字节内容的 SHA-256，返回小写十六进制字符串
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+// 姓名列表的哈希：对 JSON 数组序列化后的内容取 SHA-256，顺序敏感
+pub fn hash_name_list(names: &[String]) -> String {
+    let canonical = serde_json::to_string(names).unwrap_or_default();
+    sha256_hex(canonical.as_bytes())
+}
+
+// 流式计算文件的 SHA-256，避免一次性读入大文件
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+// 校验当前可执行文件的哈希是否与编译期嵌入的预期值一致
+pub fn check_self_integrity() -> Result<serde_json::Value, String> {
+    let exe_path = std::env::current_exe().map_err(|e| {
+        let error = format!("获取exe路径失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    let computed = sha256_file(&exe_path)?;
+    let expected = if EXPECTED_EXE_SHA256.is_empty() {
+        None
+    } else {
+        Some(EXPECTED_EXE_SHA256.to_string())
+    };
+    let matches = expected.as_deref() == Some(computed.as_str());
+
+    match &expected {
+        Some(expected) if !matches => {
+            log::warn!("可执行文件完整性校验失败: 预期 {}, 实际 {}", expected, computed);
+        }
+        Some(_) => log::info!("可执行文件完整性校验通过: {}", computed),
+        None => log::info!("未配置预期哈希，跳过比对，当前exe哈希: {}", computed),
+    }
+
+    Ok(serde_json::json!({
+        "matches": matches,
+        "computed": computed,
+        "expected": expected,
+        "exePath": exe_path.to_string_lossy()
+    }))
+}
+
+// 校验程序自身完整性
+#[tauri::command]
+pub async fn verify_self_integrity() -> Result<serde_json::Value, AppError> {
+    log::info!("校验程序完整性");
+    Ok(check_self_integrity()?)
+}
+
+// 任务的参与者列表（保存任务时可选携带 participants 字段）
+fn task_participants(task_data: &serde_json::Value) -> Option<Vec<String>> {
+    let participants = task_data.get("participants")?.as_array()?;
+    Some(
+        participants
+            .iter()
+            .filter_map(|p| p.as_str().map(|s| s.to_string()))
+            .collect(),
+    )
+}
+
+// 任务使用的随机数算法（前端抽取的旧任务没有该字段）
+fn task_rng(task_data: &serde_json::Value) -> Option<String> {
+    task_data
+        .get("rng")
+        .or_else(|| task_data.get("algorithm"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+// 任务保存的种子的文本形式（数字与字符串统一处理），没有种子时为 None
+fn task_seed(task_data: &serde_json::Value) -> Option<String> {
+    match task_data.get("seed")? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) if s.is_empty() => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+// 导出整个历史记录的可复现清单，返回任务数量
+//
+// 每个任务记录 id、种子、参与者列表哈希、中奖者哈希以及组合哈希
+// combinedHash = sha256("<id>|<seed>|<participantsHash>|<winnersHash>")，
+// 校验脚本可据此重新执行每次抽取并核对整个数据集。
+#[tauri::command]
+pub async fn export_history_manifest(dest_path: String) -> Result<usize, AppError> {
+    log::info!("导出历史记录清单: {}", dest_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let mut tasks = Vec::new();
+    let mut rng_versions = std::collections::BTreeSet::new();
+
+    for index_item in history::load_history_index()? {
+        let Some(task_data) = history::load_task_data(&index_item) else {
+            log::warn!("无法加载任务文件，清单中跳过: {:?}", index_item.get("id"));
+            continue;
+        };
+
+        let id = task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+        let seed = task_data.get("seed").cloned().unwrap_or(serde_json::Value::Null);
+        let seed_str = task_seed(&task_data).unwrap_or_default();
+        let participants_hash = task_participants(&task_data).map(|p| hash_name_list(&p));
+        let winners_hash = hash_name_list(&history::task_winners(&task_data));
+        let combined_hash = sha256_hex(
+            format!(
+                "{}|{}|{}|{}",
+                id,
+                seed_str,
+                participants_hash.as_deref().unwrap_or_default(),
+                winners_hash
+            )
+            .as_bytes(),
+        );
+
+        let rng = task_rng(&task_data);
+        if let Some(rng) = &rng {
+            rng_versions.insert(rng.clone());
+        }
+
+        tasks.push(serde_json::json!({
+            "id": id,
+            "timestamp": task_data.get("timestamp"),
+            "seed": seed,
+            "rng": rng,
+            "method": task_data.get("method"),
+            "participantsHash": participants_hash,
+            "winnersHash": winners_hash,
+            "combinedHash": combined_hash
+        }));
+    }
+
+    let manifest = serde_json::json!({
+        "manifestVersion": 1,
+        "generatedAt": chrono::Utc::now().to_rfc3339(),
+        "hashAlgorithm": "sha256",
+        "rngVersions": rng_versions,
+        "taskCount": tasks.len(),
+        "tasks": tasks
+    });
+
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("序列化清单失败: {}", e))?;
+    storage::write_atomic(Path::new(&dest_path), content.as_bytes()).map_err(|e| {
+        let error = format!("写入清单文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    export::record_export(&dest_path, "history-manifest", Some(tasks.len()), None);
+    log::info!("历史记录清单已导出: {}，共 {} 个任务", dest_path, tasks.len());
+    Ok(tasks.len())
+}
+
+// 账本中第一条记录的 prevHash
+const LEDGER_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// 哈希链账本 coredata/ledger.jsonl，每行一条 JSON 记录
+fn ledger_path() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join("ledger.jsonl"))
+}
+
+// entryHash = sha256(prevHash + taskId + resultsHash + timestamp)
+fn ledger_entry_hash(prev_hash: &str, task_id: &str, results_hash: &str, timestamp: &str) -> String {
+    sha256_hex(format!("{}{}{}{}", prev_hash, task_id, results_hash, timestamp).as_bytes())
+}
+
+// 向账本追加一条抽奖记录，调用方需持有历史索引锁以保证链的顺序
+pub fn append_ledger_entry(task_id: &str, winners: &[String]) -> Result<(), String> {
+    let path = ledger_path()?;
+
+    let prev_hash = if path.exists() {
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("读取账本失败: {}", e))?;
+        content
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .and_then(|entry| entry.get("entryHash").and_then(|v| v.as_str()).map(|s| s.to_string()))
+            .unwrap_or_else(|| LEDGER_GENESIS_HASH.to_string())
+    } else {
+        LEDGER_GENESIS_HASH.to_string()
+    };
+
+    let results_hash = hash_name_list(winners);
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let entry_hash = ledger_entry_hash(&prev_hash, task_id, &results_hash, &timestamp);
+
+    let entry = serde_json::json!({
+        "prevHash": prev_hash,
+        "taskId": task_id,
+        "resultsHash": results_hash,
+        "timestamp": timestamp,
+        "entryHash": entry_hash
+    });
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("打开账本失败: {}", e))?;
+    writeln!(file, "{}", entry).map_err(|e| format!("写入账本失败: {}", e))?;
+
+    log::info!("账本已追加记录: {} -> {}", task_id, entry_hash);
+    Ok(())
+}
+
+// 可复现抽取的审计记录 coredata/draw_audit.jsonl，每行一条 JSON 记录
+fn draw_audit_path() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join("draw_audit.jsonl"))
+}
+
+// 串行化审计记录的追加，避免并发抽取时两行内容交错
+static DRAW_AUDIT_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+// 追加一条抽取审计记录
+pub fn append_draw_record(record: &serde_json::Value) -> Result<(), String> {
+    let _guard = DRAW_AUDIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = draw_audit_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("打开抽取审计记录失败: {}", e))?;
+    writeln!(file, "{}", record).map_err(|e| format!("写入抽取审计记录失败: {}", e))
+}
+
+// 重新计算哈希链，报告第一处断裂
+#[tauri::command]
+pub async fn verify_ledger() -> Result<serde_json::Value, AppError> {
+    log::info!("校验抽奖账本");
+    crate::app_lock::ensure_unlocked()?;
+
+    let path = ledger_path()?;
+    if !path.exists() {
+        return Ok(serde_json::json!({ "valid": true, "entries": 0, "brokenAt": null }));
+    }
+
+    let content = std::fs::read_to_string(&path).map_err(|e| AppError::io("读取账本失败", e))?;
+    let mut expected_prev = LEDGER_GENESIS_HASH.to_string();
+    let mut entries = 0;
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_no = i + 1;
+
+        let broken = |reason: String| {
+            log::warn!("账本第 {} 行校验失败: {}", line_no, reason);
+            serde_json::json!({
+                "valid": false,
+                "entries": entries,
+                "brokenAt": { "line": line_no, "reason": reason }
+            })
+        };
+
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            return Ok(broken("记录无法解析".to_string()));
+        };
+        let field = |key: &str| entry.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+        let prev_hash = field("prevHash");
+        if prev_hash != expected_prev {
+            return Ok(broken(format!("prevHash 与上一条记录不一致 (任务 {})", field("taskId"))));
+        }
+
+        let recomputed = ledger_entry_hash(&prev_hash, &field("taskId"), &field("resultsHash"), &field("timestamp"));
+        if recomputed != field("entryHash") {
+            return Ok(broken(format!("entryHash 不匹配 (任务 {})", field("taskId"))));
+        }
+
+        expected_prev = recomputed;
+        entries += 1;
+    }
+
+    log::info!("账本校验通过，共 {} 条记录", entries);
+    Ok(serde_json::json!({ "valid": true, "entries": entries, "brokenAt": null }))
+}
+
+// 检查历史任务中被重复使用的种子
+//
+// 种子被多个任务重复使用时，这些抽取结果是可预测的。没有记录种子的任务（前端抽取的旧任务等）单独列出。
+#[tauri::command]
+pub async fn audit_seed_reuse() -> Result<serde_json::Value, AppError> {
+    log::info!("检查种子重复使用");
+    crate::app_lock::ensure_unlocked()?;
+
+    let mut by_seed: std::collections::BTreeMap<String, Vec<String>> = std::collections::BTreeMap::new();
+    let mut missing_seed = Vec::new();
+    let mut task_count = 0;
+
+    for index_item in history::load_history_index()? {
+        let Some(task_data) = history::load_task_data(&index_item) else {
+            log::warn!("无法加载任务文件，检查时跳过: {:?}", index_item.get("id"));
+            continue;
+        };
+        task_count += 1;
+
+        let id = task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        match task_seed(&task_data) {
+            Some(seed) => by_seed.entry(seed).or_default().push(id),
+            None => missing_seed.push(id),
+        }
+    }
+
+    let unique_seeds = by_seed.len();
+    let reused: Vec<serde_json::Value> = by_seed
+        .into_iter()
+        .filter(|(_, task_ids)| task_ids.len() > 1)
+        .map(|(seed, task_ids)| {
+            log::warn!("种子 {} 被 {} 个任务重复使用: {:?}", seed, task_ids.len(), task_ids);
+            serde_json::json!({ "seed": seed, "count": task_ids.len(), "taskIds": task_ids })
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "taskCount": task_count,
+        "uniqueSeeds": unique_seeds,
+        "reused": reused,
+        "missingSeed": missing_seed
+    }))
+}
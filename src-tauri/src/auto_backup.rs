@@ -0,0 +1,161 @@
+// 定时自动备份
+//
+// 设置 autoBackupIntervalMinutes（或按天的 autoBackupIntervalDays）大于 0 时，后台任务按该间隔把全部数据打包到
+// coredata/backups/auto-backup-<时间>.zip（格式与 create_backup 相同，可用 restore_backup 恢复），
+// 只保留最近 autoBackupKeep 个自动备份。后台任务每隔一段时间重新读取设置，修改间隔后无需重启。
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tauri::Manager;
+
+use crate::error::AppError;
+use crate::{background, backup, history, settings};
+
+// 自动备份文件名前缀
+const AUTO_BACKUP_PREFIX: &str = "auto-backup-";
+
+// 未配置 autoBackupKeep 时保留的自动备份数
+pub const DEFAULT_AUTO_BACKUP_KEEP: u64 = 10;
+
+// 后台任务检查设置与到期时间的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn backups_dir() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join("backups"))
+}
+
+// 现有的自动备份，按文件名（即时间）从旧到新排列
+fn auto_backups() -> Result<Vec<PathBuf>, String> {
+    let Ok(entries) = std::fs::read_dir(backups_dir()?) else {
+        return Ok(vec![]);
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(AUTO_BACKUP_PREFIX) && n.ends_with(".zip"))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+// 最近一次自动备份的时间（文件修改时间）
+fn last_backup_time() -> Result<Option<SystemTime>, String> {
+    Ok(auto_backups()?
+        .last()
+        .and_then(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok()))
+}
+
+// 自动备份间隔（分钟）：优先使用 autoBackupIntervalMinutes，为 0 时使用 autoBackupIntervalDays
+fn interval_minutes(settings: &serde_json::Value) -> u64 {
+    let get = |key: &str| settings.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    match get("autoBackupIntervalMinutes") {
+        0 => get("autoBackupIntervalDays").saturating_mul(24 * 60),
+        minutes => minutes,
+    }
+}
+
+fn keep_count(settings: &serde_json::Value) -> u64 {
+    settings
+        .get("autoBackupKeep")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_AUTO_BACKUP_KEEP)
+        .max(1)
+}
+
+// 执行一次自动备份并清理超出保留数的旧备份，返回备份文件路径
+fn run_backup(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let current_settings = settings::current_settings(app_handle)?;
+    let dir = backups_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    let path = dir.join(format!(
+        "{}{}.zip",
+        AUTO_BACKUP_PREFIX,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    {
+        let index_lock = app_handle.state::<history::HistoryIndexLock>();
+        let _index_guard = index_lock.lock();
+        // 批量导入中缓存的索引先落盘，保证备份内容完整
+        history::finish_bulk_import()?;
+        backup::write_full_backup(app_handle, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&path);
+            format!("自动备份失败: {}", e)
+        })?;
+    }
+    log::info!("自动备份完成: {:?}", path);
+
+    let backups = auto_backups()?;
+    let excess = backups.len().saturating_sub(keep_count(&current_settings) as usize);
+    for old in &backups[..excess] {
+        match std::fs::remove_file(old) {
+            Ok(()) => log::info!("已删除旧的自动备份: {:?}", old),
+            Err(e) => log::warn!("删除旧的自动备份失败 {:?}: {}", old, e),
+        }
+    }
+    Ok(path)
+}
+
+// 在 setup 中启动自动备份的后台任务
+pub fn spawn(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if !background::enabled() {
+                continue;
+            }
+
+            let Ok(current_settings) = settings::current_settings(&app_handle) else {
+                continue;
+            };
+            let interval = interval_minutes(&current_settings);
+            if interval == 0 {
+                continue;
+            }
+
+            let due = match last_backup_time() {
+                Ok(Some(last)) => last.elapsed().unwrap_or(Duration::MAX) >= Duration::from_secs(interval * 60),
+                Ok(None) => true,
+                Err(e) => {
+                    log::error!("读取自动备份状态失败: {}", e);
+                    continue;
+                }
+            };
+            if !due {
+                continue;
+            }
+
+            let handle = app_handle.clone();
+            match tauri::async_runtime::spawn_blocking(move || run_backup(&handle)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::error!("{}", e),
+                Err(e) => log::error!("自动备份任务异常: {}", e),
+            }
+        }
+    });
+}
+
+// 立即执行一次自动备份，返回备份文件路径
+#[tauri::command]
+pub async fn trigger_backup_now(app_handle: tauri::AppHandle) -> Result<String, AppError> {
+    log::info!("手动触发自动备份");
+    crate::app_lock::ensure_unlocked()?;
+
+    let path = run_backup(&app_handle).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 最近一次自动备份的时间（RFC3339），没有自动备份时返回 None
+#[tauri::command]
+pub async fn get_last_backup_time() -> Result<Option<String>, AppError> {
+    Ok(last_backup_time()?.map(|time| chrono::DateTime::<chrono::Local>::from(time).to_rfc3339()))
+}
@@ -0,0 +1,44 @@
+// 后台任务开关
+//
+// 现场活动期间管理员可以暂停所有后台 I/O（延迟写入设置、自动备份、定时抽取与文件监视通知等），
+// 各定时任务在执行前检查 enabled()，暂停期间跳过本轮工作但保留待处理的数据，
+// 恢复时立即补写被推迟的内容。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::settings::{self, SettingsWriter};
+
+static BACKGROUND_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// 后台任务当前是否允许执行
+pub fn enabled() -> bool {
+    BACKGROUND_ENABLED.load(Ordering::SeqCst)
+}
+
+// 暂停或恢复后台任务
+#[tauri::command]
+pub async fn set_background_tasks_enabled(
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+) -> Result<(), AppError> {
+    log::info!("设置后台任务状态: {}", if enabled { "启用" } else { "暂停" });
+
+    let was_enabled = BACKGROUND_ENABLED.swap(enabled, Ordering::SeqCst);
+
+    // 从暂停恢复时补写被推迟的设置，并通知暂停期间历史数据的外部修改
+    if enabled && !was_enabled {
+        if let Some(written) = app_handle.state::<SettingsWriter>().flush()? {
+            settings::emit_settings_changed(&app_handle, &written);
+        }
+        crate::watcher::flush_deferred(&app_handle);
+    }
+
+    app_handle
+        .emit("background-tasks-state", serde_json::json!({ "enabled": enabled }))
+        .map_err(|e| format!("发送后台任务状态事件失败: {}", e))?;
+
+    Ok(())
+}
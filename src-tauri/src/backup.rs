@@ -0,0 +1,742 @@
+// 备份与配置档案
+//
+// 使用 zip 归档打包 coredata 与设置文件。归档内的路径统一使用 "/" 分隔，
+// coredata 下的文件以 "coredata/" 为前缀，设置文件为 "settings.json"。
+// 解包时所有条目都经过 safe_entry_path 校验，防止 zip-slip 路径穿越。
+// 历史索引数据库（history.db）不直接打包，而是导出为 coredata/history.json，恢复时再写回数据库。
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+
+use crate::error::AppError;
+use crate::settings::SettingsWriter;
+use crate::{audit, encryption, export, history, history_db, settings, storage};
+
+// 配置档案的清单文件名
+const PROFILE_MANIFEST: &str = "profile.json";
+
+// 配置档案中不包含的 coredata 顶层条目（运行时状态、历史导出记录等）
+const PROFILE_EXCLUDED: &[&str] = &[".lock", "backups", "exports.json", "logs"];
+
+// 配置档案不导出、导入时拒绝的 coredata 顶层条目：PROFILE_EXCLUDED，以及只属于本机的应用锁、加密配置与账本
+// （storage::PROTECTED_DATA_FILES）。导入其他电脑的这些文件会替换主密码与加密配置，并让账本校验失败。
+fn is_profile_excluded(top: &str) -> bool {
+    let top = top.to_lowercase();
+    PROFILE_EXCLUDED.contains(&top.as_str()) || storage::PROTECTED_DATA_FILES.contains(&top.as_str())
+}
+
+// 历史记录相关的 coredata 顶层条目，仅在 include_history 时打包
+const HISTORY_ENTRIES: &[&str] = &["history", HISTORY_INDEX_ENTRY];
+
+// 归档中保存历史索引的条目（相对于 coredata/）
+const HISTORY_INDEX_ENTRY: &str = "history.json";
+
+// 打包 coredata 时跳过的历史索引文件：数据库本身，以及旧版本遗留或前端创建的 history.json
+fn is_index_file(top: &str) -> bool {
+    top == HISTORY_INDEX_ENTRY || history_db::DB_ENTRIES.contains(&top)
+}
+
+// 历史索引在归档中的内容（coredata/history.json）
+fn history_index_bytes() -> Result<Vec<u8>, String> {
+    let history_index = history::load_history_index()?;
+    serde_json::to_vec_pretty(&history_index).map_err(|e| format!("序列化索引失败: {}", e))
+}
+
+// 把历史索引以 coredata/history.json 写入归档
+fn add_history_index<W: Write + std::io::Seek>(zip: &mut zip::ZipWriter<W>) -> Result<(), String> {
+    add_bytes(zip, &format!("coredata/{}", HISTORY_INDEX_ENTRY), &history_index_bytes()?)
+}
+
+// 解析归档中的 history.json
+fn parse_history_index(data: &[u8]) -> Result<Vec<serde_json::Value>, String> {
+    serde_json::from_slice(data).map_err(|e| format!("归档中的 history.json 无效: {}", e))
+}
+
+pub fn zip_options() -> SimpleFileOptions {
+    SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated)
+}
+
+// 向归档中写入一段内容
+pub fn add_bytes<W: Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    entry_name: &str,
+    data: &[u8],
+) -> Result<(), String> {
+    zip.start_file(entry_name, zip_options())
+        .map_err(|e| format!("写入归档条目失败 {}: {}", entry_name, e))?;
+    zip.write_all(data)
+        .map_err(|e| format!("写入归档条目失败 {}: {}", entry_name, e))
+}
+
+// 递归打包目录，skip 用于按相对路径（"/" 分隔）过滤条目
+pub fn add_dir<W: Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    dir: &Path,
+    prefix: &str,
+    skip: &dyn Fn(&str) -> bool,
+) -> Result<usize, String> {
+    let mut files = Vec::new();
+    collect_dir(dir, prefix, skip, &mut files)?;
+    for (relative, data) in &files {
+        add_bytes(zip, relative, data)?;
+    }
+    Ok(files.len())
+}
+
+// 递归读取目录中的文件，把 (归档内路径, 内容) 追加到 files；skip 的含义与 add_dir 相同
+fn collect_dir(
+    dir: &Path,
+    prefix: &str,
+    skip: &dyn Fn(&str) -> bool,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), String> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("读取目录失败 {:?}: {}", dir, e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let relative = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if skip(&relative) {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.is_dir() {
+            collect_dir(&path, &relative, skip, files)?;
+        } else {
+            let data = std::fs::read(&path).map_err(|e| format!("读取文件失败 {:?}: {}", path, e))?;
+            files.push((relative, data));
+        }
+    }
+
+    Ok(())
+}
+
+// 校验归档条目路径：必须是不含 ".." 与绝对路径的相对路径
+pub fn safe_entry_path(entry: &zip::read::ZipFile) -> Result<PathBuf, String> {
+    entry
+        .enclosed_name()
+        .ok_or_else(|| format!("归档中包含不安全的路径: {}", entry.name()))
+}
+
+// 打开归档文件
+pub fn open_archive(src_zip: &str) -> Result<zip::ZipArchive<std::fs::File>, AppError> {
+    let file = std::fs::File::open(src_zip).map_err(|e| AppError::io("打开归档失败", e))?;
+    zip::ZipArchive::new(file).map_err(|e| AppError::Parse(format!("无效的归档文件: {}", e)))
+}
+
+// 读取归档中所有文件条目（已校验路径），返回 (相对路径, 内容)
+pub fn read_all_entries(
+    archive: &mut zip::ZipArchive<std::fs::File>,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("读取归档条目失败: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        let path = safe_entry_path(&entry)?;
+        let name = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| format!("读取归档条目失败 {}: {}", name, e))?;
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+// coredata 内的顶层条目名
+fn top_level(relative: &str) -> &str {
+    relative.split('/').next().unwrap_or(relative)
+}
+
+// 导出完整的应用配置档案（设置、名单及规则等，可选包含历史记录）
+#[tauri::command]
+pub async fn export_profile(
+    app_handle: tauri::AppHandle,
+    dest_zip: String,
+    include_history: bool,
+) -> Result<(), AppError> {
+    log::info!("导出配置档案: {} (包含历史: {})", dest_zip, include_history);
+    crate::app_lock::ensure_unlocked()?;
+
+    let file = std::fs::File::create(&dest_zip).map_err(|e| {
+        let error = format!("创建档案文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    let mut zip = zip::ZipWriter::new(file);
+
+    let manifest = serde_json::json!({
+        "type": "profile",
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "createdAt": chrono::Utc::now().to_rfc3339(),
+        "includeHistory": include_history
+    });
+    add_bytes(&mut zip, PROFILE_MANIFEST, manifest.to_string().as_bytes())?;
+
+    let current_settings = settings::current_settings(&app_handle)?;
+    let settings_str = serde_json::to_string_pretty(&current_settings).map_err(|e| e.to_string())?;
+    add_bytes(&mut zip, "settings.json", settings_str.as_bytes())?;
+
+    let skip = |relative: &str| {
+        let top = top_level(relative.strip_prefix("coredata/").unwrap_or(relative));
+        is_profile_excluded(top) || is_index_file(top) || (!include_history && HISTORY_ENTRIES.contains(&top))
+    };
+    let mut count = add_dir(&mut zip, &history::coredata_dir()?, "coredata", &skip)?;
+    if include_history {
+        add_history_index(&mut zip)?;
+        count += 1;
+    }
+
+    zip.finish().map_err(|e| format!("完成档案写入失败: {}", e))?;
+
+    export::record_export(&dest_zip, "profile", Some(count), None);
+    log::info!("配置档案已导出: {}，包含 {} 个数据文件", dest_zip, count);
+    Ok(())
+}
+
+// 合并两个历史索引，已有的任务 id 保持不变
+fn merge_history_index(existing: &mut Vec<serde_json::Value>, incoming: Vec<serde_json::Value>) {
+    for entry in incoming {
+        let id = entry.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let exists = existing
+            .iter()
+            .any(|item| item.get("id").and_then(|v| v.as_str()) == id.as_deref());
+        if !exists {
+            existing.push(entry);
+        }
+    }
+    existing.sort_by(|a, b| {
+        let ta = a.get("timestamp").and_then(|v| v.as_str()).unwrap_or_default();
+        let tb = b.get("timestamp").and_then(|v| v.as_str()).unwrap_or_default();
+        tb.cmp(ta)
+    });
+}
+
+// 导入配置档案
+//
+// mode 为 "replace" 时用档案内容覆盖现有数据（档案包含名单/历史时先清空对应目录）；
+// 为 "merge" 时只补充本机没有的文件，设置按键合并并以档案中的值为准，历史索引按任务 id 合并。
+#[tauri::command]
+pub async fn import_profile(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    src_zip: String,
+    mode: String,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("导入配置档案: {} (模式: {})", src_zip, mode);
+    crate::app_lock::ensure_unlocked()?;
+
+    let replace = match mode.as_str() {
+        "replace" => true,
+        "merge" => false,
+        other => return Err(AppError::InvalidInput(format!("不支持的导入模式: {}", other))),
+    };
+
+    let mut archive = open_archive(&src_zip)?;
+    let entries = read_all_entries(&mut archive)?;
+
+    // 先完整校验档案，校验通过前不修改任何数据
+    let manifest: serde_json::Value = entries
+        .iter()
+        .find(|(name, _)| name == PROFILE_MANIFEST)
+        .and_then(|(_, data)| serde_json::from_slice(data).ok())
+        .ok_or("档案缺少有效的 profile.json 清单")?;
+    if manifest.get("type").and_then(|v| v.as_str()) != Some("profile") {
+        return Err(AppError::InvalidInput("该文件不是配置档案".to_string()));
+    }
+
+    let mut incoming_settings = None;
+    let mut data_files = Vec::new();
+    for (name, data) in entries {
+        if name == PROFILE_MANIFEST {
+            continue;
+        }
+        if name == "settings.json" {
+            let value: serde_json::Value = serde_json::from_slice(&data)
+                .map_err(|e| format!("档案中的设置文件无效: {}", e))?;
+            settings::validate_settings(&value)?;
+            incoming_settings = Some(value);
+            continue;
+        }
+        match name.strip_prefix("coredata/") {
+            Some(relative) if !is_profile_excluded(top_level(relative)) => {
+                data_files.push((relative.to_string(), data));
+            }
+            _ => return Err(AppError::InvalidInput(format!("档案中包含不允许的条目: {}", name))),
+        }
+    }
+
+    let incoming_index = match data_files.iter().position(|(relative, _)| relative == HISTORY_INDEX_ENTRY) {
+        Some(pos) => Some(parse_history_index(&data_files.remove(pos).1)?),
+        None => None,
+    };
+    data_files.retain(|(relative, _)| !is_index_file(top_level(relative)));
+
+    let coredata_dir = history::coredata_dir()?;
+    let has_dir = |dir: &str| data_files.iter().any(|(r, _)| top_level(r) == dir);
+
+    if replace {
+        for dir in ["groups", "history"] {
+            let path = coredata_dir.join(dir);
+            if has_dir(dir) && path.exists() {
+                std::fs::remove_dir_all(&path).map_err(|e| format!("清空目录失败 {:?}: {}", path, e))?;
+            }
+        }
+    }
+
+    let mut written = 0;
+    let mut skipped = 0;
+    for (relative, data) in data_files {
+        let target = coredata_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+
+        if !replace && target.exists() {
+            skipped += 1;
+            continue;
+        }
+
+        storage::write_atomic(&target, &data).map_err(|e| format!("写入文件失败 {:?}: {}", target, e))?;
+        written += 1;
+    }
+
+    if let Some(incoming) = incoming_index {
+        let _index_guard = index_lock.lock();
+        history::update_history_index(|history_index| {
+            if replace {
+                *history_index = incoming;
+            } else {
+                merge_history_index(history_index, incoming);
+            }
+        })?;
+        written += 1;
+    }
+
+    let settings_applied = incoming_settings.is_some();
+    if let Some(incoming) = incoming_settings {
+        let merged = if replace {
+            incoming
+        } else {
+            let mut current = settings::current_settings(&app_handle)?;
+            if let (Some(current_obj), Some(incoming_obj)) = (current.as_object_mut(), incoming.as_object()) {
+                for (key, value) in incoming_obj {
+                    current_obj.insert(key.clone(), value.clone());
+                }
+            }
+            current
+        };
+        let writer = app_handle.state::<SettingsWriter>();
+        writer.schedule(settings::settings_path(&app_handle)?, merged);
+        if let Some(written) = writer.flush()? {
+            settings::emit_settings_changed(&app_handle, &written);
+        }
+    }
+
+    log::info!("配置档案导入完成: 写入 {} 个文件，跳过 {} 个", written, skipped);
+    Ok(serde_json::json!({
+        "mode": mode,
+        "filesWritten": written,
+        "filesSkipped": skipped,
+        "settingsApplied": settings_applied,
+        "sourceVersion": manifest.get("appVersion")
+    }))
+}
+
+// 归档内的任务文件路径 coredata/history/<年>/<月>/<文件名>，返回 (年, 月, 文件名)
+fn parse_task_entry(name: &str) -> Option<(i32, u32, String)> {
+    let parts: Vec<&str> = name.strip_prefix("coredata/history/")?.split('/').collect();
+    match parts.as_slice() {
+        [year, month, file] if file.ends_with(".json") => {
+            Some((year.parse().ok()?, month.parse().ok()?, file.to_string()))
+        }
+        _ => None,
+    }
+}
+
+// 列出备份归档中的文件
+#[tauri::command]
+pub async fn list_backup_contents(src_zip: String) -> Result<Vec<serde_json::Value>, AppError> {
+    log::info!("列出备份内容: {}", src_zip);
+    crate::app_lock::ensure_unlocked()?;
+
+    let mut archive = open_archive(&src_zip)?;
+    let mut contents = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| format!("读取归档条目失败: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        let safe = entry.enclosed_name().is_some();
+        contents.push(serde_json::json!({
+            "path": entry.name(),
+            "size": entry.size(),
+            "compressedSize": entry.compressed_size(),
+            "isTask": parse_task_entry(entry.name()).is_some(),
+            "safe": safe
+        }));
+    }
+
+    Ok(contents)
+}
+
+// 从备份中恢复单个文件到 coredata 下的原位置；恢复的是任务文件时同时把它加回历史索引
+#[tauri::command]
+pub async fn restore_backup_entry(
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    src_zip: String,
+    entry_path: String,
+) -> Result<(), AppError> {
+    log::info!("从备份恢复单个文件: {} -> {}", src_zip, entry_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let mut archive = open_archive(&src_zip)?;
+    let mut entry = archive
+        .by_name(&entry_path)
+        .map_err(|_| format!("备份中不存在该文件: {}", entry_path))?;
+
+    let path = safe_entry_path(&entry)?;
+    let relative = path
+        .strip_prefix("coredata")
+        .map_err(|_| format!("只能恢复 coredata 下的文件: {}", entry_path))?
+        .to_path_buf();
+    let relative_str = relative.to_string_lossy().replace('\\', "/");
+    if relative_str.is_empty() || top_level(&relative_str) == ".lock" {
+        return Err(AppError::InvalidInput(format!("不允许恢复该条目: {}", entry_path)));
+    }
+
+    let mut data = Vec::new();
+    entry
+        .read_to_end(&mut data)
+        .map_err(|e| format!("读取备份条目失败: {}", e))?;
+
+    // 历史索引写回数据库，而不是写成文件
+    if relative_str == HISTORY_INDEX_ENTRY {
+        let incoming = parse_history_index(&data)?;
+        let _index_guard = index_lock.lock();
+        let count = history::update_history_index(|history_index| *history_index = incoming)?;
+        log::info!("已从备份恢复历史索引，共 {} 条记录", count);
+        return Ok(());
+    }
+
+    let target = history::coredata_dir()?.join(&relative);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+
+    let task = parse_task_entry(&entry_path);
+    let task_data = match &task {
+        Some(_) => {
+            let task_file: serde_json::Value = serde_json::from_slice(&data)
+                .map_err(|e| format!("任务文件格式无效: {}", e))?;
+            Some(encryption::task_data_of(&task_file)?)
+        }
+        None => None,
+    };
+
+    storage::write_atomic(&target, &data).map_err(|e| format!("写入文件失败: {}", e))?;
+    log::info!("已恢复文件: {:?}", target);
+
+    if let (Some((year, month, file_name)), Some(task_data)) = (task, task_data) {
+        let _index_guard = index_lock.lock();
+        history::update_history_index(|history_index| {
+            history::upsert_index_entry(history_index, history::build_index_entry(&task_data, &file_name, year, month));
+        })?;
+        log::info!("已将恢复的任务加回历史索引: {}", file_name);
+    }
+
+    Ok(())
+}
+
+// 完整备份的清单文件名
+const BACKUP_MANIFEST: &str = "backup.json";
+
+// 当前的完整备份格式版本，格式变化时递增，以便新版本识别并迁移旧备份
+//
+// 版本 2：清单中记录每个条目的 SHA-256 校验和（checksums），恢复前逐一校验。
+const BACKUP_FORMAT_VERSION: u64 = 2;
+
+// 完整备份中不包含的 coredata 顶层条目：实例锁以及备份目录本身
+const BACKUP_EXCLUDED: &[&str] = &[".lock", "backups"];
+
+// 把整个 coredata（含名单、抽取池等）与设置文件写入归档，返回打包的数据文件数（调用方须持有历史索引锁）
+pub fn write_full_backup(app_handle: &tauri::AppHandle, dest: &Path) -> Result<usize, String> {
+    let current_settings = settings::current_settings(app_handle)?;
+    let settings_data = serde_json::to_vec_pretty(&current_settings).map_err(|e| e.to_string())?;
+    let mut files = vec![("settings.json".to_string(), settings_data)];
+
+    let skip = |relative: &str| {
+        let top = top_level(relative.strip_prefix("coredata/").unwrap_or(relative));
+        BACKUP_EXCLUDED.contains(&top) || is_index_file(top)
+    };
+    collect_dir(&history::coredata_dir()?, "coredata", &skip, &mut files)?;
+    files.push((format!("coredata/{}", HISTORY_INDEX_ENTRY), history_index_bytes()?));
+
+    let checksums: serde_json::Map<String, serde_json::Value> = files
+        .iter()
+        .map(|(name, data)| (name.clone(), serde_json::Value::String(audit::sha256_hex(data))))
+        .collect();
+    let manifest = serde_json::json!({
+        "type": "backup",
+        "formatVersion": BACKUP_FORMAT_VERSION,
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "createdAt": chrono::Utc::now().to_rfc3339(),
+        "checksumAlgorithm": "sha256",
+        "checksums": checksums
+    });
+
+    let file = std::fs::File::create(dest).map_err(|e| format!("创建备份文件失败: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    add_bytes(&mut zip, BACKUP_MANIFEST, manifest.to_string().as_bytes())?;
+    for (name, data) in &files {
+        add_bytes(&mut zip, name, data)?;
+    }
+    zip.finish().map_err(|e| format!("完成备份写入失败: {}", e))?;
+
+    // 不计设置文件
+    Ok(files.len() - 1)
+}
+
+// 按清单中的校验和检查备份条目：内容不符、清单外的条目以及缺少的条目都视为备份已损坏
+fn verify_checksums(manifest: &serde_json::Value, entries: &[(String, Vec<u8>)]) -> Result<(), AppError> {
+    let checksums = manifest
+        .get("checksums")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| AppError::InvalidInput("备份清单缺少校验和".to_string()))?;
+
+    for (name, data) in entries {
+        if name == BACKUP_MANIFEST {
+            continue;
+        }
+        let expected = checksums
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AppError::InvalidInput(format!("备份中的条目 {} 不在清单中", name)))?;
+        if audit::sha256_hex(data) != expected {
+            return Err(AppError::InvalidInput(format!("备份已损坏：条目 {} 的校验和不匹配", name)));
+        }
+    }
+
+    if let Some(missing) = checksums.keys().find(|name| !entries.iter().any(|(entry, _)| entry == *name)) {
+        return Err(AppError::InvalidInput(format!("备份不完整：缺少条目 {}", missing)));
+    }
+    Ok(())
+}
+
+// 完整备份：把 coredata 目录（含名单与抽取池）与设置文件打包为一个带校验和的归档，返回打包的数据文件数
+#[tauri::command]
+pub async fn create_backup(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    target_path: String,
+) -> Result<usize, AppError> {
+    log::info!("完整备份数据: {}", target_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+    // 批量导入中缓存的索引先落盘，保证备份内容完整
+    history::finish_bulk_import()?;
+
+    let count = write_full_backup(&app_handle, Path::new(&target_path)).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+
+    export::record_export(&target_path, "backup", Some(count), None);
+    log::info!("完整备份完成: {}，包含 {} 个数据文件", target_path, count);
+    Ok(count)
+}
+
+// 与 create_backup 相同，保留给旧版前端
+#[tauri::command]
+pub async fn backup_data(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    out_path: String,
+) -> Result<usize, AppError> {
+    create_backup(app_handle, index_lock, out_path).await
+}
+
+// 从完整备份恢复数据
+//
+// 先完整校验归档（清单、校验和、history.json、设置文件），再把现有数据备份到 coredata/backups/ 下的 .bak 归档，
+// 最后清空 coredata（保留实例锁与备份目录）并解包。恢复出错时可用该 .bak 归档再次恢复。
+// 版本 1 的备份没有校验和，只做结构校验。
+#[tauri::command]
+pub async fn restore_backup(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    archive_path: String,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("从完整备份恢复数据: {}", archive_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let mut archive = open_archive(&archive_path)?;
+    let entries = read_all_entries(&mut archive)?;
+
+    // 校验通过前不修改任何数据
+    let manifest: serde_json::Value = entries
+        .iter()
+        .find(|(name, _)| name == BACKUP_MANIFEST)
+        .and_then(|(_, data)| serde_json::from_slice(data).ok())
+        .ok_or("备份缺少有效的 backup.json 清单")?;
+    if manifest.get("type").and_then(|v| v.as_str()) != Some("backup") {
+        return Err(AppError::InvalidInput("该文件不是完整备份".to_string()));
+    }
+    let format_version = manifest.get("formatVersion").and_then(|v| v.as_u64()).unwrap_or(0);
+    if format_version == 0 || format_version > BACKUP_FORMAT_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "不支持的备份格式版本 {}（由 {} 版本创建）",
+            format_version,
+            manifest.get("appVersion").and_then(|v| v.as_str()).unwrap_or("未知")
+        )));
+    }
+    let verified = format_version >= 2;
+    if verified {
+        verify_checksums(&manifest, &entries).map_err(|e| {
+            log::error!("{}", e);
+            e
+        })?;
+    }
+
+    let mut incoming_settings = None;
+    let mut data_files = Vec::new();
+    for (name, data) in entries {
+        if name == BACKUP_MANIFEST {
+            continue;
+        }
+        if name == "settings.json" {
+            let value: serde_json::Value = serde_json::from_slice(&data)
+                .map_err(|e| format!("备份中的设置文件无效: {}", e))?;
+            settings::validate_settings(&value)?;
+            incoming_settings = Some(value);
+            continue;
+        }
+        match name.strip_prefix("coredata/") {
+            Some(relative) if !BACKUP_EXCLUDED.contains(&top_level(relative)) => {
+                data_files.push((relative.to_string(), data));
+            }
+            _ => return Err(AppError::InvalidInput(format!("备份中包含不允许的条目: {}", name))),
+        }
+    }
+
+    let index_pos = data_files
+        .iter()
+        .position(|(relative, _)| relative == HISTORY_INDEX_ENTRY)
+        .ok_or("备份中缺少 history.json，已取消恢复")?;
+    let history_index = parse_history_index(&data_files.remove(index_pos).1)?;
+    data_files.retain(|(relative, _)| !is_index_file(top_level(relative)));
+
+    let _index_guard = index_lock.lock();
+    history::finish_bulk_import()?;
+
+    // 先备份现有数据
+    let coredata_dir = history::coredata_dir()?;
+    let backups_dir = coredata_dir.join("backups");
+    std::fs::create_dir_all(&backups_dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    let pre_restore = backups_dir.join(format!(
+        "before-restore-{}.bak",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    write_full_backup(&app_handle, &pre_restore).map_err(|e| {
+        let error = format!("备份现有数据失败，已取消恢复: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    log::info!("现有数据已备份到: {:?}", pre_restore);
+
+    // 清空 coredata（保留实例锁与备份目录）
+    let existing = std::fs::read_dir(&coredata_dir).map_err(|e| format!("读取目录失败 {:?}: {}", coredata_dir, e))?;
+    for entry in existing.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if BACKUP_EXCLUDED.contains(&name.as_str()) {
+            continue;
+        }
+        let path = entry.path();
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        result.map_err(|e| format!("清理现有数据失败 {:?}: {}", path, e))?;
+    }
+
+    let mut restored = 0;
+    for (relative, data) in data_files {
+        let target = coredata_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        storage::write_atomic(&target, &data).map_err(|e| format!("写入文件失败 {:?}: {}", target, e))?;
+        restored += 1;
+    }
+    history::save_history_index(&history_index)?;
+    restored += 1;
+
+    let settings_restored = incoming_settings.is_some();
+    if let Some(incoming) = incoming_settings {
+        let writer = app_handle.state::<SettingsWriter>();
+        writer.schedule(settings::settings_path(&app_handle)?, incoming);
+        if let Some(written) = writer.flush()? {
+            settings::emit_settings_changed(&app_handle, &written);
+        }
+    }
+
+    log::info!("完整备份恢复完成: 恢复 {} 个文件", restored);
+    Ok(serde_json::json!({
+        "filesRestored": restored,
+        "settingsRestored": settings_restored,
+        "sourceVersion": manifest.get("appVersion"),
+        "formatVersion": format_version,
+        "checksumVerified": verified,
+        "preRestoreBackup": pre_restore
+    }))
+}
+
+// 与 restore_backup 相同，保留给旧版前端
+#[tauri::command]
+pub async fn restore_data(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    archive_path: String,
+) -> Result<serde_json::Value, AppError> {
+    restore_backup(app_handle, index_lock, archive_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_excludes_machine_bound_files() {
+        for top in ["app_lock.json", "encryption.json", "ledger.jsonl", "Ledger.JSONL", "logs", ".lock"] {
+            assert!(is_profile_excluded(top), "{}", top);
+        }
+        for top in ["groups", "history", "pools"] {
+            assert!(!is_profile_excluded(top), "{}", top);
+        }
+    }
+}
@@ -0,0 +1,1132 @@
+// 抽奖引擎
+//
+// 所有抽取都使用以 64 位种子初始化的 ChaCha20 生成器（密码学安全的伪随机数生成器）：未指定种子时
+// 从系统熵源（OsRng）生成，并总是在结果中返回实际使用的种子，相同的参与者列表与种子可以复现完全相同的结果。
+// 抽取前会去除空白姓名、截断超长姓名，排除禁抽名单中的人，并按中奖次数上限规则排除已达上限的人。
+//
+// 抽取过程同时以事件推送给前端（见 DrawStream）：开始抽样前发送带参与者池的 draw://started，每个中奖者按抽中顺序
+// 发送一条 draw://candidate，最后发送带完整结果的 draw://finished，前端的滚动动画据此展示真实的抽取过程。
+// 三种事件与命令返回值都带有同一个 drawId，用于对应同一次抽取。
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::{audit, history, roster, rules, settings};
+
+// 随机数算法标识，随抽取结果一起保存以便复现
+pub const RNG_ALGORITHM: &str = "chacha20";
+
+// 种子限制在 53 位以内，保证前端 JavaScript 的 number 可以精确表示
+const SEED_MASK: u64 = (1 << 53) - 1;
+
+// 从系统熵源生成新的随机种子
+pub fn new_seed() -> u64 {
+    rand::rngs::OsRng.next_u64() & SEED_MASK
+}
+
+// 根据种子创建生成器
+pub fn rng_from_seed(seed: u64) -> ChaCha20Rng {
+    ChaCha20Rng::seed_from_u64(seed)
+}
+
+// 直接使用系统熵源（OsRng）时的算法标识；这种模式没有种子，结果无法复现
+pub const RNG_OS: &str = "os";
+
+// 抽取使用的生成器：默认是以种子初始化的 ChaCha20，设置 rngMode 为 "os" 时直接使用 OsRng
+pub enum DrawRng {
+    Seeded(Box<ChaCha20Rng>),
+    Os(rand::rngs::OsRng),
+}
+
+impl RngCore for DrawRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            DrawRng::Seeded(rng) => rng.next_u32(),
+            DrawRng::Os(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            DrawRng::Seeded(rng) => rng.next_u64(),
+            DrawRng::Os(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            DrawRng::Seeded(rng) => rng.fill_bytes(dest),
+            DrawRng::Os(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            DrawRng::Seeded(rng) => rng.try_fill_bytes(dest),
+            DrawRng::Os(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+// 按设置 rngMode 创建本次抽取的生成器，返回生成器、实际使用的种子（OsRng 模式下为 None）与算法标识
+//
+// 调用方指定了种子时总是使用 ChaCha20，以便复现。
+pub fn draw_rng(app_handle: &tauri::AppHandle, seed: Option<u64>) -> Result<(DrawRng, Option<u64>, &'static str), String> {
+    Ok(settings_rng(&settings::current_settings(app_handle)?, seed))
+}
+
+// 按给定设置的 rngMode 创建生成器，见 draw_rng
+fn settings_rng(current_settings: &serde_json::Value, seed: Option<u64>) -> (DrawRng, Option<u64>, &'static str) {
+    let os_mode = current_settings.get("rngMode").and_then(|v| v.as_str()) == Some(RNG_OS);
+    match seed {
+        None if os_mode => (DrawRng::Os(rand::rngs::OsRng), None, RNG_OS),
+        _ => {
+            let seed = seed.unwrap_or_else(new_seed);
+            (DrawRng::Seeded(Box::new(rng_from_seed(seed))), Some(seed), RNG_ALGORITHM)
+        }
+    }
+}
+
+// 抽取前的参与者池
+pub struct DrawPool {
+    pub participants: Vec<String>,
+    pub capped: Vec<rules::CappedParticipant>,
+}
+
+// 整理参与者：去除空白姓名、截断超长姓名，排除禁抽名单中的人与重名，并排除已达到中奖次数上限的人
+pub fn prepare_pool(app_handle: &tauri::AppHandle, participants: Vec<String>) -> Result<DrawPool, String> {
+    let current_settings = settings::current_settings(app_handle)?;
+    let max_length = rules::max_name_length(&current_settings);
+    let participants: Vec<String> = participants
+        .into_iter()
+        .map(|p| rules::truncate_name(p.trim(), max_length))
+        .filter(|p| !p.is_empty())
+        .collect();
+    let participants = rules::apply_denylist_and_unique(participants, &current_settings);
+
+    let caps = rules::WinCaps::from_settings(&current_settings);
+    let (participants, capped) = rules::apply_win_caps(&participants, caps)?;
+    Ok(DrawPool { participants, capped })
+}
+
+// 不放回地抽取 count 人（部分 Fisher-Yates 洗牌），结果按抽中顺序排列
+pub fn sample_without_replacement(pool: &[String], count: usize, rng: &mut impl Rng) -> Vec<String> {
+    let mut items = pool.to_vec();
+    let count = count.min(items.len());
+    for i in 0..count {
+        let j = rng.gen_range(i..items.len());
+        items.swap(i, j);
+    }
+    items.truncate(count);
+    items
+}
+
+// 不放回抽样算法，随抽取结果保存到任务的 method 字段，复现时需使用相同算法
+pub const METHOD_FISHER_YATES: &str = "fisher-yates";
+pub const METHOD_RESERVOIR: &str = "reservoir";
+
+// 蓄水池抽样（Algorithm R）：单次遍历参与者，只保留 count 个位置，适合超大参与者池
+//
+// 蓄水池中的顺序与抽中先后无关，最后再用同一生成器打乱一次作为抽中顺序。
+pub fn sample_reservoir(pool: &[String], count: usize, rng: &mut impl Rng) -> Vec<String> {
+    let count = count.min(pool.len());
+    let mut reservoir: Vec<String> = pool[..count].to_vec();
+    for (i, item) in pool.iter().enumerate().skip(count) {
+        let j = rng.gen_range(0..=i);
+        if j < count {
+            reservoir[j] = item.clone();
+        }
+    }
+    sample_without_replacement(&reservoir, count, rng)
+}
+
+// 解析抽样算法参数，未指定时使用 Fisher-Yates
+pub fn parse_method(method: Option<&str>) -> Result<&'static str, AppError> {
+    match method.map(|m| m.trim().to_lowercase()).as_deref() {
+        None | Some("") | Some(METHOD_FISHER_YATES) => Ok(METHOD_FISHER_YATES),
+        Some(METHOD_RESERVOIR) => Ok(METHOD_RESERVOIR),
+        Some(other) => Err(AppError::InvalidInput(format!("不支持的抽样算法: {}", other))),
+    }
+}
+
+// 按指定算法不放回抽取
+pub fn sample_with_method(method: &str, pool: &[String], count: usize, rng: &mut impl Rng) -> Vec<String> {
+    if method == METHOD_RESERVOIR {
+        sample_reservoir(pool, count, rng)
+    } else {
+        sample_without_replacement(pool, count, rng)
+    }
+}
+
+// 校验抽取人数
+pub fn check_count(pool_size: usize, count: usize) -> Result<(), AppError> {
+    if count == 0 {
+        return Err(AppError::InvalidInput("抽取人数必须大于0".to_string()));
+    }
+    if count > pool_size {
+        return Err(AppError::InvalidInput(format!("抽取人数 {} 超过可参与人数 {}", count, pool_size)));
+    }
+    Ok(())
+}
+
+// 从 n 人中有序抽取 k 人的结果数（排列数 n!/(n-k)!）的 log2
+pub fn permutation_entropy_bits(n: usize, k: usize) -> f64 {
+    (0..k.min(n)).map(|i| ((n - i) as f64).log2()).sum()
+}
+
+// 从 n 人中无序抽取 k 人的结果数（组合数 C(n,k)）的 log2
+pub fn combination_entropy_bits(n: usize, k: usize) -> f64 {
+    let k = k.min(n);
+    let k_factorial: f64 = (2..=k).map(|i| (i as f64).log2()).sum();
+    permutation_entropy_bits(n, k) - k_factorial
+}
+
+// 抽取过程事件名
+pub const DRAW_STARTED_EVENT: &str = "draw://started";
+pub const DRAW_CANDIDATE_EVENT: &str = "draw://candidate";
+pub const DRAW_FINISHED_EVENT: &str = "draw://finished";
+
+// 本次运行中的抽取序号，与时间一起组成 drawId
+static DRAW_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// 一次抽取的事件推送：start 发送 started，candidates 逐个发送中奖者，finish 在结果中写入 drawId 并发送 finished
+pub struct DrawStream<'a> {
+    app_handle: &'a tauri::AppHandle,
+    draw_id: String,
+    next_index: usize,
+}
+
+impl<'a> DrawStream<'a> {
+    // kind 为抽取方式（winners、names、seed、entropy、redraw、tiered、lottery、without-replacement），pool 为整理后的参与者池
+    pub fn start(app_handle: &'a tauri::AppHandle, kind: &str, pool: &[String], count: usize) -> Self {
+        let draw_id = format!(
+            "draw-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            DRAW_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        );
+        let stream = DrawStream { app_handle, draw_id, next_index: 0 };
+        stream.emit(
+            DRAW_STARTED_EVENT,
+            serde_json::json!({
+                "drawId": stream.draw_id,
+                "kind": kind,
+                "pool": pool,
+                "poolSize": pool.len(),
+                "count": count
+            }),
+        );
+        stream
+    }
+
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Err(e) = self.app_handle.emit(event, payload) {
+            log::warn!("发送 {} 事件失败: {}", event, e);
+        }
+    }
+
+    // 按抽中顺序发送中奖者，index 在整次抽取中连续编号；tier 为分奖项抽取时所属的奖项
+    pub fn candidates<T: serde::Serialize>(&mut self, winners: &[T], tier: Option<&str>) {
+        for winner in winners {
+            self.emit(
+                DRAW_CANDIDATE_EVENT,
+                serde_json::json!({
+                    "drawId": self.draw_id,
+                    "index": self.next_index,
+                    "candidate": winner,
+                    "tier": tier
+                }),
+            );
+            self.next_index += 1;
+        }
+    }
+
+    pub fn finish(self, result: &mut serde_json::Value) {
+        result["drawId"] = serde_json::json!(self.draw_id);
+        self.emit(DRAW_FINISHED_EVENT, result.clone());
+    }
+}
+
+// 抽取中奖者
+//
+// count 等于可参与人数时不是错误：所有人都会中奖，结果只是随机排列顺序，此时 allSelected 为 true，
+// 前端可据此标注为"全员入选"。count 超过可参与人数时仍然报错。
+//
+// method 可选 "fisher-yates"（默认）或 "reservoir"。同一算法下相同种子结果相同，
+// 但换用另一种算法后同一种子会得到不同结果，因此返回值中的 method 需要与种子一起保存到任务中。
+#[tauri::command]
+pub async fn draw_winners(
+    app_handle: tauri::AppHandle,
+    participants: Vec<String>,
+    count: usize,
+    seed: Option<u64>,
+    method: Option<String>,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("抽取中奖者: {} 人中抽取 {} 人", participants.len(), count);
+    crate::app_lock::ensure_unlocked()?;
+
+    let method = parse_method(method.as_deref())?;
+    let pool = prepare_pool(&app_handle, participants)?;
+    let n = pool.participants.len();
+    check_count(n, count)?;
+
+    let all_selected = count == n;
+    if all_selected {
+        log::info!("抽取人数等于可参与人数，全员入选，仅打乱顺序");
+    }
+
+    let (mut rng, seed, rng_name) = draw_rng(&app_handle, seed)?;
+    let mut stream = DrawStream::start(&app_handle, "winners", &pool.participants, count);
+    let winners = sample_with_method(method, &pool.participants, count, &mut rng);
+    stream.candidates(&winners, None);
+
+    let mut result = serde_json::json!({
+        "winners": winners,
+        "seed": seed,
+        "rng": rng_name,
+        "method": method,
+        "poolSize": n,
+        "count": count,
+        "allSelected": all_selected,
+        "capped": pool.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 从名单中抽取 count 人，options 为 { "seed"?: 种子, "method"?: "fisher-yates" | "reservoir" }
+//
+// 与 draw_winners 使用同一套参与者规则与抽样算法，返回结构化结果，每次抽取的结果与种子都写入日志以便事后核对。
+#[tauri::command]
+pub async fn draw_names(
+    app_handle: tauri::AppHandle,
+    pool: Vec<String>,
+    count: usize,
+    options: Option<serde_json::Value>,
+) -> Result<serde_json::Value, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let options = options.unwrap_or(serde_json::Value::Null);
+    if !options.is_null() && !options.is_object() {
+        return Err(AppError::InvalidInput("抽取选项必须是 JSON 对象".to_string()));
+    }
+    let seed = match options.get("seed") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(value) => Some(value.as_u64().ok_or_else(|| AppError::InvalidInput(format!("无效的种子: {}", value)))?),
+    };
+    let method = parse_method(options.get("method").and_then(|v| v.as_str()))?;
+
+    let prepared = prepare_pool(&app_handle, pool)?;
+    let n = prepared.participants.len();
+    check_count(n, count)?;
+
+    let (mut rng, seed, rng_name) = draw_rng(&app_handle, seed)?;
+    let mut stream = DrawStream::start(&app_handle, "names", &prepared.participants, count);
+    let winners = sample_with_method(method, &prepared.participants, count, &mut rng);
+    stream.candidates(&winners, None);
+    let drawn_at = chrono::Utc::now().to_rfc3339();
+    log::info!(
+        "抽取完成: {} 人中抽取 {} 人，种子 {:?}，生成器 {}，算法 {}，中奖者: {:?}",
+        n,
+        count,
+        seed,
+        rng_name,
+        method,
+        winners
+    );
+
+    let mut result = serde_json::json!({
+        "winners": winners,
+        "seed": seed,
+        "rng": rng_name,
+        "method": method,
+        "poolSize": n,
+        "count": count,
+        "drawnAt": drawn_at,
+        "capped": prepared.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 可复现抽取的算法版本：ChaCha20 + 部分 Fisher-Yates，算法或姓名整理方式变化时递增
+pub const DRAW_ALGORITHM_VERSION: u32 = 1;
+
+// 以指定种子从名单中抽取 count 人，并把种子、算法版本、名单哈希与结果追加到 coredata/draw_audit.jsonl
+//
+// 为保证事后可以复现，这里只去除首尾空白与空白姓名，不应用禁抽名单与中奖次数上限（它们会随历史变化）。
+// 相同的种子、名单（顺序相同）与人数总是得到相同的结果，inputHash 可用于核对争议时提供的名单是否一致。
+#[tauri::command]
+pub async fn draw_with_seed(
+    app_handle: tauri::AppHandle,
+    seed: u64,
+    pool: Vec<String>,
+    count: usize,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("以种子 {} 抽取: {} 人中抽取 {} 人", seed, pool.len(), count);
+    crate::app_lock::ensure_unlocked()?;
+
+    let pool: Vec<String> = pool
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    check_count(pool.len(), count)?;
+
+    let mut stream = DrawStream::start(&app_handle, "seed", &pool, count);
+    let winners = sample_without_replacement(&pool, count, &mut rng_from_seed(seed));
+    stream.candidates(&winners, None);
+    let mut record = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "seed": seed,
+        "rng": RNG_ALGORITHM,
+        "method": METHOD_FISHER_YATES,
+        "algorithmVersion": DRAW_ALGORITHM_VERSION,
+        "inputHash": audit::hash_name_list(&pool),
+        "poolSize": pool.len(),
+        "count": count,
+        "winners": winners,
+        "winnersHash": audit::hash_name_list(&winners)
+    });
+    audit::append_draw_record(&record).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+
+    log::info!("以种子 {} 抽取完成，中奖者: {:?}", seed, winners);
+    stream.finish(&mut record);
+    Ok(record)
+}
+
+// 抽取并报告本次抽取的理论熵（比特）
+//
+// 中奖结果有先后顺序，entropyBits 按排列数计算；combinationEntropyBits 为只关心中奖名单时的组合数熵。
+// 使用对数逐项累加，n、k 很大时也不会溢出。
+#[tauri::command]
+pub async fn draw_with_entropy(
+    app_handle: tauri::AppHandle,
+    participants: Vec<String>,
+    count: usize,
+    seed: Option<u64>,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("抽取并计算熵: {} 人中抽取 {} 人", participants.len(), count);
+    crate::app_lock::ensure_unlocked()?;
+
+    let pool = prepare_pool(&app_handle, participants)?;
+    let n = pool.participants.len();
+    check_count(n, count)?;
+
+    let (mut rng, seed, rng_name) = draw_rng(&app_handle, seed)?;
+    let mut stream = DrawStream::start(&app_handle, "entropy", &pool.participants, count);
+    let winners = sample_without_replacement(&pool.participants, count, &mut rng);
+    stream.candidates(&winners, None);
+
+    let mut result = serde_json::json!({
+        "winners": winners,
+        "seed": seed,
+        "rng": rng_name,
+        "poolSize": n,
+        "count": count,
+        "entropyBits": permutation_entropy_bits(n, count),
+        "combinationEntropyBits": combination_entropy_bits(n, count),
+        "capped": pool.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 重抽某一个中奖位置（例如中奖者放弃时），其余中奖者保持不变
+//
+// 新人选从参与者中排除全部现有中奖者（包括被替换、已放弃的那一位）后抽取。
+#[tauri::command]
+pub async fn redraw_slot(
+    app_handle: tauri::AppHandle,
+    participants: Vec<String>,
+    current_winners: Vec<String>,
+    replace_index: usize,
+    seed: Option<u64>,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("重抽第 {} 个中奖位置", replace_index + 1);
+    crate::app_lock::ensure_unlocked()?;
+
+    if replace_index >= current_winners.len() {
+        return Err(AppError::InvalidInput(format!(
+            "重抽位置 {} 超出范围，当前共 {} 名中奖者",
+            replace_index + 1,
+            current_winners.len()
+        )));
+    }
+
+    let pool = prepare_pool(&app_handle, participants)?;
+    let excluded: std::collections::HashSet<String> = current_winners
+        .iter()
+        .map(|w| history::normalize_name(w))
+        .collect();
+    let candidates: Vec<String> = pool
+        .participants
+        .into_iter()
+        .filter(|p| !excluded.contains(&history::normalize_name(p)))
+        .collect();
+
+    if candidates.is_empty() {
+        return Err(AppError::InvalidInput("没有可重抽的候选人".to_string()));
+    }
+
+    let (mut rng, seed, rng_name) = draw_rng(&app_handle, seed)?;
+    let mut stream = DrawStream::start(&app_handle, "redraw", &candidates, 1);
+    let replacement = sample_without_replacement(&candidates, 1, &mut rng)
+        .pop()
+        .ok_or("没有可重抽的候选人")?;
+    stream.candidates(std::slice::from_ref(&replacement), None);
+
+    let mut winners = current_winners;
+    let replaced = std::mem::replace(&mut winners[replace_index], replacement.clone());
+    log::info!("第 {} 位中奖者 {} 由 {} 替换", replace_index + 1, replaced, replacement);
+
+    let mut result = serde_json::json!({
+        "winners": winners,
+        "replaced": replaced,
+        "replacement": replacement,
+        "replaceIndex": replace_index,
+        "seed": seed,
+        "rng": rng_name,
+        "candidateCount": candidates.len(),
+        "capped": pool.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 一个奖项等级，例如 { "name": "一等奖", "count": 1 }
+struct PrizeTier {
+    name: String,
+    count: usize,
+}
+
+// 解析奖项等级参数：名称不能为空或重复，人数必须大于0
+fn parse_tiers(tiers: &[serde_json::Value]) -> Result<Vec<PrizeTier>, AppError> {
+    if tiers.is_empty() {
+        return Err(AppError::InvalidInput("至少需要一个奖项".to_string()));
+    }
+
+    let mut parsed: Vec<PrizeTier> = Vec::with_capacity(tiers.len());
+    for (i, tier) in tiers.iter().enumerate() {
+        let name = tier
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| AppError::InvalidInput(format!("第 {} 个奖项缺少名称", i + 1)))?;
+        let count = tier
+            .get("count")
+            .and_then(|v| v.as_u64())
+            .filter(|&c| c > 0)
+            .ok_or_else(|| AppError::InvalidInput(format!("奖项 {} 的人数必须是大于0的整数", name)))?;
+        if parsed.iter().any(|t| t.name == name) {
+            return Err(AppError::InvalidInput(format!("奖项名称重复: {}", name)));
+        }
+        parsed.push(PrizeTier { name, count: count as usize });
+    }
+    Ok(parsed)
+}
+
+// 分奖项抽取：按奖项顺序依次不放回抽取，同一人只能获得一个奖项
+//
+// 返回的 tiers 即保存任务时的 tiers 字段（[{ name, count, winners }]），results 为按奖项顺序展开的全部中奖者，
+// winnersByTier 为奖项名称到中奖者的映射。
+#[tauri::command]
+pub async fn draw_tiered(
+    app_handle: tauri::AppHandle,
+    participants: Vec<String>,
+    tiers: Vec<serde_json::Value>,
+    seed: Option<u64>,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("分奖项抽取: {} 人, {} 个奖项", participants.len(), tiers.len());
+    crate::app_lock::ensure_unlocked()?;
+
+    let tiers = parse_tiers(&tiers)?;
+    let total: usize = tiers.iter().map(|t| t.count).sum();
+
+    let pool = prepare_pool(&app_handle, participants)?;
+    let n = pool.participants.len();
+    if total > n {
+        return Err(AppError::InvalidInput(format!("各奖项人数合计 {} 超过可参与人数 {}", total, n)));
+    }
+
+    // 一次抽出全部中奖者再按奖项顺序切分，与逐个奖项依次抽取等价
+    let (mut rng, seed, rng_name) = draw_rng(&app_handle, seed)?;
+    let mut stream = DrawStream::start(&app_handle, "tiered", &pool.participants, total);
+    let winners = sample_without_replacement(&pool.participants, total, &mut rng);
+
+    let mut remaining = winners.as_slice();
+    let mut tier_results = Vec::with_capacity(tiers.len());
+    let mut by_tier = serde_json::Map::new();
+    for tier in &tiers {
+        let (tier_winners, rest) = remaining.split_at(tier.count);
+        remaining = rest;
+        log::info!("{}: {:?}", tier.name, tier_winners);
+        stream.candidates(tier_winners, Some(&tier.name));
+        tier_results.push(serde_json::json!({
+            "name": tier.name,
+            "count": tier.count,
+            "winners": tier_winners
+        }));
+        by_tier.insert(tier.name.clone(), serde_json::json!(tier_winners));
+    }
+
+    let mut result = serde_json::json!({
+        "tiers": tier_results,
+        "winnersByTier": by_tier,
+        "results": winners,
+        "seed": seed,
+        "rng": rng_name,
+        "poolSize": n,
+        "capped": pool.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 加权抽奖的候选项：{ "id", "name"?, "weight"? }，未指定权重时为 1
+struct Candidate {
+    name: String,
+    weight: f64,
+    raw: serde_json::Value,
+}
+
+// 解析候选项：必须有 id，权重必须是大于 0 的有限数
+fn parse_candidates(candidates: Vec<serde_json::Value>) -> Result<Vec<Candidate>, AppError> {
+    candidates
+        .into_iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            let id = match raw.get("id") {
+                Some(serde_json::Value::String(s)) if !s.trim().is_empty() => s.trim().to_string(),
+                Some(serde_json::Value::Number(n)) => n.to_string(),
+                _ => return Err(AppError::InvalidInput(format!("第 {} 个候选项缺少 id", i + 1))),
+            };
+            let name = raw
+                .get("name")
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| id.clone());
+            let weight = match raw.get("weight") {
+                None | Some(serde_json::Value::Null) => 1.0,
+                Some(value) => value
+                    .as_f64()
+                    .ok_or_else(|| AppError::InvalidInput(format!("候选项 {} 的权重不是数字", id)))?,
+            };
+            if !weight.is_finite() || weight <= 0.0 {
+                return Err(AppError::InvalidInput(format!("候选项 {} 的权重无效: {}，权重必须大于0", id, weight)));
+            }
+            Ok(Candidate { name, weight, raw })
+        })
+        .collect()
+}
+
+// 按权重抽取一个下标
+fn pick_weighted(weights: &[f64], rng: &mut impl Rng) -> usize {
+    let total: f64 = weights.iter().sum();
+    let mut target = rng.gen_range(0.0..total);
+    for (i, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return i;
+        }
+        target -= weight;
+    }
+    // 浮点误差兜底
+    weights.len() - 1
+}
+
+// 加权抽取：不允许重复时每抽中一人就从候选中移除，允许重复时每次都从全部候选中抽取
+fn sample_weighted(candidates: &[Candidate], count: usize, allow_repeat: bool, rng: &mut impl Rng) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+    let mut weights: Vec<f64> = candidates.iter().map(|c| c.weight).collect();
+    let mut picked = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let i = pick_weighted(&weights, rng);
+        picked.push(remaining[i]);
+        if !allow_repeat {
+            remaining.remove(i);
+            weights.remove(i);
+        }
+    }
+    picked
+}
+
+// 一次加权抽奖的结果：参与抽取的候选、按抽中顺序排列的下标、因达到上限被排除的人以及实际使用的种子和算法
+struct LotteryDraw {
+    pool: Vec<Candidate>,
+    picked: Vec<usize>,
+    capped: Vec<rules::CappedParticipant>,
+    seed: Option<u64>,
+    rng_name: &'static str,
+}
+
+// 按设置执行加权抽奖（未指定种子时按 rngMode 选择生成器）
+//
+// apply_rules 为 true 时按候选项的 name（缺省为 id）排除禁抽名单中的人与已达中奖次数上限的人；
+// 复现抽奖时为 false，禁抽名单与上限会随设置和历史变化，应用它们会让相同的种子得到不同的结果。
+fn lottery_draw(
+    current_settings: &serde_json::Value,
+    candidates: Vec<serde_json::Value>,
+    count: usize,
+    allow_repeat: bool,
+    seed: Option<u64>,
+    apply_rules: bool,
+) -> Result<LotteryDraw, AppError> {
+    let candidates = parse_candidates(candidates)?;
+
+    let (pool, capped) = if apply_rules {
+        let denied: std::collections::HashSet<String> = rules::denylist(current_settings)
+            .iter()
+            .map(|name| history::normalize_name(name))
+            .collect();
+        let names: Vec<String> = candidates
+            .iter()
+            .filter(|c| !denied.contains(&history::normalize_name(&c.name)))
+            .map(|c| c.name.clone())
+            .collect();
+        let (eligible, capped) = rules::apply_win_caps(&names, rules::WinCaps::from_settings(current_settings))?;
+        let eligible: std::collections::HashSet<String> = eligible.iter().map(|n| history::normalize_name(n)).collect();
+        let pool: Vec<Candidate> = candidates
+            .into_iter()
+            .filter(|c| eligible.contains(&history::normalize_name(&c.name)))
+            .collect();
+        (pool, capped)
+    } else {
+        (candidates, vec![])
+    };
+
+    if allow_repeat {
+        if count == 0 {
+            return Err(AppError::InvalidInput("抽取人数必须大于0".to_string()));
+        }
+        if pool.is_empty() {
+            return Err(AppError::InvalidInput("没有可参与抽奖的候选项".to_string()));
+        }
+    } else {
+        check_count(pool.len(), count)?;
+    }
+
+    let (mut rng, seed, rng_name) = settings_rng(current_settings, seed);
+    let picked = sample_weighted(&pool, count, allow_repeat, &mut rng);
+    Ok(LotteryDraw { pool, picked, capped, seed, rng_name })
+}
+
+// 执行加权抽奖并推送抽取事件，apply_rules 见 lottery_draw
+fn run_lottery(
+    app_handle: &tauri::AppHandle,
+    candidates: Vec<serde_json::Value>,
+    count: usize,
+    allow_repeat: bool,
+    seed: Option<u64>,
+    apply_rules: bool,
+) -> Result<serde_json::Value, AppError> {
+    let current_settings = settings::current_settings(app_handle)?;
+    let draw = lottery_draw(&current_settings, candidates, count, allow_repeat, seed, apply_rules)?;
+
+    let pool_names: Vec<String> = draw.pool.iter().map(|c| c.name.clone()).collect();
+    let mut stream = DrawStream::start(app_handle, "lottery", &pool_names, count);
+    let winners: Vec<&serde_json::Value> = draw.picked.iter().map(|&i| &draw.pool[i].raw).collect();
+    stream.candidates(&winners, None);
+
+    let mut result = serde_json::json!({
+        "winners": winners,
+        "seed": draw.seed,
+        "rng": draw.rng_name,
+        "poolSize": draw.pool.len(),
+        "count": count,
+        "allowRepeat": allow_repeat,
+        "capped": draw.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 加权抽奖
+//
+// candidates 为 [{ "id", "name"?, "weight"? }]，权重为 0、负数或非数字时直接报错，不会悄悄影响概率。
+// 返回的 seed 与 rng 应随任务一起保存（save_history_task），用于日后复现与审计。
+#[tauri::command]
+pub async fn draw_lottery(
+    app_handle: tauri::AppHandle,
+    candidates: Vec<serde_json::Value>,
+    count: usize,
+    allow_repeat: bool,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("加权抽奖: {} 个候选项中抽取 {} 个 (允许重复: {})", candidates.len(), count, allow_repeat);
+    crate::app_lock::ensure_unlocked()?;
+    run_lottery(&app_handle, candidates, count, allow_repeat, None, true)
+}
+
+// 按 (姓名, 权重) 对加权不放回抽取，例如给被抽中次数少的学生更高的权重
+//
+// 与 draw_lottery 使用同一套累积权重抽样：每抽中一人就从候选中移除，剩余候选按权重重新分配概率。
+// 返回的 winners 为 [{ "id", "name", "weight" }]（id 即姓名）；指定 seed 时结果可复现。
+#[tauri::command]
+pub async fn draw_weighted(
+    app_handle: tauri::AppHandle,
+    entries: Vec<(String, f64)>,
+    count: usize,
+    seed: Option<u64>,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("按权重抽取: {} 人中抽取 {} 人", entries.len(), count);
+    crate::app_lock::ensure_unlocked()?;
+
+    let candidates = entries
+        .into_iter()
+        .map(|(name, weight)| serde_json::json!({ "id": name, "name": name, "weight": weight }))
+        .collect();
+    run_lottery(&app_handle, candidates, count, false, seed, true)
+}
+
+// 以指定种子复现加权抽奖：相同的候选项列表（顺序相同）与种子得到完全相同的中奖顺序
+//
+// 与 draw_with_seed 相同，不应用禁抽名单与中奖次数上限，设置或历史变化后复现的结果也不变。
+#[tauri::command]
+pub async fn draw_lottery_with_seed(
+    app_handle: tauri::AppHandle,
+    seed: u64,
+    candidates: Vec<serde_json::Value>,
+    count: usize,
+    allow_repeat: bool,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("以种子 {} 复现加权抽奖: {} 个候选项中抽取 {} 个", seed, candidates.len(), count);
+    crate::app_lock::ensure_unlocked()?;
+    run_lottery(&app_handle, candidates, count, allow_repeat, Some(seed), false)
+}
+
+// 不重复点名状态的读-改-写锁
+static DRAWN_STATE_LOCK: Mutex<()> = Mutex::new(());
+
+// 名单的已点名状态文件 coredata/pools/<名单 id>.json：{ "groupName", "drawn": [姓名], "updatedTime" }
+//
+// 旧版本保存在 coredata/drawn/ 下，首次访问时移动到 pools/。
+fn drawn_state_path(roster: &roster::Roster) -> Result<std::path::PathBuf, String> {
+    let coredata = history::coredata_dir()?;
+    let file_name = format!("{}.json", roster.id);
+    let path = coredata.join("pools").join(&file_name);
+    let legacy_path = coredata.join("drawn").join(&file_name);
+    if !path.exists() && legacy_path.exists() {
+        std::fs::create_dir_all(coredata.join("pools")).map_err(|e| format!("创建目录失败: {}", e))?;
+        std::fs::rename(&legacy_path, &path).map_err(|e| format!("迁移点名状态失败: {}", e))?;
+        log::info!("已迁移点名状态: {:?} -> {:?}", legacy_path, path);
+    }
+    Ok(path)
+}
+
+// 读取本轮已抽中的姓名（规范化形式），文件不存在时为空
+fn load_drawn(path: &std::path::Path) -> Result<std::collections::HashSet<String>, String> {
+    let Some(state) = crate::storage::read_json_or_recover(path)? else {
+        return Ok(std::collections::HashSet::new());
+    };
+    Ok(state
+        .get("drawn")
+        .and_then(|v| v.as_array())
+        .map(|names| names.iter().filter_map(|n| n.as_str()).map(history::normalize_name).collect())
+        .unwrap_or_default())
+}
+
+// 保存本轮已抽中的姓名
+fn save_drawn(path: &std::path::Path, group_name: &str, drawn: &[String]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    let state = serde_json::json!({
+        "groupName": group_name,
+        "drawn": drawn,
+        "updatedTime": chrono::Utc::now().to_rfc3339()
+    });
+    let content = serde_json::to_string_pretty(&state).map_err(|e| format!("序列化点名状态失败: {}", e))?;
+    crate::storage::write_atomic(path, content.as_bytes()).map_err(|e| {
+        let error = format!("保存点名状态失败: {}", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 从已保存的名单中不重复抽取：本轮所有人都被抽中过之前，不会有人被抽中第二次
+//
+// 已抽中的人记录在 coredata/pools/<名单 id>.json 中，重启后仍然有效。本轮剩余人数不足 count 时，
+// 先抽走剩余的所有人，再开始新一轮并从其他人中补足，新一轮只记录补足的人。
+// 返回的 remaining 为本轮还剩多少人未被抽中（为 0 时下一次抽取会开始新一轮），resetOccurred 表示本次抽取是否开始了新一轮。
+#[tauri::command]
+pub async fn draw_without_replacement(
+    app_handle: tauri::AppHandle,
+    group_name: String,
+    count: usize,
+) -> Result<serde_json::Value, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    run_draw_without_replacement(app_handle, group_name, count).await
+}
+
+// draw_without_replacement 的实现，不检查应用锁；定时抽取在应用锁定时也照常执行
+pub async fn run_draw_without_replacement(
+    app_handle: tauri::AppHandle,
+    group_name: String,
+    count: usize,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("不重复点名: {} 抽取 {} 人", group_name, count);
+
+    let roster = roster::resolve_roster(&group_name)?;
+    let pool = prepare_pool(&app_handle, roster.member_names())?;
+    let n = pool.participants.len();
+    check_count(n, count)?;
+
+    let _state_guard = DRAWN_STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = drawn_state_path(&roster)?;
+    let drawn = load_drawn(&path)?;
+
+    let (mut rng, seed, rng_name) = draw_rng(&app_handle, None)?;
+    let mut stream = DrawStream::start(&app_handle, "without-replacement", &pool.participants, count);
+    let (winners, round_drawn, reset_occurred) = draw_round(&pool.participants, &drawn, count, &mut rng);
+    if reset_occurred {
+        log::info!("名单 {} 的本轮点名已完成，开始新一轮", roster.name);
+    }
+
+    save_drawn(&path, &roster.name, &round_drawn)?;
+    stream.candidates(&winners, None);
+
+    let mut result = serde_json::json!({
+        "winners": winners,
+        "seed": seed,
+        "rng": rng_name,
+        "method": METHOD_FISHER_YATES,
+        "poolSize": n,
+        "count": count,
+        "remaining": n - round_drawn.len(),
+        "resetOccurred": reset_occurred,
+        "capped": pool.capped
+    });
+    stream.finish(&mut result);
+    Ok(result)
+}
+
+// 从本轮尚未抽中的人中抽取 count 人，返回中奖者、抽取后本轮已抽中的人以及是否开始了新一轮
+//
+// 剩余的人不足 count 时先全部抽走，再从本轮已抽中的人中补足，补足的人计入新一轮。
+fn draw_round(
+    participants: &[String],
+    drawn: &std::collections::HashSet<String>,
+    count: usize,
+    rng: &mut impl Rng,
+) -> (Vec<String>, Vec<String>, bool) {
+    let (already_drawn, remaining): (Vec<String>, Vec<String>) = participants
+        .iter()
+        .cloned()
+        .partition(|name| drawn.contains(&history::normalize_name(name)));
+
+    if remaining.len() >= count {
+        let winners = sample_without_replacement(&remaining, count, rng);
+        let mut round_drawn = already_drawn;
+        round_drawn.extend(winners.iter().cloned());
+        (winners, round_drawn, false)
+    } else {
+        let mut winners = sample_without_replacement(&remaining, remaining.len(), rng);
+        let refill = sample_without_replacement(&already_drawn, count - winners.len(), rng);
+        winners.extend(refill.iter().cloned());
+        (winners, refill, true)
+    }
+}
+
+// 名单中本轮尚未被抽中的成员（保持名单顺序）
+fn remaining_members(roster: &roster::Roster, drawn: &std::collections::HashSet<String>) -> Vec<String> {
+    roster
+        .member_names()
+        .into_iter()
+        .filter(|name| !name.trim().is_empty() && !drawn.contains(&history::normalize_name(name)))
+        .collect()
+}
+
+// 把前端已抽中的人记入名单的不重复抽取状态，返回本轮剩余的成员
+//
+// group_id 可以是名单名称或 id；不在名单中的姓名会被忽略。记入后全部成员都已抽中时不会自动开始新一轮，需调用 reset_pool。
+#[tauri::command]
+pub async fn mark_drawn(group_id: String, names: Vec<String>) -> Result<Vec<String>, AppError> {
+    log::info!("记录已抽中: {} {:?}", group_id, names);
+    crate::app_lock::ensure_unlocked()?;
+
+    let roster = roster::resolve_roster(&group_id)?;
+    let members: std::collections::HashMap<String, String> = roster
+        .member_names()
+        .into_iter()
+        .map(|name| (history::normalize_name(&name), name))
+        .collect();
+
+    let _state_guard = DRAWN_STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = drawn_state_path(&roster)?;
+    let mut drawn = load_drawn(&path)?;
+    for name in &names {
+        let key = history::normalize_name(name);
+        if members.contains_key(&key) {
+            drawn.insert(key);
+        } else {
+            log::warn!("{} 不在名单 {} 中，已忽略", name, roster.name);
+        }
+    }
+
+    // 按名单中的原始姓名保存，与 draw_without_replacement 写入的格式一致
+    let round_drawn: Vec<String> = roster
+        .member_names()
+        .into_iter()
+        .filter(|name| drawn.contains(&history::normalize_name(name)))
+        .collect();
+    save_drawn(&path, &roster.name, &round_drawn)?;
+    Ok(remaining_members(&roster, &drawn))
+}
+
+// 名单中本轮尚未被抽中的成员（保持名单顺序）
+#[tauri::command]
+pub async fn get_remaining(group_id: String) -> Result<Vec<String>, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let roster = roster::resolve_roster(&group_id)?;
+    let _state_guard = DRAWN_STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let drawn = load_drawn(&drawn_state_path(&roster)?)?;
+    Ok(remaining_members(&roster, &drawn))
+}
+
+// 清空名单的已点名状态，下一次不重复抽取从新一轮开始
+#[tauri::command]
+pub async fn reset_pool(group_id: String) -> Result<(), AppError> {
+    log::info!("重置不重复抽取: {}", group_id);
+    crate::app_lock::ensure_unlocked()?;
+
+    Ok(clear_drawn_state(&roster::resolve_roster(&group_id)?)?)
+}
+
+// 与 reset_pool 相同，保留给旧版前端
+#[tauri::command]
+pub async fn reset_draw_pool(group_name: String) -> Result<(), AppError> {
+    reset_pool(group_name).await
+}
+
+// 删除名单的已点名状态文件，重置或删除名单时调用
+pub fn clear_drawn_state(roster: &roster::Roster) -> Result<(), String> {
+    let _state_guard = DRAWN_STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = drawn_state_path(roster)?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("删除点名状态失败: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("成员{}", i)).collect()
+    }
+
+    #[test]
+    fn same_seed_draws_same_winners() {
+        let pool = people(50);
+        for method in [METHOD_FISHER_YATES, METHOD_RESERVOIR] {
+            let first = sample_with_method(method, &pool, 5, &mut rng_from_seed(20240601));
+            let second = sample_with_method(method, &pool, 5, &mut rng_from_seed(20240601));
+            assert_eq!(first, second);
+        }
+    }
+
+    fn weighted_candidates(n: usize) -> Vec<serde_json::Value> {
+        (1..=n)
+            .map(|i| serde_json::json!({ "id": format!("c{}", i), "name": format!("成员{}", i), "weight": i as f64 }))
+            .collect()
+    }
+
+    fn lottery_winners(draw: &LotteryDraw) -> Vec<serde_json::Value> {
+        draw.picked.iter().map(|&i| draw.pool[i].raw.clone()).collect()
+    }
+
+    #[test]
+    fn same_seed_weighted_lottery_draws_same_winners() {
+        let settings = serde_json::json!({});
+        for allow_repeat in [false, true] {
+            let first = lottery_draw(&settings, weighted_candidates(30), 5, allow_repeat, Some(42), true).unwrap();
+            let second = lottery_draw(&settings, weighted_candidates(30), 5, allow_repeat, Some(42), true).unwrap();
+            assert_eq!(first.seed, Some(42));
+            assert_eq!(lottery_winners(&first), lottery_winners(&second));
+        }
+    }
+
+    #[test]
+    fn seeded_lottery_replay_ignores_denylist() {
+        let original = lottery_draw(&serde_json::json!({}), weighted_candidates(10), 3, false, Some(7), false).unwrap();
+        // 抽取之后才把中奖者加入禁抽名单，复现结果不受影响
+        let denied = original.pool[original.picked[0]].name.clone();
+        let settings = serde_json::json!({ "denylist": [denied] });
+        let replay = lottery_draw(&settings, weighted_candidates(10), 3, false, Some(7), false).unwrap();
+        assert_eq!(lottery_winners(&original), lottery_winners(&replay));
+
+        let ruled = lottery_draw(&settings, weighted_candidates(10), 3, false, Some(7), true).unwrap();
+        assert!(ruled.pool.iter().all(|c| c.name != denied));
+    }
+
+    #[test]
+    fn unseeded_lotteries_use_different_seeds() {
+        let settings = serde_json::json!({});
+        let first = lottery_draw(&settings, weighted_candidates(30), 5, false, None, true).unwrap();
+        let second = lottery_draw(&settings, weighted_candidates(30), 5, false, None, true).unwrap();
+        let (Some(first_seed), Some(second_seed)) = (first.seed, second.seed) else {
+            panic!("默认模式应返回实际使用的种子");
+        };
+        assert_ne!(first_seed, second_seed);
+        assert!(first_seed <= SEED_MASK && second_seed <= SEED_MASK);
+
+        // 返回的种子可以复现该次抽取
+        let replay = lottery_draw(&settings, weighted_candidates(30), 5, false, Some(first_seed), true).unwrap();
+        assert_eq!(lottery_winners(&first), lottery_winners(&replay));
+    }
+
+    #[test]
+    fn os_rng_mode_reports_no_seed() {
+        let draw = lottery_draw(&serde_json::json!({ "rngMode": RNG_OS }), weighted_candidates(5), 2, false, None, true).unwrap();
+        assert_eq!((draw.seed, draw.rng_name), (None, RNG_OS));
+    }
+
+    #[test]
+    fn without_replacement_picks_everyone_once_per_round() {
+        let dir = crate::storage::test_support::TempDir::new("pool");
+        let path = dir.path().join("pool.json");
+        let pool = people(6);
+        let mut rng = rng_from_seed(new_seed());
+
+        // 每次抽取都从磁盘读写已抽中状态，与重启后继续抽取相同
+        let mut picked = Vec::new();
+        for _ in 0..pool.len() {
+            let (winners, round_drawn, reset_occurred) = draw_round(&pool, &load_drawn(&path).unwrap(), 1, &mut rng);
+            assert!(!reset_occurred);
+            save_drawn(&path, "测试名单", &round_drawn).unwrap();
+            picked.extend(winners);
+        }
+        let mut sorted = picked.clone();
+        sorted.sort();
+        let mut expected = pool.clone();
+        expected.sort();
+        assert_eq!(sorted, expected, "每个人应恰好被抽中一次: {:?}", picked);
+
+        let (winners, round_drawn, reset_occurred) = draw_round(&pool, &load_drawn(&path).unwrap(), 1, &mut rng);
+        assert!(reset_occurred);
+        assert_eq!(round_drawn, winners);
+    }
+
+    #[test]
+    fn without_replacement_refills_next_round() {
+        let pool = people(5);
+        let drawn: std::collections::HashSet<String> =
+            pool[..4].iter().map(|name| history::normalize_name(name)).collect();
+        let (winners, round_drawn, reset_occurred) = draw_round(&pool, &drawn, 3, &mut rng_from_seed(7));
+        assert!(reset_occurred);
+        assert_eq!(winners.len(), 3);
+        assert_eq!(winners[0], pool[4], "本轮剩下的人先被抽走");
+        assert_eq!(round_drawn, winners[1..].to_vec());
+        assert!(!round_drawn.contains(&pool[4]));
+    }
+}
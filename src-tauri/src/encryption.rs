@@ -0,0 +1,635 @@
+// 历史任务文件的静态加密（可选）
+//
+// 设置 encryptionEnabled 为 true 后，任务文件中的 task-data 改为保存 AES-256-GCM 加密后的
+// encrypted-task-data（{ "alg", "nonce", "ciphertext" }，Base64），年月与创建时间仍为明文。
+// 密钥由用户密码经 Argon2id 派生（见 password.rs），参数串与密码校验值保存在 coredata/encryption.json，密钥本身只保存在内存中：
+// 每次启动后需先调用 unlock_history 输入密码，才能读取或保存加密的任务。旧版本以 PBKDF2-SHA256 派生的配置仍可解锁，
+// 下次更换加密密码时改为 Argon2id。
+// 历史索引（history.db）保持明文，索引条目中不含中奖者姓名。
+//
+// 单个任务还可以用 set_task_password 设置任务密码：完整的任务数据以 AES-256-GCM 加密保存在
+// password-task-data 中，密钥同样由任务密码经 Argon2id 派生（参数串随密文保存）；task-data 只保留
+// 供历史列表显示的概要（password_protected 为 true，不含参与者与中奖者）。
+// 用 unlock_history_task 输入任务密码后，本次运行期间读取该任务得到完整数据，保存时自动重新加密。
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, RwLock};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use rand::RngCore;
+
+use crate::error::AppError;
+use crate::{history, password, protection, settings, storage};
+
+// 加密任务文件中替代 task-data 的字段
+pub const ENCRYPTED_FIELD: &str = "encrypted-task-data";
+
+const CIPHER_ALGORITHM: &str = "aes-256-gcm";
+const KDF_ALGORITHM: &str = "argon2id";
+const NONCE_LENGTH: usize = 12;
+
+// 用于校验密码是否正确的已知明文
+const VERIFIER_PLAINTEXT: &[u8] = b"starrandom-encryption-check";
+
+// 解锁后的密钥
+static ENCRYPTION_KEY: RwLock<Option<[u8; 32]>> = RwLock::new(None);
+
+// 更换加密密码尚未完成时的旧密钥：还未重新加密的任务文件用它解密
+static PREVIOUS_KEY: RwLock<Option<[u8; 32]>> = RwLock::new(None);
+
+// 加密配置中以新密钥加密保存旧密钥的字段，更换加密密码完成后删除
+const PREVIOUS_KEY_FIELD: &str = "previousKey";
+
+// 设置了任务密码的任务文件中保存完整任务数据密文的字段
+pub const PASSWORD_FIELD: &str = "password-task-data";
+
+// 任务概要中标记任务已设置密码的字段
+const PASSWORD_PROTECTED_FLAG: &str = "password_protected";
+
+// 任务概要保留的字段，足以生成历史索引条目
+const SUMMARY_FIELDS: &[&str] = &["id", "name", "timestamp", "timezone_offset_minutes", "group_name", "total_count"];
+
+// 已解锁任务的密钥及其参数串（任务 id -> 密钥），保存时沿用同一密钥重新加密
+#[derive(Clone)]
+struct TaskKey {
+    key: [u8; 32],
+    params: String,
+}
+
+static TASK_KEYS: LazyLock<Mutex<HashMap<String, TaskKey>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 设置中是否启用了加密
+pub fn is_enabled(settings: &serde_json::Value) -> bool {
+    settings.get("encryptionEnabled").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+// 加密配置文件 coredata/encryption.json，设置过密码后才存在
+fn config_path() -> Result<std::path::PathBuf, String> {
+    Ok(history::coredata_dir()?.join("encryption.json"))
+}
+
+fn load_config() -> Result<Option<serde_json::Value>, AppError> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| AppError::io("读取加密配置失败", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| AppError::Parse(format!("解析加密配置失败: {}", e)))
+}
+
+fn current_key() -> Option<[u8; 32]> {
+    *ENCRYPTION_KEY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn set_keys(key: [u8; 32], previous: Option<[u8; 32]>) {
+    *ENCRYPTION_KEY.write().unwrap_or_else(|e| e.into_inner()) = Some(key);
+    *PREVIOUS_KEY.write().unwrap_or_else(|e| e.into_inner()) = previous;
+}
+
+fn save_config(config: &serde_json::Value) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config).map_err(|e| format!("序列化加密配置失败: {}", e))?;
+    storage::write_atomic(&config_path()?, content.as_bytes()).map_err(|e| {
+        let error = format!("保存加密配置失败: {}", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 配置中保存的旧密钥（上次更换加密密码未完成时存在），用解锁得到的密钥解密
+fn previous_key_of(config: &serde_json::Value, key: &[u8; 32]) -> Result<Option<[u8; 32]>, String> {
+    let Some(wrapped) = config.get(PREVIOUS_KEY_FIELD) else {
+        return Ok(None);
+    };
+    let previous = decrypt_bytes(key, wrapped)?;
+    let previous: [u8; 32] = previous.try_into().map_err(|_| "加密配置中的旧密钥无效".to_string())?;
+    Ok(Some(previous))
+}
+
+// 用当前密钥解密任务数据，失败时再尝试更换密码前的旧密钥
+fn decrypt_task_bytes(encrypted: &serde_json::Value) -> Result<Vec<u8>, String> {
+    let key = current_key().ok_or("历史记录已加密，请先输入加密密码解锁")?;
+    match decrypt_bytes(&key, encrypted) {
+        Ok(plaintext) => Ok(plaintext),
+        Err(e) => match *PREVIOUS_KEY.read().unwrap_or_else(|e| e.into_inner()) {
+            Some(previous) => decrypt_bytes(&previous, encrypted).map_err(|_| e),
+            None => Err(e),
+        },
+    }
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn encrypt_bytes(key: &[u8; 32], plaintext: &[u8]) -> Result<serde_json::Value, String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| format!("初始化加密失败: {}", e))?;
+    let nonce = random_bytes::<NONCE_LENGTH>();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "加密失败".to_string())?;
+    Ok(serde_json::json!({
+        "alg": CIPHER_ALGORITHM,
+        "nonce": engine.encode(nonce),
+        "ciphertext": engine.encode(ciphertext)
+    }))
+}
+
+fn decrypt_bytes(key: &[u8; 32], encrypted: &serde_json::Value) -> Result<Vec<u8>, String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    if encrypted.get("alg").and_then(|v| v.as_str()) != Some(CIPHER_ALGORITHM) {
+        return Err("不支持的加密算法".to_string());
+    }
+    let field = |key: &str| {
+        encrypted
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|s| engine.decode(s).ok())
+            .ok_or_else(|| format!("加密数据缺少有效的 {}", key))
+    };
+    let nonce = field("nonce")?;
+    if nonce.len() != NONCE_LENGTH {
+        return Err("加密数据的 nonce 长度无效".to_string());
+    }
+    let ciphertext = field("ciphertext")?;
+
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| format!("初始化解密失败: {}", e))?;
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "解密失败：加密密码错误或数据已损坏".to_string())
+}
+
+// 按配置中的参数派生密钥，并用校验值确认密码正确
+//
+// kdf 为 argon2id 时 params 为 Argon2id 参数串；旧版本的配置为 pbkdf2-sha256，使用 salt 与 iterations。
+fn unlock_with_config(config: &serde_json::Value, passphrase: &str) -> Result<[u8; 32], String> {
+    let verifier = config.get("verifier").ok_or("加密配置缺少密码校验值")?;
+    let key = if config.get("kdf").and_then(|v| v.as_str()) == Some(KDF_ALGORITHM) {
+        let params = config
+            .get("params")
+            .and_then(|v| v.as_str())
+            .ok_or("加密配置缺少密钥参数")?;
+        password::derive_key(passphrase, params)?
+    } else {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let salt = config
+            .get("salt")
+            .and_then(|v| v.as_str())
+            .and_then(|s| engine.decode(s).ok())
+            .ok_or("加密配置缺少有效的盐")?;
+        let iterations = config
+            .get("iterations")
+            .and_then(|v| v.as_u64())
+            .and_then(|n| u32::try_from(n).ok())
+            .filter(|n| *n > 0)
+            .ok_or("加密配置缺少有效的迭代次数")?;
+        password::derive_legacy_key(passphrase, &salt, iterations)
+    };
+
+    match decrypt_bytes(&key, verifier) {
+        Ok(plaintext) if plaintext == VERIFIER_PLAINTEXT => Ok(key),
+        _ => Err("加密密码错误".to_string()),
+    }
+}
+
+fn task_key(task_id: &str) -> Option<TaskKey> {
+    TASK_KEYS.lock().unwrap_or_else(|e| e.into_inner()).get(task_id).cloned()
+}
+
+// 清除本次运行中已解锁的全部任务密钥（锁定应用时调用），之后读取这些任务只能得到概要
+pub fn forget_task_keys() {
+    TASK_KEYS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+// 任务密钥的参数串；旧版本保存为 { kdf: "argon2id", m, t, p, salt } 对象，转换为等价的参数串
+fn task_key_params(kdf: &serde_json::Value) -> Result<String, String> {
+    if let Some(params) = kdf.as_str() {
+        return Ok(params.to_string());
+    }
+    if kdf.get("kdf").and_then(|v| v.as_str()) != Some(KDF_ALGORITHM) {
+        return Err("不支持的任务密码派生算法".to_string());
+    }
+    let engine = base64::engine::general_purpose::STANDARD;
+    let param = |key: &str| {
+        kdf.get(key)
+            .and_then(|v| v.as_u64())
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| format!("任务密码参数缺少有效的 {}", key))
+    };
+    let salt = kdf
+        .get("salt")
+        .and_then(|v| v.as_str())
+        .and_then(|s| engine.decode(s).ok())
+        .ok_or("任务密码参数缺少有效的盐")?;
+    password::key_params(param("m")?, param("t")?, param("p")?, &salt)
+}
+
+// 设置了任务密码的任务在 task-data 中保存的概要
+fn task_summary(task_data: &serde_json::Value) -> serde_json::Value {
+    let mut summary = serde_json::Map::new();
+    for field in SUMMARY_FIELDS {
+        if let Some(value) = task_data.get(*field) {
+            summary.insert(field.to_string(), value.clone());
+        }
+    }
+    summary.insert(PASSWORD_PROTECTED_FLAG.to_string(), serde_json::Value::Bool(true));
+    serde_json::Value::Object(summary)
+}
+
+// 任务数据是否为设置了任务密码、尚未解锁时得到的概要
+pub fn is_password_summary(task_data: &serde_json::Value) -> bool {
+    task_data.get(PASSWORD_PROTECTED_FLAG).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+// 读取加密的任务之前调用：设置过加密密码但尚未解锁时返回错误
+pub fn ensure_unlocked() -> Result<(), String> {
+    if current_key().is_none() && config_path()?.exists() {
+        return Err("历史记录已加密，请先输入加密密码解锁".to_string());
+    }
+    Ok(())
+}
+
+// 从任务文件内容中取出 task-data，加密的任务用已解锁的密钥解密
+//
+// 设置了任务密码的任务，已解锁时返回完整数据，否则返回概要。
+pub fn task_data_of(task_file_data: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let task_data = match task_file_data.get("task-data") {
+        Some(task_data) => task_data.clone(),
+        None => {
+            let encrypted = task_file_data.get(ENCRYPTED_FIELD).ok_or("任务文件缺少 task-data")?;
+            let plaintext = decrypt_task_bytes(encrypted)?;
+            serde_json::from_slice(&plaintext).map_err(|e| format!("解析解密后的任务数据失败: {}", e))?
+        }
+    };
+
+    let Some(protected) = task_file_data.get(PASSWORD_FIELD) else {
+        return Ok(task_data);
+    };
+    let task_id = task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+    let Some(task_key) = task_key(task_id) else {
+        return Ok(task_data);
+    };
+    let plaintext = decrypt_bytes(&task_key.key, protected)?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("解析解密后的任务数据失败: {}", e))
+}
+
+// 按任务密码解密任务文件中的完整数据，密码错误时返回 None
+fn unlock_task_file(task_file_data: &serde_json::Value, password: &str) -> Result<Option<(TaskKey, serde_json::Value)>, String> {
+    let protected = task_file_data.get(PASSWORD_FIELD).ok_or("任务未设置密码")?;
+    let params = task_key_params(protected.get("kdf").ok_or("任务密码参数缺失")?)?;
+    let key = password::derive_key(password, &params)?;
+    let Ok(plaintext) = decrypt_bytes(&key, protected) else {
+        return Ok(None);
+    };
+    let task_data = serde_json::from_slice(&plaintext).map_err(|e| format!("解析解密后的任务数据失败: {}", e))?;
+    Ok(Some((TaskKey { key, params }, task_data)))
+}
+
+// 任务文件内容是否为加密形式
+pub fn is_encrypted(task_file_data: &serde_json::Value) -> bool {
+    task_file_data.get(ENCRYPTED_FIELD).is_some()
+}
+
+// 把 task-data 写回任务文件内容：encrypt 为 true 时加密保存为 encrypted-task-data
+//
+// 已解锁任务密码的任务把完整数据加密保存到 password-task-data，task-data 只写入概要；
+// 设置了任务密码但未解锁时只能写回概要，传入完整数据会被拒绝，避免以明文覆盖。
+pub fn store_task_data(
+    task_file_data: &mut serde_json::Value,
+    mut task_data: serde_json::Value,
+    encrypt: bool,
+) -> Result<(), String> {
+    let obj = task_file_data.as_object_mut().ok_or("任务文件必须是 JSON 对象")?;
+    let task_id = task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    if let Some(task_key) = task_key(&task_id) {
+        let plaintext = serde_json::to_vec(&task_data).map_err(|e| format!("序列化任务数据失败: {}", e))?;
+        let mut protected = encrypt_bytes(&task_key.key, &plaintext)?;
+        protected["kdf"] = serde_json::Value::String(task_key.params);
+        obj.insert(PASSWORD_FIELD.to_string(), protected);
+        task_data = task_summary(&task_data);
+    } else if obj.contains_key(PASSWORD_FIELD) && !is_password_summary(&task_data) {
+        return Err("任务已设置密码，请先输入任务密码解锁".to_string());
+    }
+    obj.remove("task-data");
+    obj.remove(ENCRYPTED_FIELD);
+
+    if encrypt {
+        let key = current_key().ok_or("已启用加密，请先设置或输入加密密码")?;
+        let plaintext = serde_json::to_vec(&task_data).map_err(|e| format!("序列化任务数据失败: {}", e))?;
+        obj.insert(ENCRYPTED_FIELD.to_string(), encrypt_bytes(&key, &plaintext)?);
+    } else {
+        obj.insert("task-data".to_string(), task_data);
+    }
+    Ok(())
+}
+
+// 输入加密密码解锁历史记录（每次启动后需要调用一次）
+#[tauri::command]
+pub async fn unlock_history(passphrase: String) -> Result<(), AppError> {
+    log::info!("解锁加密的历史记录");
+    crate::app_lock::ensure_unlocked()?;
+
+    let config = load_config()?.ok_or_else(|| AppError::NotFound("尚未设置加密密码".to_string()))?;
+    let key = unlock_with_config(&config, &passphrase).map_err(|e| {
+        log::warn!("{}", e);
+        e
+    })?;
+    let previous = previous_key_of(&config, &key)?;
+    if previous.is_some() {
+        log::warn!("上次更换加密密码未完成，尚未重新加密的任务用旧密钥读取，再次设置加密密码即可完成");
+    }
+    set_keys(key, previous);
+    log::info!("历史记录已解锁");
+    Ok(())
+}
+
+// 设置或更换加密密码，并用新密钥重新加密全部任务文件（coredata/history 下的全部任务文件，不依赖历史索引）
+//
+// 首次设置时 old 可以为空；启用了加密时，尚未加密的任务文件也会在这里加密。
+// 先写入新的加密配置，旧密钥以新密钥加密保存在其中，再逐个重新加密任务文件，全部完成后删除旧密钥：
+// 中途失败或程序退出时，用新密码解锁仍能读取尚未重新加密的任务，再次调用即可完成；
+// 再次调用时先把仍使用旧密钥的任务改用当前密钥，再更换新密码，见 finish_pending_rotation。
+#[tauri::command]
+pub async fn set_encryption_passphrase(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    old: String,
+    new: String,
+) -> Result<usize, AppError> {
+    log::info!("设置加密密码");
+    crate::app_lock::ensure_unlocked()?;
+
+    if new.is_empty() {
+        return Err(AppError::InvalidInput("加密密码不能为空".to_string()));
+    }
+
+    let encrypt_all = is_enabled(&settings::current_settings(&app_handle)?);
+    let _index_guard = index_lock.lock();
+    change_passphrase(&old, &new, encrypt_all)
+}
+
+// set_encryption_passphrase 的实现（调用方须持有历史索引锁）
+fn change_passphrase(old: &str, new: &str, encrypt_all: bool) -> Result<usize, AppError> {
+    let old_key = match load_config()? {
+        Some(mut config) => {
+            let key = unlock_with_config(&config, old)?;
+            set_keys(key, None);
+            finish_pending_rotation(&mut config, &key)?;
+            Some(key)
+        }
+        None => None,
+    };
+
+    // 先确认全部任务都能读取，避免写入新配置后才发现有无法解密的文件
+    let mut task_files = Vec::new();
+    for path in history::history_task_files()? {
+        let task_file_data = read_task_file_at(&path)?;
+        if !is_encrypted(&task_file_data) && !encrypt_all {
+            continue;
+        }
+        task_data_of(&task_file_data).map_err(|e| format!("{:?}: {}", path, e))?;
+        task_files.push(path);
+    }
+
+    let params = password::new_key_params()?;
+    let new_key = password::derive_key(new, &params)?;
+    let mut config = serde_json::json!({
+        "version": 2,
+        "kdf": KDF_ALGORITHM,
+        "params": params,
+        "verifier": encrypt_bytes(&new_key, VERIFIER_PLAINTEXT)?
+    });
+    if let Some(old_key) = old_key {
+        config[PREVIOUS_KEY_FIELD] = encrypt_bytes(&new_key, &old_key)?;
+    }
+    save_config(&config)?;
+    set_keys(new_key, old_key);
+
+    reencrypt_task_files(&task_files)?;
+
+    // 全部重新加密后不再需要旧密钥
+    if let Some(obj) = config.as_object_mut() {
+        obj.remove(PREVIOUS_KEY_FIELD);
+    }
+    save_config(&config)?;
+    set_keys(new_key, None);
+
+    log::info!("加密密码已更新，重新加密了 {} 个任务文件", task_files.len());
+    Ok(task_files.len())
+}
+
+// 上次更换加密密码未完成时，先用当前密钥重新加密仍使用旧密钥的任务文件，再从配置中删除旧密钥
+//
+// 配置中只保存一个旧密钥，不先处理就换新密钥会丢掉它，这些任务文件将再也无法解密。
+fn finish_pending_rotation(config: &mut serde_json::Value, key: &[u8; 32]) -> Result<(), AppError> {
+    let Some(previous) = previous_key_of(config, key)? else {
+        return Ok(());
+    };
+    set_keys(*key, Some(previous));
+
+    let mut stale_files = Vec::new();
+    for path in history::history_task_files()? {
+        let task_file_data = read_task_file_at(&path)?;
+        let Some(encrypted) = task_file_data.get(ENCRYPTED_FIELD) else {
+            continue;
+        };
+        if decrypt_bytes(key, encrypted).is_err() {
+            task_data_of(&task_file_data).map_err(|e| format!("{:?}: {}", path, e))?;
+            stale_files.push(path);
+        }
+    }
+    reencrypt_task_files(&stale_files)?;
+
+    if let Some(obj) = config.as_object_mut() {
+        obj.remove(PREVIOUS_KEY_FIELD);
+    }
+    save_config(config)?;
+    set_keys(*key, None);
+    log::info!("已完成上次未完成的加密密码更换，重新加密了 {} 个任务文件", stale_files.len());
+    Ok(())
+}
+
+// 用当前密钥重新加密并原子写回任务文件
+fn reencrypt_task_files(paths: &[std::path::PathBuf]) -> Result<(), AppError> {
+    for path in paths {
+        let mut task_file_data = read_task_file_at(path)?;
+        let task_data = task_data_of(&task_file_data).map_err(|e| format!("{:?}: {}", path, e))?;
+        store_task_data(&mut task_file_data, task_data, true)?;
+        let content = serde_json::to_string_pretty(&task_file_data)
+            .map_err(|e| format!("序列化任务数据失败: {}", e))?;
+        storage::write_atomic(path, content.as_bytes()).map_err(|e| {
+            let error = AppError::io(&format!("写入任务文件失败 {:?}", path), e);
+            log::error!("{}", error);
+            error
+        })?;
+    }
+    Ok(())
+}
+
+// 读取并解析指定路径的任务文件
+fn read_task_file_at(path: &std::path::Path) -> Result<serde_json::Value, AppError> {
+    let content = std::fs::read_to_string(path).map_err(|e| AppError::io(&format!("读取任务文件失败 {:?}", path), e))?;
+    serde_json::from_str(&content).map_err(|e| AppError::Parse(format!("解析任务文件失败 {:?}: {}", path, e)))
+}
+
+// 读取任务文件（调用方须持有历史索引锁）
+fn read_task_file_data(task_id: &str) -> Result<(std::path::PathBuf, serde_json::Value), AppError> {
+    let path = history::find_task_file(task_id)?.ok_or_else(|| AppError::NotFound(format!("任务不存在: {}", task_id)))?;
+    let content = std::fs::read_to_string(&path).map_err(|e| AppError::io("读取任务文件失败", e))?;
+    let task_file_data = serde_json::from_str(&content).map_err(|e| AppError::Parse(format!("解析任务文件失败: {}", e)))?;
+    Ok((path, task_file_data))
+}
+
+// 输入任务密码解锁单个任务，返回完整的任务数据；本次运行期间读取与保存该任务不再需要密码
+//
+// 未设置任务密码的任务直接返回任务数据。
+#[tauri::command]
+pub async fn unlock_history_task(
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_id: String,
+    password: String,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("解锁任务: {}", task_id);
+    crate::app_lock::ensure_unlocked()?;
+    ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+    let (_, task_file_data) = read_task_file_data(&task_id)?;
+    let mut task_data = if task_file_data.get(PASSWORD_FIELD).is_none() {
+        task_data_of(&task_file_data)?
+    } else {
+        let Some((task_key, task_data)) = unlock_task_file(&task_file_data, &password)? else {
+            log::warn!("任务 {} 的密码错误", task_id);
+            return Err(AppError::PermissionDenied("任务密码错误".to_string()));
+        };
+        TASK_KEYS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(task_id.clone(), task_key);
+        log::info!("任务 {} 已解锁", task_id);
+        task_data
+    };
+
+    protection::redact(&mut task_data);
+    Ok(task_data)
+}
+
+// 设置或更换任务密码，密码为空时取消任务密码；已设置密码的任务须先用 unlock_history_task 解锁
+#[tauri::command]
+pub async fn set_task_password(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_id: String,
+    password: String,
+) -> Result<(), AppError> {
+    log::info!("设置任务密码: {}", task_id);
+    crate::app_lock::ensure_unlocked()?;
+    ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+    let (path, mut task_file_data) = read_task_file_data(&task_id)?;
+    let task_data = task_data_of(&task_file_data)?;
+    if is_password_summary(&task_data) {
+        return Err(AppError::PermissionDenied(
+            "任务已设置密码，请先输入原密码解锁".to_string(),
+        ));
+    }
+
+    if password.is_empty() {
+        TASK_KEYS.lock().unwrap_or_else(|e| e.into_inner()).remove(&task_id);
+        if let Some(obj) = task_file_data.as_object_mut() {
+            obj.remove(PASSWORD_FIELD);
+        }
+    } else {
+        let params = password::new_key_params()?;
+        let key = password::derive_key(&password, &params)?;
+        TASK_KEYS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(task_id.clone(), TaskKey { key, params });
+    }
+
+    let encrypt = is_encrypted(&task_file_data) || is_enabled(&settings::current_settings(&app_handle)?);
+    store_task_data(&mut task_file_data, task_data, encrypt)?;
+    let content = serde_json::to_string_pretty(&task_file_data)
+        .map_err(|e| format!("序列化任务数据失败: {}", e))?;
+    storage::write_atomic(&path, content.as_bytes()).map_err(|e| {
+        let error = AppError::io("写入任务文件失败", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    if password.is_empty() {
+        log::info!("已取消任务 {} 的密码", task_id);
+    } else {
+        log::info!("已为任务 {} 设置密码", task_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDataRoot;
+
+    // 在 coredata/history/2024/06 下写入明文任务文件
+    fn write_plain_task(task_id: &str) -> std::path::PathBuf {
+        let dir = history::history_dir().unwrap().join("2024").join("06");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("抽奖_{}.json", task_id));
+        let task_data = serde_json::json!({ "id": task_id, "name": "抽奖", "results": ["张三"] });
+        std::fs::write(&path, serde_json::json!({ "task-data": task_data }).to_string()).unwrap();
+        path
+    }
+
+    // 只用指定密钥（不带旧密钥）读取任务数据
+    fn read_with_only(key: [u8; 32], path: &std::path::Path) -> Result<serde_json::Value, String> {
+        set_keys(key, None);
+        task_data_of(&read_task_file_at(path).unwrap())
+    }
+
+    #[test]
+    fn rotation_after_interrupted_rotation_keeps_tasks_readable() {
+        let _root = TempDataRoot::new("encryption-rotation");
+        let first = write_plain_task("task-1");
+        let second = write_plain_task("task-2");
+        assert_eq!(change_passphrase("", "a", true).unwrap(), 2);
+        let key_a = current_key().unwrap();
+
+        // 模拟更换为 b 时中断：新配置已写入，只有 task-1 改用了新密钥
+        let params = password::new_key_params().unwrap();
+        let key_b = password::derive_key("b", &params).unwrap();
+        save_config(&serde_json::json!({
+            "version": 2,
+            "kdf": KDF_ALGORITHM,
+            "params": params,
+            "verifier": encrypt_bytes(&key_b, VERIFIER_PLAINTEXT).unwrap(),
+            PREVIOUS_KEY_FIELD: encrypt_bytes(&key_b, &key_a).unwrap()
+        }))
+        .unwrap();
+        set_keys(key_b, Some(key_a));
+        reencrypt_task_files(std::slice::from_ref(&first)).unwrap();
+        assert!(read_with_only(key_b, &second).is_err());
+
+        // 再次更换前先处理完上次的更换，此后再中断也只需要 b 的密钥
+        let mut config = load_config().unwrap().unwrap();
+        finish_pending_rotation(&mut config, &key_b).unwrap();
+        assert!(load_config().unwrap().unwrap().get(PREVIOUS_KEY_FIELD).is_none());
+        for path in [&first, &second] {
+            assert_eq!(read_with_only(key_b, path).unwrap()["results"], serde_json::json!(["张三"]));
+        }
+
+        assert_eq!(change_passphrase("b", "c", true).unwrap(), 2);
+        let key_c = current_key().unwrap();
+        assert!(change_passphrase("b", "d", true).is_err(), "旧密码不应再能解锁");
+        for path in [&first, &second] {
+            assert_eq!(read_with_only(key_c, path).unwrap()["results"], serde_json::json!(["张三"]));
+        }
+    }
+}
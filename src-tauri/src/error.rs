@@ -0,0 +1,98 @@
+// 命令返回的结构化错误
+//
+// 序列化为 { "code": "NOT_FOUND", "message": "读取JSON文件失败: ..." }：
+// code 是稳定的机器可读错误码，供前端区分处理；message 仍是原来的中文提示，可以直接显示。
+// 所有命令都返回 AppError。
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    // 文件或数据不存在
+    NotFound(String),
+    // 没有读写权限
+    PermissionDenied(String),
+    // 其他文件读写错误
+    Io(String),
+    // 内容无法解析
+    Parse(String),
+    // 参数无效
+    InvalidInput(String),
+    // 路径超出允许访问的数据目录
+    PathNotAllowed(String),
+    // 与已有数据冲突
+    Conflict(String),
+    // 其他内部错误
+    Internal(String),
+}
+
+impl AppError {
+    // 稳定的错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::PermissionDenied(_) => "PERMISSION_DENIED",
+            AppError::Io(_) => "IO",
+            AppError::Parse(_) => "PARSE",
+            AppError::InvalidInput(_) => "INVALID_INPUT",
+            AppError::PathNotAllowed(_) => "PATH_NOT_ALLOWED",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(message)
+            | AppError::PermissionDenied(message)
+            | AppError::Io(message)
+            | AppError::Parse(message)
+            | AppError::InvalidInput(message)
+            | AppError::PathNotAllowed(message)
+            | AppError::Conflict(message)
+            | AppError::Internal(message) => message,
+        }
+    }
+
+    // 按 io::ErrorKind 归类文件错误，消息为 "<context>: <原始错误>"
+    pub fn io(context: &str, error: std::io::Error) -> Self {
+        let message = format!("{}: {}", context, error);
+        match error.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound(message),
+            std::io::ErrorKind::PermissionDenied => AppError::PermissionDenied(message),
+            std::io::ErrorKind::InvalidData => AppError::Parse(message),
+            _ => AppError::Io(message),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+// 仍返回 String 的辅助函数的错误归为内部错误；校验用户输入的函数直接返回 InvalidInput 等具体错误
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal(message.to_string())
+    }
+}
@@ -0,0 +1,759 @@
+// 数据导出
+//
+// 导出的 CSV 均为带 BOM 的 UTF-8，保证 Excel 打开中文姓名时不乱码。
+
+use std::io::Write;
+
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::{backup, history, roster, storage};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+// 导出记录最多保留的条数
+const MAX_EXPORT_LOG_ENTRIES: usize = 200;
+
+// 导出记录文件 coredata/exports.json
+fn exports_log_path() -> Result<std::path::PathBuf, String> {
+    Ok(history::coredata_dir()?.join("exports.json"))
+}
+
+fn load_export_log() -> Result<Vec<serde_json::Value>, String> {
+    let path = exports_log_path()?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("读取导出记录失败: {}", e))?;
+    Ok(serde_json::from_str(&content).unwrap_or_else(|_| vec![]))
+}
+
+fn save_export_log(entries: &[serde_json::Value]) -> Result<(), String> {
+    let path = exports_log_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("序列化导出记录失败: {}", e))?;
+    storage::write_atomic(&path, content.as_bytes()).map_err(|e| format!("保存导出记录失败: {}", e))
+}
+
+// 记录一次导出（最新的在最前），记录失败只写日志，不影响导出本身
+pub fn record_export(dest_path: &str, export_type: &str, count: Option<usize>, task_id: Option<&str>) {
+    let absolute = std::path::absolute(dest_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| dest_path.to_string());
+
+    let entry = serde_json::json!({
+        "path": absolute,
+        "type": export_type,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "count": count,
+        "taskId": task_id
+    });
+
+    let result = load_export_log().and_then(|mut entries| {
+        entries.insert(0, entry);
+        entries.truncate(MAX_EXPORT_LOG_ENTRIES);
+        save_export_log(&entries)
+    });
+    if let Err(e) = result {
+        log::error!("记录导出失败: {}", e);
+    }
+}
+
+// CSV 字段转义：包含逗号、引号或换行时用双引号包裹
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 把若干字段拼成一行 CSV
+pub fn csv_line(fields: &[&str]) -> String {
+    let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    format!("{}\r\n", line.join(","))
+}
+
+// 生成完整的 CSV 内容（带 BOM）
+pub fn csv_bytes(header: &[&str], rows: &[Vec<String>]) -> Vec<u8> {
+    let mut content = Vec::from(UTF8_BOM);
+    content.extend_from_slice(csv_line(header).as_bytes());
+    for row in rows {
+        let fields: Vec<&str> = row.iter().map(|s| s.as_str()).collect();
+        content.extend_from_slice(csv_line(&fields).as_bytes());
+    }
+    content
+}
+
+// 将 CSV 行写入目标文件（带 BOM）
+pub fn write_csv_file(dest_path: &str, header: &[&str], rows: &[Vec<String>]) -> Result<(), String> {
+    let mut file = std::fs::File::create(dest_path).map_err(|e| {
+        let error = format!("创建导出文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    file.write_all(&csv_bytes(header, rows)).map_err(|e| {
+        let error = format!("写入导出文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 参与者的一次中奖记录
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WinRecord {
+    task_id: String,
+    task_name: String,
+    timestamp: String,
+    group_name: String,
+    position: usize,
+}
+
+fn str_field(task_data: &serde_json::Value, key: &str) -> String {
+    task_data.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+}
+
+// 导出某个参与者的全部中奖记录，返回中奖次数
+#[tauri::command]
+pub async fn export_participant_history(
+    name: String,
+    dest_path: String,
+    format: String,
+) -> Result<usize, AppError> {
+    log::info!("导出参与者中奖记录: {} -> {} ({})", name, dest_path, format);
+    crate::app_lock::ensure_unlocked()?;
+
+    let target = history::normalize_name(&name);
+    if target.is_empty() {
+        return Err(AppError::InvalidInput("参与者姓名不能为空".to_string()));
+    }
+
+    let format = format.trim().trim_start_matches('.').to_lowercase();
+    if format != "csv" && format != "json" {
+        return Err(AppError::InvalidInput(format!("不支持的导出格式: {}", format)));
+    }
+
+    // 扫描所有任务文件，记录该参与者每一次中奖
+    let mut records = Vec::new();
+    for index_item in history::load_history_index()? {
+        let Some(task_data) = history::load_task_data(&index_item) else {
+            continue;
+        };
+
+        for (i, winner) in history::task_winners(&task_data).iter().enumerate() {
+            if history::normalize_name(winner) == target {
+                records.push(WinRecord {
+                    task_id: str_field(&task_data, "id"),
+                    task_name: str_field(&task_data, "name"),
+                    timestamp: str_field(&task_data, "timestamp"),
+                    group_name: str_field(&task_data, "group_name"),
+                    position: i + 1,
+                });
+            }
+        }
+    }
+
+    if format == "csv" {
+        let rows: Vec<Vec<String>> = records
+            .iter()
+            .map(|r| {
+                vec![
+                    r.task_id.clone(),
+                    r.task_name.clone(),
+                    r.timestamp.clone(),
+                    r.group_name.clone(),
+                    r.position.to_string(),
+                ]
+            })
+            .collect();
+        write_csv_file(&dest_path, &["任务ID", "任务名称", "时间", "小组", "中奖序号"], &rows)?;
+    } else {
+        let export_data = serde_json::json!({
+            "name": name.trim(),
+            "wins": records.len(),
+            "export_time": chrono::Utc::now().to_rfc3339(),
+            "records": records
+        });
+        let content = serde_json::to_string_pretty(&export_data)
+            .map_err(|e| format!("序列化导出数据失败: {}", e))?;
+        storage::write_atomic(std::path::Path::new(&dest_path), content.as_bytes()).map_err(|e| {
+            let error = format!("写入导出文件失败: {}", e);
+            log::error!("{}", error);
+            error
+        })?;
+    }
+
+    record_export(&dest_path, "participant-history", Some(records.len()), None);
+    log::info!("参与者 {} 共中奖 {} 次，已导出到 {}", name, records.len(), dest_path);
+    Ok(records.len())
+}
+
+// 导出进度事件，payload 为 { processed, total }（已处理 / 总任务数）
+const EXPORT_PROGRESS_EVENT: &str = "export-progress";
+
+// 每处理多少个任务发送一次进度
+const PROGRESS_STEP: usize = 50;
+
+fn emit_progress(app_handle: &tauri::AppHandle, processed: usize, total: usize) {
+    if processed == total || processed.is_multiple_of(PROGRESS_STEP) {
+        let payload = serde_json::json!({ "processed": processed, "total": total });
+        if let Err(e) = app_handle.emit(EXPORT_PROGRESS_EVENT, payload) {
+            log::warn!("发送导出进度失败: {}", e);
+        }
+    }
+}
+
+// 按月导出历史记录：每个月一个 CSV（如 2025-01.csv），打包成一个 ZIP，返回导出的月份数
+//
+// 每行是一名中奖者；没有任何中奖记录的月份不会生成文件。先按索引分好月份，
+// 再逐个读取任务文件并直接写入归档，内存占用与任务数无关。
+#[tauri::command]
+pub async fn export_monthly_csv_zip(app_handle: tauri::AppHandle, dest_zip: String) -> Result<usize, AppError> {
+    log::info!("按月导出历史记录: {}", dest_zip);
+    crate::app_lock::ensure_unlocked()?;
+
+    let mut months: std::collections::BTreeMap<(i64, i64), Vec<serde_json::Value>> = std::collections::BTreeMap::new();
+    for index_item in history::load_history_index()? {
+        let year = index_item.get("year").and_then(|v| v.as_i64());
+        let month = index_item.get("month").and_then(|v| v.as_i64());
+        if let (Some(year), Some(month)) = (year, month) {
+            months.entry((year, month)).or_default().push(index_item);
+        }
+    }
+    let total: usize = months.values().map(|items| items.len()).sum();
+
+    let file = std::fs::File::create(&dest_zip).map_err(|e| {
+        let error = format!("创建导出文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+    let header = ["任务ID", "任务名称", "时间", "小组", "中奖序号", "中奖者"];
+
+    let mut exported = 0;
+    let mut processed = 0;
+    for ((year, month), index_items) in &months {
+        let mut started = false;
+        for index_item in index_items {
+            processed += 1;
+            emit_progress(&app_handle, processed, total);
+            let Some(task_data) = history::load_task_data(index_item) else {
+                continue;
+            };
+            let winners = history::task_winners(&task_data);
+            if winners.is_empty() {
+                continue;
+            }
+
+            // 月份中第一次出现中奖记录时才创建文件，没有中奖记录的月份不生成文件
+            if !started {
+                let entry_name = format!("{}-{:02}.csv", year, month);
+                backup::add_bytes(&mut zip, &entry_name, &csv_bytes(&header, &[]))?;
+                started = true;
+                exported += 1;
+            }
+            for (i, winner) in winners.into_iter().enumerate() {
+                let position = (i + 1).to_string();
+                let line = csv_line(&[
+                    &str_field(&task_data, "id"),
+                    &str_field(&task_data, "name"),
+                    &str_field(&task_data, "timestamp"),
+                    &str_field(&task_data, "group_name"),
+                    &position,
+                    &winner,
+                ]);
+                zip.write_all(line.as_bytes()).map_err(|e| format!("写入归档条目失败: {}", e))?;
+            }
+        }
+    }
+
+    zip.finish().map_err(|e| format!("完成归档写入失败: {}", e))?;
+
+    record_export(&dest_zip, "monthly-csv-zip", Some(exported), None);
+    log::info!("已按月导出 {} 个月的历史记录到 {}", exported, dest_zip);
+    Ok(exported)
+}
+
+// 逐行写入的导出文件：CSV 直接写入带缓冲的文件，XLSX 使用常量内存模式的工作表
+enum RowWriter {
+    Csv(std::io::BufWriter<std::fs::File>),
+    Xlsx {
+        workbook: Box<rust_xlsxwriter::Workbook>,
+        next_row: u32,
+    },
+}
+
+fn xlsx_error(e: rust_xlsxwriter::XlsxError) -> String {
+    format!("写入导出文件失败: {}", e)
+}
+
+impl RowWriter {
+    // 创建导出文件并写入表头
+    fn create(format: &str, dest_path: &str, header: &[&str]) -> Result<Self, String> {
+        if format == "csv" {
+            let file = std::fs::File::create(dest_path).map_err(|e| format!("创建导出文件失败: {}", e))?;
+            let mut writer = std::io::BufWriter::new(file);
+            writer
+                .write_all(&csv_bytes(header, &[]))
+                .map_err(|e| format!("写入导出文件失败: {}", e))?;
+            return Ok(RowWriter::Csv(writer));
+        }
+
+        let mut workbook = Box::new(rust_xlsxwriter::Workbook::new());
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        worksheet.set_name("中奖记录").map_err(xlsx_error)?;
+        for (col, title) in header.iter().enumerate() {
+            worksheet.write_string(0, col as u16, *title).map_err(xlsx_error)?;
+        }
+        Ok(RowWriter::Xlsx { workbook, next_row: 1 })
+    }
+
+    // 写入一行；XLSX 中最后一列（中奖序号）写为数字
+    fn write_row(&mut self, row: &[String]) -> Result<(), String> {
+        match self {
+            RowWriter::Csv(writer) => {
+                let fields: Vec<&str> = row.iter().map(|s| s.as_str()).collect();
+                writer
+                    .write_all(csv_line(&fields).as_bytes())
+                    .map_err(|e| format!("写入导出文件失败: {}", e))
+            }
+            RowWriter::Xlsx { workbook, next_row } => {
+                let worksheet = workbook.worksheet_from_index(0).map_err(xlsx_error)?;
+                for (col, value) in row.iter().enumerate() {
+                    match value.parse::<f64>() {
+                        Ok(number) if col == row.len() - 1 => {
+                            worksheet.write_number(*next_row, col as u16, number).map_err(xlsx_error)?;
+                        }
+                        _ => {
+                            worksheet.write_string(*next_row, col as u16, value).map_err(xlsx_error)?;
+                        }
+                    }
+                }
+                *next_row += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self, dest_path: &str) -> Result<(), String> {
+        match self {
+            RowWriter::Csv(mut writer) => writer.flush().map_err(|e| format!("写入导出文件失败: {}", e)),
+            RowWriter::Xlsx { mut workbook, .. } => workbook.save(dest_path).map_err(xlsx_error),
+        }
+    }
+}
+
+// 逐个读取任务文件并写出每名中奖者一行，每处理完一个任务调用一次 on_progress（参数为已处理的任务数），
+// 返回读取到的任务数与写出的行数。required 为 true 时任一任务文件无法读取即报错，否则跳过
+fn write_history_rows(
+    writer: &mut RowWriter,
+    index_items: &[&serde_json::Value],
+    required: bool,
+    mut on_progress: impl FnMut(usize),
+) -> Result<(usize, usize), String> {
+    let mut task_count = 0;
+    let mut row_count = 0;
+    for (processed, index_item) in index_items.iter().enumerate() {
+        match history::load_task_data(index_item) {
+            Some(task_data) => {
+                task_count += 1;
+                for (i, winner) in history::task_winners(&task_data).into_iter().enumerate() {
+                    writer.write_row(&[
+                        str_field(&task_data, "name"),
+                        str_field(&task_data, "group_name"),
+                        str_field(&task_data, "timestamp"),
+                        winner,
+                        (i + 1).to_string(),
+                    ])?;
+                    row_count += 1;
+                }
+            }
+            None if required => {
+                let id = index_item.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                return Err(format!("任务不存在: {}", id));
+            }
+            None => {}
+        }
+        on_progress(processed + 1);
+    }
+    Ok((task_count, row_count))
+}
+
+// 导出历史中奖记录为 CSV 或 XLSX，返回导出的行数
+//
+// 每行是一名中奖者：任务名称、小组、时间、中奖者、中奖序号。task_ids 为空时导出索引中的全部任务，
+// 否则按给定顺序只导出这些任务，任一任务不存在时报错。任务文件逐个读取并立即写出，
+// 不会把全部任务载入内存；处理进度通过 export-progress 事件发送。
+#[tauri::command]
+pub async fn export_history(
+    app_handle: tauri::AppHandle,
+    format: String,
+    task_ids: Option<Vec<String>>,
+    out_path: String,
+) -> Result<usize, AppError> {
+    log::info!("导出历史记录: {} ({})", out_path, format);
+    crate::app_lock::ensure_unlocked()?;
+
+    let format = format.trim().trim_start_matches('.').to_lowercase();
+    if format != "csv" && format != "xlsx" {
+        return Err(AppError::InvalidInput(format!("不支持的导出格式: {}，仅支持 csv 或 xlsx", format)));
+    }
+
+    // 先只根据索引确定要导出的任务，开始写入前就能发现不存在的任务
+    let history_index = history::load_history_index()?;
+    let index_items: Vec<&serde_json::Value> = match &task_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| {
+                history_index
+                    .iter()
+                    .find(|item| item.get("id").and_then(|v| v.as_str()) == Some(id.as_str()))
+                    .ok_or_else(|| AppError::NotFound(format!("任务不存在: {}", id)))
+            })
+            .collect::<Result<_, AppError>>()?,
+        None => history_index.iter().collect(),
+    };
+    let total = index_items.len();
+
+    let header = ["任务名称", "小组", "时间", "中奖者", "中奖序号"];
+    let mut writer = RowWriter::create(&format, &out_path, &header).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+
+    let result = write_history_rows(&mut writer, &index_items, task_ids.is_some(), |processed| {
+        emit_progress(&app_handle, processed, total)
+    })
+    .and_then(|counts| writer.finish(&out_path).map(|()| counts));
+    let (task_count, row_count) = match result {
+        Ok(counts) => counts,
+        Err(e) => {
+            log::error!("{}", e);
+            let _ = std::fs::remove_file(&out_path);
+            return Err(e.into());
+        }
+    };
+
+    record_export(&out_path, "history", Some(row_count), None);
+    log::info!("已导出 {} 个任务、{} 条中奖记录到 {}", task_count, row_count, out_path);
+    Ok(row_count)
+}
+
+// 按月导出历史记录到格式化的 Excel 工作簿，返回导出的任务数
+//
+// filter 与 query_history_page 的筛选条件相同，省略时导出全部任务。每个月一个工作表（如 2025-01），
+// 每行一个任务：时间（本地时间）、小组、任务名称、中奖人数、中奖者（以“、”分隔），按时间先后排列；
+// 没有任务的月份不生成工作表。工作表使用常量内存模式逐行写出，处理进度通过 export-progress 事件发送。
+#[tauri::command]
+pub async fn export_history_xlsx(
+    app_handle: tauri::AppHandle,
+    filter: Option<serde_json::Value>,
+    output_path: String,
+) -> Result<usize, AppError> {
+    log::info!("导出历史记录到Excel: {}", output_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let filter = crate::query::HistoryFilter::parse(filter.as_ref())?;
+    let (index_items, _) = filter.query(crate::history_db::SortKey::Timestamp, false, 0, None)?;
+    let total = index_items.len();
+
+    let mut months: std::collections::BTreeMap<(i64, i64), Vec<&serde_json::Value>> =
+        std::collections::BTreeMap::new();
+    for index_item in &index_items {
+        let year = index_item.get("year").and_then(|v| v.as_i64());
+        let month = index_item.get("month").and_then(|v| v.as_i64());
+        if let (Some(year), Some(month)) = (year, month) {
+            months.entry((year, month)).or_default().push(index_item);
+        }
+    }
+
+    let header = ["时间", "小组", "任务名称", "中奖人数", "中奖者"];
+    let widths = [20.0, 16.0, 24.0, 10.0, 60.0];
+    let header_format = rust_xlsxwriter::Format::new()
+        .set_bold()
+        .set_background_color(rust_xlsxwriter::Color::RGB(0xDDEBF7))
+        .set_border_bottom(rust_xlsxwriter::FormatBorder::Thin);
+    let wrap_format = rust_xlsxwriter::Format::new().set_text_wrap();
+
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let mut task_count = 0;
+    let mut processed = 0;
+    let result = (|| {
+        for ((year, month), items) in &months {
+            let worksheet = workbook.add_worksheet_with_constant_memory();
+            worksheet
+                .set_name(format!("{}-{:02}", year, month))
+                .map_err(xlsx_error)?;
+            for (col, width) in widths.iter().enumerate() {
+                worksheet.set_column_width(col as u16, *width).map_err(xlsx_error)?;
+            }
+            worksheet
+                .write_row_with_format(0, 0, header, &header_format)
+                .map_err(xlsx_error)?;
+            worksheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+
+            let mut row = 1;
+            for index_item in items {
+                processed += 1;
+                emit_progress(&app_handle, processed, total);
+                let Some(task_data) = history::load_task_data(index_item) else {
+                    continue;
+                };
+                let time = history::parse_timestamp(&task_data)
+                    .map(|ts| ts.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_else(|| str_field(&task_data, "timestamp"));
+                let winners = history::task_winners(&task_data);
+
+                worksheet.write_string(row, 0, time).map_err(xlsx_error)?;
+                worksheet
+                    .write_string(row, 1, str_field(&task_data, "group_name"))
+                    .map_err(xlsx_error)?;
+                worksheet.write_string(row, 2, str_field(&task_data, "name")).map_err(xlsx_error)?;
+                worksheet.write_number(row, 3, winners.len() as f64).map_err(xlsx_error)?;
+                worksheet
+                    .write_string_with_format(row, 4, winners.join("、"), &wrap_format)
+                    .map_err(xlsx_error)?;
+                row += 1;
+                task_count += 1;
+            }
+        }
+        if months.is_empty() {
+            let worksheet = workbook.add_worksheet();
+            worksheet.set_name("中奖记录").map_err(xlsx_error)?;
+            worksheet
+                .write_row_with_format(0, 0, header, &header_format)
+                .map_err(xlsx_error)?;
+        }
+        emit_progress(&app_handle, total, total);
+        workbook.save(&output_path).map_err(xlsx_error)
+    })();
+
+    if let Err(e) = result {
+        log::error!("{}", e);
+        let _ = std::fs::remove_file(&output_path);
+        return Err(e.into());
+    }
+
+    record_export(&output_path, "history-xlsx", Some(task_count), None);
+    log::info!("已导出 {} 个月、{} 个任务到 {}", months.len(), task_count, output_path);
+    Ok(task_count)
+}
+
+// HTML 文本转义
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// 导出名单签到表（可打印的 HTML，字体优先使用系统中文字体以正确显示中文姓名）
+#[tauri::command]
+pub async fn export_roster_checklist(roster_id: String, dest_path: String) -> Result<(), AppError> {
+    log::info!("导出名单签到表: {} -> {}", roster_id, dest_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let lower_path = dest_path.to_lowercase();
+    if !lower_path.ends_with(".html") && !lower_path.ends_with(".htm") {
+        return Err(AppError::InvalidInput("签到表仅支持导出为 HTML 文件".to_string()));
+    }
+
+    let roster = roster::load_roster(&roster_id)?;
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+    let mut rows = String::new();
+    for (i, member) in roster.members.iter().enumerate() {
+        rows.push_str(&format!(
+            "      <tr><td class=\"check\">&#9744;</td><td class=\"index\">{}</td><td>{}</td><td class=\"sign\"></td></tr>\n",
+            i + 1,
+            html_escape(&member.name)
+        ));
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+  <meta charset="UTF-8">
+  <title>{name} 签到表</title>
+  <style>
+    body {{ font-family: "Microsoft YaHei", "PingFang SC", "Noto Sans CJK SC", "SimHei", sans-serif; margin: 24px; }}
+    h1 {{ font-size: 22px; margin-bottom: 4px; }}
+    .meta {{ color: #555; margin-bottom: 16px; }}
+    table {{ width: 100%; border-collapse: collapse; }}
+    th, td {{ border: 1px solid #999; padding: 6px 8px; text-align: left; }}
+    .check {{ width: 40px; text-align: center; font-size: 18px; }}
+    .index {{ width: 50px; text-align: center; }}
+    .sign {{ width: 40%; }}
+    @media print {{ body {{ margin: 0; }} tr {{ page-break-inside: avoid; }} }}
+  </style>
+</head>
+<body>
+  <h1>{name} 签到表</h1>
+  <div class="meta">日期: {date}　共 {count} 人</div>
+  <table>
+    <thead>
+      <tr><th class="check">到</th><th class="index">序号</th><th>姓名</th><th class="sign">签名</th></tr>
+    </thead>
+    <tbody>
+{rows}    </tbody>
+  </table>
+</body>
+</html>
+"#,
+        name = html_escape(&roster.name),
+        date = date,
+        count = roster.members.len(),
+        rows = rows
+    );
+
+    std::fs::write(&dest_path, html).map_err(|e| {
+        let error = format!("写入签到表失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    record_export(&dest_path, "roster-checklist", Some(roster.members.len()), None);
+    log::info!("签到表已导出: {}，共 {} 人", dest_path, roster.members.len());
+    Ok(())
+}
+
+// 列出仍然存在的导出文件（最新的在最前），并清理已被删除的记录
+#[tauri::command]
+pub async fn list_exports() -> Result<Vec<serde_json::Value>, AppError> {
+    log::info!("列出导出记录");
+    crate::app_lock::ensure_unlocked()?;
+
+    let entries = load_export_log()?;
+    let total = entries.len();
+    let existing: Vec<serde_json::Value> = entries
+        .into_iter()
+        .filter(|entry| {
+            entry
+                .get("path")
+                .and_then(|v| v.as_str())
+                .map(|p| std::path::Path::new(p).exists())
+                .unwrap_or(false)
+        })
+        .collect();
+
+    if existing.len() != total {
+        log::info!("清理 {} 条文件已不存在的导出记录", total - existing.len());
+        save_export_log(&existing)?;
+    }
+
+    Ok(existing)
+}
+
+// 在文件管理器中定位文件
+fn reveal_in_file_manager(path: &std::path::Path) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let result = {
+        use std::os::windows::process::CommandExt;
+        std::process::Command::new("explorer")
+            .arg(format!("/select,{}", path.display()))
+            .creation_flags(crate::CREATE_NO_WINDOW)
+            .spawn()
+    };
+
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open").arg("-R").arg(path).spawn();
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = std::process::Command::new("xdg-open")
+        .arg(path.parent().unwrap_or(path))
+        .spawn();
+
+    result.map(|_| ()).map_err(|e| format!("打开文件管理器失败: {}", e))
+}
+
+// 在文件管理器中显示第 index 条导出记录（与 list_exports 返回的顺序一致）
+#[tauri::command]
+pub async fn open_export(index: usize) -> Result<(), AppError> {
+    log::info!("打开导出文件: {}", index);
+    crate::app_lock::ensure_unlocked()?;
+
+    let exports = list_exports().await?;
+    let entry = exports
+        .get(index)
+        .ok_or_else(|| AppError::NotFound(format!("导出记录不存在: {}", index)))?;
+    let path = entry.get("path").and_then(|v| v.as_str()).unwrap_or_default();
+
+    Ok(reveal_in_file_manager(std::path::Path::new(path))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDataRoot;
+    use std::sync::atomic::Ordering;
+
+    // 直接写入 count 个任务文件，返回它们的索引条目
+    fn synthetic_tasks(root: &TempDataRoot, count: usize) -> Vec<serde_json::Value> {
+        let month_dir = root.coredata().join("history").join("2024").join("06");
+        std::fs::create_dir_all(&month_dir).unwrap();
+        (0..count)
+            .map(|i| {
+                let task_data = serde_json::json!({
+                    "id": format!("task-{:04}", i),
+                    "name": format!("抽奖{}", i),
+                    "group_name": "一班",
+                    "timestamp": "2024-06-01T02:00:00.000Z",
+                    "results": [format!("成员{}", i), format!("成员{}", i + 1)],
+                    "total_count": 2
+                });
+                let file_name = format!("task_{:04}.json", i);
+                let content = serde_json::json!({ "task-data": task_data, "year": 2024, "month": 6 });
+                std::fs::write(month_dir.join(&file_name), content.to_string()).unwrap();
+                history::build_index_entry(&task_data, &file_name, 2024, 6)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn export_streams_tasks_one_at_a_time() {
+        let root = TempDataRoot::new("export-stream");
+        let index = synthetic_tasks(&root, 1000);
+        let index_items: Vec<&serde_json::Value> = index.iter().collect();
+        let out_path = root.coredata().join("export.csv");
+        let out = out_path.to_string_lossy().to_string();
+
+        let mut writer = RowWriter::create("csv", &out, &["任务名称", "小组", "时间", "中奖者", "中奖序号"]).unwrap();
+        let reads_before = history::TASK_FILE_READS.load(Ordering::SeqCst);
+        let mut written_midway = 0;
+        let (task_count, row_count) = write_history_rows(&mut writer, &index_items, true, |processed| {
+            // 每处理完一个任务只读取过这一个任务文件，已处理的行陆续写入磁盘
+            assert_eq!(history::TASK_FILE_READS.load(Ordering::SeqCst) - reads_before, processed);
+            if processed == 500 {
+                written_midway = std::fs::metadata(&out_path).unwrap().len();
+            }
+        })
+        .unwrap();
+        writer.finish(&out).unwrap();
+
+        assert_eq!((task_count, row_count), (1000, 2000));
+        assert!(written_midway > 0, "导出进行到一半时应已写出部分内容");
+        let content = std::fs::read_to_string(&out_path).unwrap();
+        assert_eq!(content.lines().count(), 2001);
+        assert!(written_midway < content.len() as u64);
+    }
+
+    #[test]
+    fn export_of_missing_required_task_fails() {
+        let root = TempDataRoot::new("export-missing");
+        let index = synthetic_tasks(&root, 3);
+        std::fs::remove_file(root.coredata().join("history/2024/06/task_0001.json")).unwrap();
+        let index_items: Vec<&serde_json::Value> = index.iter().collect();
+        let out = root.coredata().join("export.csv").to_string_lossy().to_string();
+
+        let mut writer = RowWriter::create("csv", &out, &["任务名称"]).unwrap();
+        assert!(write_history_rows(&mut writer, &index_items, true, |_| {}).is_err());
+        let mut writer = RowWriter::create("csv", &out, &["任务名称"]).unwrap();
+        assert_eq!(write_history_rows(&mut writer, &index_items, false, |_| {}).unwrap(), (2, 4));
+    }
+}
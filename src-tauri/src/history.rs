@@ -0,0 +1,999 @@
+// 历史记录读取辅助函数
+//
+// 历史记录由历史索引（coredata/history.db，见 history_db.rs）和 coredata/history/<年>/<月>/ 下的任务文件组成，
+// 这里集中封装对它们的读取，供统计、导出等功能复用。
+//
+// 年月目录（以及索引中的 year/month）按抽取时的本地日期划分，即前端提交的时间戳所带时区的日期；
+// 任务中的 timestamp 统一保存为 UTC，原时区偏移保存在 timezone_offset_minutes。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::AppError;
+use crate::{encryption, history_db};
+
+// 历史索引锁：对历史索引（以及任务文件）的读-改-写必须在持有该锁时进行
+//
+// 在 main 中通过 app.manage 注册，命令以 State 注入，没有 State 参数的地方用 app_handle.state() 取得。
+#[derive(Default)]
+pub struct HistoryIndexLock(Mutex<()>);
+
+impl HistoryIndexLock {
+    pub fn lock(&self) -> MutexGuard<'_, ()> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// 批量导入期间每累计这么多次索引更新就落盘一次，避免中途崩溃丢失全部索引
+const BULK_FLUSH_INTERVAL: usize = 50;
+
+// 批量导入状态：索引更新先缓存在内存中，结束时一次性写入
+struct BulkImport {
+    index: Vec<serde_json::Value>,
+    unsaved: usize,
+}
+
+// 仅在持有历史索引锁时访问
+static BULK_IMPORT: Mutex<Option<BulkImport>> = Mutex::new(None);
+
+// 数据根目录 coredata（程序目录只读时位于用户目录下，见 storage::init_data_root）
+pub fn coredata_dir() -> Result<PathBuf, String> {
+    Ok(crate::storage::data_base_dir()?.join("coredata"))
+}
+
+// 任务文件所在目录 coredata/history
+pub fn history_dir() -> Result<PathBuf, String> {
+    Ok(coredata_dir()?.join("history"))
+}
+
+// 读取历史索引，最新的在最前面
+pub fn load_history_index() -> Result<Vec<serde_json::Value>, String> {
+    history_db::load()
+}
+
+// 用给定的条目替换整个历史索引（只写入有变化的条目）
+pub fn save_history_index(history_index: &[serde_json::Value]) -> Result<(), String> {
+    history_db::save(history_index)
+}
+
+// 修改历史索引（调用方须持有历史索引锁），返回修改后的条目数
+//
+// 批量导入期间只修改内存中的索引，每 BULK_FLUSH_INTERVAL 次更新落盘一次；否则立即写入数据库。
+pub fn update_history_index<F>(update: F) -> Result<usize, String>
+where
+    F: FnOnce(&mut Vec<serde_json::Value>),
+{
+    let mut bulk = BULK_IMPORT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(bulk) = bulk.as_mut() {
+        update(&mut bulk.index);
+        bulk.unsaved += 1;
+        if bulk.unsaved >= BULK_FLUSH_INTERVAL {
+            save_history_index(&bulk.index)?;
+            bulk.unsaved = 0;
+            log::info!("批量导入中途写入历史索引，当前总数: {}", bulk.index.len());
+        }
+        return Ok(bulk.index.len());
+    }
+
+    history_db::update(update)
+}
+
+// 保存一个任务的索引条目并按保留规则修剪（调用方须持有历史索引锁），返回 (是否为更新, 被移出的条目, 修改后的条目数)
+//
+// 批量导入期间修改内存中的索引；否则只在数据库中写入这一行和被移出的行，不读取整个索引。
+pub fn save_index_entry(
+    entry: serde_json::Value,
+    retention: &HistoryRetention,
+) -> Result<(bool, Vec<serde_json::Value>, usize), String> {
+    if bulk_import_active() {
+        let mut updated = false;
+        let mut evicted = Vec::new();
+        let total = update_history_index(|history_index| {
+            updated = upsert_index_entry(history_index, entry);
+            evicted = apply_retention(history_index, retention);
+        })?;
+        return Ok((updated, evicted, total));
+    }
+    history_db::upsert(entry, retention)
+}
+
+// 从历史索引中移除给定 id 的条目（调用方须持有历史索引锁），返回被移除的条目
+pub fn remove_index_entry(task_id: &str) -> Result<Vec<serde_json::Value>, String> {
+    if bulk_import_active() {
+        let mut removed = Vec::new();
+        update_history_index(|history_index| {
+            history_index.retain(|item| {
+                if item.get("id").and_then(|v| v.as_str()) == Some(task_id) {
+                    removed.push(item.clone());
+                    return false;
+                }
+                true
+            });
+        })?;
+        return Ok(removed);
+    }
+    history_db::remove(task_id)
+}
+
+// 按任务 id 查找索引条目，批量导入期间优先查找内存中的索引
+pub fn find_index_entry(task_id: &str) -> Result<Option<serde_json::Value>, String> {
+    {
+        let bulk = BULK_IMPORT.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(bulk) = bulk.as_ref() {
+            return Ok(bulk
+                .index
+                .iter()
+                .find(|item| item.get("id").and_then(|v| v.as_str()) == Some(task_id))
+                .cloned());
+        }
+    }
+    history_db::find(task_id)
+}
+
+fn bulk_import_active() -> bool {
+    BULK_IMPORT.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+// 结束批量导入并写入缓存的索引，返回是否处于批量导入中（调用方须持有历史索引锁）
+pub fn finish_bulk_import() -> Result<bool, String> {
+    let mut bulk = BULK_IMPORT.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(pending) = bulk.as_ref() else {
+        return Ok(false);
+    };
+
+    // 写入失败时保留缓存，以便重试
+    save_history_index(&pending.index)?;
+    log::info!("批量导入结束，历史索引已写入，总数: {}", pending.index.len());
+    *bulk = None;
+    Ok(true)
+}
+
+// 开始批量导入：此后 save_history_task 只写任务文件，索引更新缓存在内存中
+#[tauri::command]
+pub async fn begin_bulk_import(index_lock: tauri::State<'_, HistoryIndexLock>) -> Result<(), AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let _index_guard = index_lock.lock();
+    let mut bulk = BULK_IMPORT.lock().unwrap_or_else(|e| e.into_inner());
+    if bulk.is_some() {
+        log::warn!("批量导入已在进行中");
+        return Ok(());
+    }
+
+    *bulk = Some(BulkImport {
+        index: load_history_index()?,
+        unsaved: 0,
+    });
+    log::info!("开始批量导入");
+    Ok(())
+}
+
+// 结束批量导入，一次性写入历史索引
+#[tauri::command]
+pub async fn end_bulk_import(index_lock: tauri::State<'_, HistoryIndexLock>) -> Result<(), AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let _index_guard = index_lock.lock();
+    if !finish_bulk_import()? {
+        log::warn!("当前没有进行中的批量导入");
+    }
+    Ok(())
+}
+
+// 为任务生成索引条目，任务文件位于 history/<year>/<month>/<file_name>
+pub fn build_index_entry(
+    task_data: &serde_json::Value,
+    file_name: &str,
+    year: i32,
+    month: u32,
+) -> serde_json::Value {
+    serde_json::json!({
+        "id": task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default(),
+        "name": task_data.get("name").and_then(|v| v.as_str()).unwrap_or("未命名任务"),
+        "timestamp": task_data.get("timestamp").and_then(|v| v.as_str()).unwrap_or_default(),
+        "fileName": file_name,
+        "relativePath": format!("{}/{:02}/{}", year, month, file_name),
+        "totalCount": task_data.get("total_count").unwrap_or(&serde_json::Value::Number(serde_json::Number::from(0))),
+        "groupName": task_data.get("group_name").and_then(|v| v.as_str()).unwrap_or("未知小组"),
+        "tiers": tier_summary(task_data),
+        "year": year,
+        "month": month
+    })
+}
+
+// 分奖项任务的奖项概要 [{ name, count }]，供历史列表展示；普通任务为 null
+fn tier_summary(task_data: &serde_json::Value) -> serde_json::Value {
+    match task_data.get("tiers").and_then(|v| v.as_array()) {
+        Some(tiers) => tiers
+            .iter()
+            .map(|tier| {
+                serde_json::json!({
+                    "name": tier.get("name"),
+                    "count": tier.get("count")
+                })
+            })
+            .collect(),
+        None => serde_json::Value::Null,
+    }
+}
+
+// 按任务 id 更新已有条目（保留收藏标记），或把新条目插入到最前面；返回是否为更新
+pub fn upsert_index_entry(history_index: &mut Vec<serde_json::Value>, mut entry: serde_json::Value) -> bool {
+    let id = entry.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
+    if let Some(pos) = history_index
+        .iter()
+        .position(|item| item.get("id").and_then(|v| v.as_str()) == id.as_deref())
+    {
+        if is_favorite(&history_index[pos]) {
+            if let Some(obj) = entry.as_object_mut() {
+                obj.insert("favorite".to_string(), serde_json::Value::Bool(true));
+            }
+        }
+        history_index[pos] = entry;
+        true
+    } else {
+        history_index.insert(0, entry);
+        false
+    }
+}
+
+// 索引项是否被收藏
+pub fn is_favorite(index_item: &serde_json::Value) -> bool {
+    index_item.get("favorite").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+// 未配置 historyRetention 时索引保留的记录数，0 表示不限制
+pub const DEFAULT_HISTORY_RETENTION: usize = 0;
+
+// 未配置 historyRetentionDays 时保留记录的天数，0 表示不限制
+pub const DEFAULT_HISTORY_RETENTION_DAYS: u64 = 0;
+
+// 历史记录保留规则，两项都为 0 时不限制；同时设置时任一条件超出即移出
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryRetention {
+    // 最多保留的未收藏记录数
+    pub max_records: usize,
+    // 只保留最近这么多天内的记录
+    pub max_age_days: u64,
+}
+
+impl HistoryRetention {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_records == 0 && self.max_age_days == 0
+    }
+
+    // 早于这一时间的记录按天数过期；不限天数或天数大到超出可表示的时间范围时为 None，没有记录过期
+    pub fn cutoff(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        if self.max_age_days == 0 {
+            return None;
+        }
+        let max_age = chrono::Duration::try_days(i64::try_from(self.max_age_days).ok()?)?;
+        chrono::Utc::now().checked_sub_signed(max_age)
+    }
+}
+
+// 从设置中读取历史记录保留规则（historyRetention、historyRetentionDays）
+pub fn history_retention(settings: &serde_json::Value) -> HistoryRetention {
+    HistoryRetention {
+        max_records: settings
+            .get("historyRetention")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_HISTORY_RETENTION),
+        max_age_days: settings
+            .get("historyRetentionDays")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_HISTORY_RETENTION_DAYS),
+    }
+}
+
+// 按保留规则修剪历史索引：只保留最近 max_records 条、max_age_days 天内的未收藏记录
+//
+// 收藏的记录不计入上限、永远不会被移出索引；时间戳无法解析的记录不按天数移出。
+// 返回被移出索引的条目，由调用方删除对应的任务文件。
+pub fn apply_retention(history_index: &mut Vec<serde_json::Value>, retention: &HistoryRetention) -> Vec<serde_json::Value> {
+    if retention.is_unlimited() {
+        return vec![];
+    }
+
+    let cutoff = retention.cutoff();
+    let mut kept = 0;
+    let mut evicted = Vec::new();
+    history_index.retain(|item| {
+        if is_favorite(item) {
+            return true;
+        }
+        let expired = cutoff.is_some_and(|cutoff| parse_timestamp(item).is_some_and(|time| time < cutoff));
+        if !expired {
+            kept += 1;
+        }
+        if expired || (retention.max_records > 0 && kept > retention.max_records) {
+            evicted.push(item.clone());
+            return false;
+        }
+        true
+    });
+    evicted
+}
+
+// 删除被保留规则移出索引的任务文件，避免留下孤立文件；单个文件删除失败只记录日志
+pub fn delete_evicted_task_files(evicted: &[serde_json::Value]) {
+    for item in evicted {
+        match delete_task_file(item) {
+            Ok(()) => log::info!("已删除超出保留规则的任务文件: {:?}", item.get("relativePath")),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+}
+
+// 设置历史记录保留规则并立即按新规则清理，返回被删除的记录数
+//
+// max_records 与 max_age_days 缺省或为 0 表示该项不限制，两项都缺省即不限制。收藏的记录不会被删除。
+#[tauri::command]
+pub async fn set_history_retention(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, HistoryIndexLock>,
+    max_records: Option<usize>,
+    max_age_days: Option<u64>,
+) -> Result<usize, AppError> {
+    log::info!("设置历史记录保留规则: max_records={:?}, max_age_days={:?}", max_records, max_age_days);
+    crate::app_lock::ensure_unlocked()?;
+
+    let retention = HistoryRetention {
+        max_records: max_records.unwrap_or(0),
+        max_age_days: max_age_days.unwrap_or(0),
+    };
+    let mut values = serde_json::Map::new();
+    values.insert("historyRetention".to_string(), serde_json::json!(retention.max_records));
+    values.insert("historyRetentionDays".to_string(), serde_json::json!(retention.max_age_days));
+    crate::settings::update_settings(&app_handle, values)?;
+
+    let _index_guard = index_lock.lock();
+    let evicted = if bulk_import_active() {
+        let mut evicted = Vec::new();
+        update_history_index(|history_index| {
+            evicted = apply_retention(history_index, &retention);
+        })?;
+        evicted
+    } else {
+        history_db::evict(&retention)?
+    };
+    delete_evicted_task_files(&evicted);
+
+    log::info!("按保留规则删除了 {} 条历史记录", evicted.len());
+    Ok(evicted.len())
+}
+
+// 删除索引条目对应的任务文件，文件不存在时忽略
+pub fn delete_task_file(index_item: &serde_json::Value) -> Result<(), String> {
+    let Some(relative_path) = index_item.get("relativePath").and_then(|v| v.as_str()) else {
+        return Ok(());
+    };
+    let task_file_path = history_dir()?.join(relative_path);
+    if !task_file_path.exists() {
+        return Ok(());
+    }
+    crate::watcher::note_own_write(&task_file_path);
+    std::fs::remove_file(&task_file_path).map_err(|e| format!("删除任务文件失败 {}: {}", relative_path, e))
+}
+
+// 设置或取消任务的收藏标记
+#[tauri::command]
+pub async fn set_task_favorite(
+    index_lock: tauri::State<'_, HistoryIndexLock>,
+    task_id: String,
+    favorite: bool,
+) -> Result<(), AppError> {
+    log::info!("设置任务收藏: {} -> {}", task_id, favorite);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+    let mut found = false;
+    update_history_index(|history_index| {
+        let item = history_index
+            .iter_mut()
+            .find(|item| item.get("id").and_then(|v| v.as_str()) == Some(task_id.as_str()));
+        if let Some(obj) = item.and_then(|item| item.as_object_mut()) {
+            obj.insert("favorite".to_string(), serde_json::Value::Bool(favorite));
+            found = true;
+        }
+    })?;
+
+    if !found {
+        return Err(AppError::NotFound(format!("任务不存在: {}", task_id)));
+    }
+    Ok(())
+}
+
+// 测试中统计读取过的任务文件数
+#[cfg(test)]
+pub static TASK_FILE_READS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+// 根据索引项读取完整的任务数据（task-data 字段），文件缺失、无法解析或无法解密时返回错误
+pub fn read_task_data(index_item: &serde_json::Value) -> Result<serde_json::Value, AppError> {
+    #[cfg(test)]
+    TASK_FILE_READS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    let relative_path = index_item
+        .get("relativePath")
+        .and_then(|v| v.as_str())
+        .ok_or("索引项缺少任务文件路径")?;
+    let task_file_path = history_dir()?.join(relative_path);
+
+    let task_content = std::fs::read_to_string(&task_file_path)
+        .map_err(|e| AppError::io(&format!("读取任务文件失败 {}", relative_path), e))?;
+    let task_file_data: serde_json::Value = serde_json::from_str(&task_content)
+        .map_err(|e| AppError::Parse(format!("解析任务文件失败 {}: {}", relative_path, e)))?;
+    Ok(encryption::task_data_of(&task_file_data).map_err(|e| format!("读取任务数据失败 {}: {}", relative_path, e))?)
+}
+
+// 根据索引项加载完整的任务数据，无法加载时记录日志并返回 None
+pub fn load_task_data(index_item: &serde_json::Value) -> Option<serde_json::Value> {
+    index_item.get("relativePath").and_then(|v| v.as_str())?;
+    match read_task_data(index_item) {
+        Ok(task_data) => Some(task_data),
+        Err(e) => {
+            log::error!("{}", e);
+            None
+        }
+    }
+}
+
+// 解析单个任务文件并生成索引条目；年月优先取文件中记录的值，其次取所在目录名
+fn index_entry_from_task_file(path: &std::path::Path, dir_year: i32, dir_month: u32) -> Result<serde_json::Value, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取任务文件失败: {}", e))?;
+    let task_file_data: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析任务文件失败: {}", e))?;
+    let mut task_data = encryption::task_data_of(&task_file_data)?;
+    if task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default().is_empty() {
+        return Err("任务数据缺少 id".to_string());
+    }
+
+    // 缺少 timestamp 的任务用文件的 created-time 代替，保证条目可以排序
+    if task_data.get("timestamp").and_then(|v| v.as_str()).is_none() {
+        if let (Some(created), Some(obj)) = (task_file_data.get("created-time").cloned(), task_data.as_object_mut()) {
+            obj.insert("timestamp".to_string(), created);
+        }
+    }
+
+    let year = task_file_data
+        .get("year")
+        .and_then(|v| v.as_i64())
+        .map(|y| y as i32)
+        .unwrap_or(dir_year);
+    let month = task_file_data
+        .get("month")
+        .and_then(|v| v.as_u64())
+        .map(|m| m as u32)
+        .unwrap_or(dir_month);
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+
+    Ok(build_index_entry(&task_data, file_name, year, month))
+}
+
+// history/<年>/<月>/ 下的全部任务文件（.json）
+pub fn history_task_files() -> Result<Vec<PathBuf>, String> {
+    let subdirs = |dir: &std::path::Path| -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect())
+            .unwrap_or_default()
+    };
+
+    let mut files = Vec::new();
+    for year_dir in subdirs(&history_dir()?) {
+        for month_dir in subdirs(&year_dir) {
+            let Ok(entries) = std::fs::read_dir(&month_dir) else {
+                continue;
+            };
+            files.extend(
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "json")),
+            );
+        }
+    }
+    Ok(files)
+}
+
+// 统计将被清空的任务文件数，供确认对话框显示
+#[tauri::command]
+pub async fn count_history_files() -> Result<usize, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let count = history_task_files()?.len();
+    log::info!("历史任务文件数: {}", count);
+    Ok(count)
+}
+
+// 子目录中名称可解析为数字的项，例如 history/<year> 或 history/<year>/<month>
+fn numbered_subdirs<T: std::str::FromStr>(dir: &std::path::Path) -> Vec<(T, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let number = entry.file_name().to_str()?.parse().ok()?;
+            Some((number, entry.path()))
+        })
+        .collect()
+}
+
+// 根据 history/<year>/<month>/ 下的任务文件重建历史索引，返回恢复的条目数
+//
+// 无法解析的文件记录日志后跳过。原索引可读取时保留其中的收藏标记（损坏的数据库会备份为 history.db.corrupt）。
+#[tauri::command]
+pub async fn rebuild_history_index(index_lock: tauri::State<'_, HistoryIndexLock>) -> Result<usize, AppError> {
+    log::info!("重建历史索引");
+    crate::app_lock::ensure_unlocked()?;
+    // 未解锁时无法读取加密的任务文件，重建会丢失它们的条目
+    encryption::ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+
+    let favorites: std::collections::HashSet<String> = load_history_index()?
+        .iter()
+        .filter(|item| is_favorite(item))
+        .filter_map(|item| item.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()))
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut seen_ids = std::collections::HashSet::new();
+    for (year, year_dir) in numbered_subdirs::<i32>(&history_dir()?) {
+        for (month, month_dir) in numbered_subdirs::<u32>(&year_dir) {
+            let Ok(files) = std::fs::read_dir(&month_dir) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let path = file.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    continue;
+                }
+                match index_entry_from_task_file(&path, year, month) {
+                    Ok(mut entry) => {
+                        let id = entry.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                        if !seen_ids.insert(id.clone()) {
+                            log::warn!("任务 id 重复，跳过: {:?}", path);
+                            continue;
+                        }
+                        if favorites.contains(&id) {
+                            entry["favorite"] = serde_json::json!(true);
+                        }
+                        entries.push(entry);
+                    }
+                    Err(e) => log::warn!("跳过无法解析的任务文件 {:?}: {}", path, e),
+                }
+            }
+        }
+    }
+
+    // 与 save_history_task 保持一致：最新的任务在最前面
+    entries.sort_by_key(|entry| std::cmp::Reverse(parse_timestamp(entry)));
+
+    let count = entries.len();
+    update_history_index(|history_index| *history_index = entries)?;
+
+    log::info!("历史索引重建完成，共恢复 {} 条记录", count);
+    Ok(count)
+}
+
+// 整理历史索引与任务文件，返回 { orphans_deleted, dangling_entries_removed, duplicates_merged }
+//
+// 依次：移除任务文件已不存在的索引条目；id 重复的条目只保留时间最新的一条（任一条收藏则保留收藏标记）；
+// 最后删除 history/<年>/<月>/ 下未被任何索引条目引用的任务文件。可以重复执行，整理后的数据再次执行不会有变化。
+#[tauri::command]
+pub async fn compact_history(index_lock: tauri::State<'_, HistoryIndexLock>) -> Result<serde_json::Value, AppError> {
+    log::info!("整理历史记录");
+    crate::app_lock::ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+    compact_history_index()
+}
+
+// compact_history 的实现（调用方须持有历史索引锁）
+fn compact_history_index() -> Result<serde_json::Value, AppError> {
+    // 批量导入中缓存的条目先落盘，避免把它们的任务文件当作孤立文件
+    finish_bulk_import()?;
+
+    let dir = history_dir()?;
+    let mut dangling_entries_removed = 0;
+    let mut duplicates_merged = 0;
+    let mut referenced = std::collections::HashSet::new();
+    update_history_index(|history_index| {
+        history_index.retain(|item| {
+            let exists = item
+                .get("relativePath")
+                .and_then(|v| v.as_str())
+                .is_some_and(|relative_path| dir.join(relative_path).is_file());
+            if !exists {
+                log::info!("移除任务文件缺失的索引条目: {:?}", item.get("id"));
+                dangling_entries_removed += 1;
+            }
+            exists
+        });
+
+        let mut kept: Vec<serde_json::Value> = Vec::with_capacity(history_index.len());
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for item in history_index.drain(..) {
+            let Some(id) = item.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()) else {
+                kept.push(item);
+                continue;
+            };
+            let Some(&position) = positions.get(&id) else {
+                positions.insert(id, kept.len());
+                kept.push(item);
+                continue;
+            };
+
+            duplicates_merged += 1;
+            let favorite = is_favorite(&kept[position]) || is_favorite(&item);
+            if parse_timestamp(&item) > parse_timestamp(&kept[position]) {
+                kept[position] = item;
+            }
+            if favorite {
+                kept[position]["favorite"] = serde_json::json!(true);
+            }
+            log::info!("合并 id 重复的索引条目: {}", id);
+        }
+        *history_index = kept;
+
+        referenced = history_index
+            .iter()
+            .filter_map(|item| item.get("relativePath").and_then(|v| v.as_str()))
+            .map(|relative_path| dir.join(relative_path))
+            .collect();
+    })?;
+
+    let mut orphans_deleted = 0;
+    for task_file in history_task_files()? {
+        if referenced.contains(&task_file) {
+            continue;
+        }
+        crate::watcher::note_own_write(&task_file);
+        match std::fs::remove_file(&task_file) {
+            Ok(()) => {
+                log::info!("删除未被索引引用的任务文件: {:?}", task_file);
+                orphans_deleted += 1;
+            }
+            Err(e) => log::error!("删除任务文件失败 {:?}: {}", task_file, e),
+        }
+    }
+
+    log::info!(
+        "历史记录整理完成: 删除孤立文件 {} 个，移除缺失文件的条目 {} 条，合并重复条目 {} 条",
+        orphans_deleted,
+        dangling_entries_removed,
+        duplicates_merged
+    );
+    Ok(serde_json::json!({
+        "orphans_deleted": orphans_deleted,
+        "dangling_entries_removed": dangling_entries_removed,
+        "duplicates_merged": duplicates_merged
+    }))
+}
+
+// 任务文件无法加载时，根据索引信息生成的备用数据
+pub fn fallback_task_data(index_item: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "id": index_item.get("id"),
+        "name": index_item.get("name"),
+        "timestamp": index_item.get("timestamp"),
+        "total_count": index_item.get("totalCount"),
+        "group_name": index_item.get("groupName"),
+        "results": [],
+        "file_path": index_item.get("fileName"),
+        "edit_protected": false,
+        "edit_password": ""
+    })
+}
+
+// 分页获取历史记录数据：只读取索引中 [offset, offset + limit) 范围内的任务文件
+//
+// 索引已按时间倒序排列，返回该页的完整任务数据以及索引总条数。
+#[tauri::command]
+pub async fn get_history_data_paged(offset: usize, limit: usize) -> Result<serde_json::Value, AppError> {
+    log::info!("分页获取历史记录数据: offset={}, limit={}", offset, limit);
+    crate::app_lock::ensure_unlocked()?;
+    encryption::ensure_unlocked()?;
+
+    let (page, total) = history_db::query(
+        &history_db::IndexFilter::default(),
+        history_db::SortKey::Seq,
+        true,
+        offset,
+        Some(limit),
+    )?;
+
+    let items: Vec<serde_json::Value> = page
+        .iter()
+        .filter(|index_item| index_item.get("relativePath").and_then(|v| v.as_str()).is_some())
+        .map(|index_item| {
+            let mut task_data = load_task_data(index_item).unwrap_or_else(|| fallback_task_data(index_item));
+            crate::protection::redact(&mut task_data);
+            task_data
+        })
+        .collect();
+
+    log::info!("返回 {} 条历史记录，共 {} 条", items.len(), total);
+    Ok(serde_json::json!({ "items": items, "total": total }))
+}
+
+// 后端生成的任务 id：毫秒时间戳加随机后缀，避免与同一毫秒内前端保存的任务 id 冲突
+pub fn new_task_id() -> String {
+    use rand::Rng;
+    format!("{}-{:06x}", chrono::Utc::now().timestamp_millis(), rand::thread_rng().gen_range(0..0x100_0000u32))
+}
+
+// 按任务 id 查找任务文件路径
+pub fn find_task_file(task_id: &str) -> Result<Option<PathBuf>, String> {
+    let relative_path = find_index_entry(task_id)?
+        .and_then(|item| item.get("relativePath").and_then(|v| v.as_str()).map(|s| s.to_string()));
+    match relative_path {
+        Some(relative_path) => Ok(Some(history_dir()?.join(relative_path))),
+        None => Ok(None),
+    }
+}
+
+// 按任务 id 查找并加载完整任务数据
+pub fn find_task(task_id: &str) -> Result<Option<serde_json::Value>, String> {
+    Ok(find_index_entry(task_id)?.as_ref().and_then(load_task_data))
+}
+
+// 两份 id 相同的任务数据是否为同一次抽取：编辑后重新保存不会改变时间戳，时间戳不同说明是 id 冲突的另一个任务
+//
+// 任一方没有可解析的时间戳时无法判断，按同一任务处理。
+pub fn is_same_logical_task(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    match (parse_timestamp(a), parse_timestamp(b)) {
+        (Some(ta), Some(tb)) => ta == tb,
+        _ => true,
+    }
+}
+
+// 直接读取任务文件中的 task-data，文件不存在或无法解析时返回 None
+pub fn read_task_file(path: &std::path::Path) -> Option<serde_json::Value> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let task_file_data: serde_json::Value = serde_json::from_str(&content).ok()?;
+    encryption::task_data_of(&task_file_data).ok()
+}
+
+// 任务中的中奖者列表（按抽取顺序）
+pub fn task_winners(task_data: &serde_json::Value) -> Vec<String> {
+    task_data
+        .get("results")
+        .and_then(|v| v.as_array())
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|r| r.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 比较姓名时使用的规范化形式：去除首尾空白并忽略大小写
+pub fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+// 解析索引项或任务中的 RFC3339 时间戳
+pub fn parse_timestamp(value: &serde_json::Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    let timestamp = value.get("timestamp").and_then(|v| v.as_str())?;
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+}
+
+// 校验并规范化任务的 timestamp：统一转换为 UTC、毫秒精度的 RFC3339 形式写回任务数据
+//
+// 原始时间戳带有非零时区偏移时，另存偏移分钟数到 timezone_offset_minutes，返回规范化后的 UTC 时间。
+pub fn normalize_task_timestamp(
+    task_data: &mut serde_json::Value,
+) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let timestamp = task_data
+        .get("timestamp")
+        .and_then(|v| v.as_str())
+        .ok_or("缺少时间戳")?;
+    let datetime = chrono::DateTime::parse_from_rfc3339(timestamp.trim())
+        .map_err(|e| format!("时间戳解析失败 {}: {}", timestamp, e))?;
+
+    let offset_minutes = datetime.offset().local_minus_utc() / 60;
+    let utc = datetime.with_timezone(&chrono::Utc);
+
+    let obj = task_data.as_object_mut().ok_or("任务数据必须是 JSON 对象")?;
+    obj.insert(
+        "timestamp".to_string(),
+        serde_json::Value::String(utc.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+    );
+    if offset_minutes != 0 {
+        obj.insert("timezone_offset_minutes".to_string(), serde_json::json!(offset_minutes));
+    }
+
+    Ok(utc)
+}
+
+// 任务抽取时的本地时间：UTC 时间加上 timezone_offset_minutes（未记录偏移时即 UTC），用于划分年月目录
+pub fn task_local_datetime(
+    task_data: &serde_json::Value,
+    utc: &chrono::DateTime<chrono::Utc>,
+) -> chrono::DateTime<chrono::FixedOffset> {
+    let offset_minutes = task_data
+        .get("timezone_offset_minutes")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+    let offset = i32::try_from(offset_minutes * 60)
+        .ok()
+        .and_then(chrono::FixedOffset::east_opt)
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).expect("零偏移总是有效"));
+    utc.with_timezone(&offset)
+}
+
+// 解析命令参数中的可选起始时间（RFC3339），格式无效时返回错误
+pub fn parse_since(since: Option<&str>) -> Result<Option<chrono::DateTime<chrono::Utc>>, AppError> {
+    match since.map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        None => Ok(None),
+        Some(s) => chrono::DateTime::parse_from_rfc3339(s)
+            .map(|dt| Some(dt.with_timezone(&chrono::Utc)))
+            .map_err(|e| AppError::InvalidInput(format!("时间格式无效 {}: {}", s, e))),
+    }
+}
+
+// 统计每个人（按规范化姓名）的中奖次数，可限定起始时间
+pub fn win_counts_since(
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<HashMap<String, usize>, String> {
+    let mut counts = HashMap::new();
+
+    for index_item in load_history_index()? {
+        // 先用索引中的时间过滤，避免读取窗口外的任务文件
+        if let Some(since) = since {
+            match parse_timestamp(&index_item) {
+                Some(ts) if ts >= since => {}
+                _ => continue,
+            }
+        }
+
+        if let Some(task_data) = load_task_data(&index_item) {
+            for winner in task_winners(&task_data) {
+                *counts.entry(normalize_name(&winner)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+// 检查任务数据的一致性，返回发现的问题（为空表示一致）
+//
+// - results 数量必须等于 total_count
+// - 携带 participants 且不允许重复中奖时，中奖人数不能超过参与人数
+// - 携带 tiers 时，各奖项中奖者合计必须等于结果数量
+pub fn check_task_consistency(task_data: &serde_json::Value) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    let result_count = task_data
+        .get("results")
+        .and_then(|v| v.as_array())
+        .map(|a| a.len());
+    let total_count = task_data.get("total_count").and_then(|v| v.as_u64());
+
+    if let (Some(results), Some(total)) = (result_count, total_count) {
+        if results as u64 != total {
+            issues.push(format!("结果数量 {} 与 total_count {} 不一致", results, total));
+        }
+    }
+
+    let allow_repeat = task_data
+        .get("allow_repeat")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if let Some(participants) = task_data.get("participants").and_then(|v| v.as_array()) {
+        let winners = result_count.unwrap_or(0);
+        if !allow_repeat && winners > participants.len() {
+            issues.push(format!(
+                "中奖人数 {} 超过参与人数 {}",
+                winners,
+                participants.len()
+            ));
+        }
+    }
+
+    // 分奖项任务：各奖项中奖者合计必须等于 results 数量
+    if let Some(tiers) = task_data.get("tiers").and_then(|v| v.as_array()) {
+        let tier_winners: usize = tiers
+            .iter()
+            .filter_map(|t| t.get("winners").and_then(|v| v.as_array()))
+            .map(|w| w.len())
+            .sum();
+        if tier_winners != result_count.unwrap_or(0) {
+            issues.push(format!(
+                "各奖项中奖人数合计 {} 与结果数量 {} 不一致",
+                tier_winners,
+                result_count.unwrap_or(0)
+            ));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDataRoot;
+
+    // 写入任务文件并返回它的索引条目
+    fn write_task(id: &str, file_name: &str, timestamp: &str) -> serde_json::Value {
+        let task_data = serde_json::json!({
+            "id": id,
+            "name": "整理测试",
+            "timestamp": timestamp,
+            "results": ["张三"],
+            "total_count": 1
+        });
+        let month_dir = history_dir().unwrap().join("2024").join("06");
+        std::fs::create_dir_all(&month_dir).unwrap();
+        let content = serde_json::json!({ "task-data": task_data, "year": 2024, "month": 6 });
+        std::fs::write(month_dir.join(file_name), content.to_string()).unwrap();
+        build_index_entry(&task_data, file_name, 2024, 6)
+    }
+
+    fn summary(orphans: usize, dangling: usize, duplicates: usize) -> serde_json::Value {
+        serde_json::json!({
+            "orphans_deleted": orphans,
+            "dangling_entries_removed": dangling,
+            "duplicates_merged": duplicates
+        })
+    }
+
+    #[test]
+    fn compaction_cleans_orphans_dangling_entries_and_duplicates() {
+        let _root = TempDataRoot::new("compact");
+        let kept = write_task("kept", "kept.json", "2024-06-01T02:00:00.000Z");
+        let kept_content = std::fs::read(history_dir().unwrap().join("2024/06/kept.json")).unwrap();
+        // 索引中有条目但任务文件已不存在
+        let dangling = write_task("dangling", "dangling.json", "2024-06-02T02:00:00.000Z");
+        std::fs::remove_file(history_dir().unwrap().join("2024/06/dangling.json")).unwrap();
+        // id 重复的两条，旧的一条已收藏
+        let mut older = write_task("dup", "dup_old.json", "2024-06-03T02:00:00.000Z");
+        older["favorite"] = serde_json::json!(true);
+        let newer = write_task("dup", "dup_new.json", "2024-06-04T02:00:00.000Z");
+        // 没有被任何索引条目引用的任务文件
+        write_task("orphan", "orphan.json", "2024-06-05T02:00:00.000Z");
+        save_history_index(&[kept.clone(), dangling, older, newer]).unwrap();
+
+        // 被合并掉的旧条目的任务文件也成为孤立文件
+        assert_eq!(compact_history_index().unwrap(), summary(2, 1, 1));
+
+        let index = load_history_index().unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[0], kept);
+        assert_eq!(index[1]["relativePath"], "2024/06/dup_new.json");
+        assert_eq!(index[1]["favorite"], true);
+
+        let mut files: Vec<String> = history_task_files()
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["dup_new.json", "kept.json"]);
+        assert_eq!(std::fs::read(history_dir().unwrap().join("2024/06/kept.json")).unwrap(), kept_content);
+
+        // 再次执行不会有变化
+        assert_eq!(compact_history_index().unwrap(), summary(0, 0, 0));
+        assert_eq!(load_history_index().unwrap(), index);
+    }
+
+    #[test]
+    fn huge_retention_days_expire_nothing() {
+        for max_age_days in [u64::MAX, 1 << 40, 100_000_000] {
+            let retention = HistoryRetention { max_records: 0, max_age_days };
+            assert!(retention.cutoff().is_none(), "{}", max_age_days);
+            let mut index = vec![serde_json::json!({ "id": "old", "timestamp": "1970-01-01T00:00:00Z" })];
+            assert!(apply_retention(&mut index, &retention).is_empty());
+            assert_eq!(index.len(), 1);
+        }
+        let retention = HistoryRetention { max_records: 0, max_age_days: 30 };
+        let mut index = vec![serde_json::json!({ "id": "old", "timestamp": "1970-01-01T00:00:00Z" })];
+        assert_eq!(apply_retention(&mut index, &retention).len(), 1);
+    }
+}
@@ -0,0 +1,649 @@
+// 历史索引数据库
+//
+// 历史索引保存在 coredata/history.db（SQLite）的 history_index 表中，每个索引条目一行：
+// seq 决定列表顺序（越大越靠前，即越新），entry 为条目的 JSON，名称、小组、时间、结果数与收藏标记另存为列，
+// 筛选、排序与分页直接在 SQL 中完成（见 query），不必把整个索引读入内存。任务数据仍保存在 history/<年>/<月>/ 下的任务文件中。
+// 修改索引时只写入有变化的行，保存一个任务不再需要重写整个索引。
+//
+// 旧版本的 coredata/history.json 在首次打开数据库时导入，随后改名为 history.json.migrated 保留。
+// 备份与配置档案中仍以 history.json 的形式保存索引（见 backup.rs），新旧版本的归档可以互相恢复。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::history;
+
+// 数据库结构版本（PRAGMA user_version），结构变化时递增并在 init_schema 中迁移
+const SCHEMA_VERSION: i64 = 2;
+
+// 其他连接正在写入时等待的时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 数据库文件名
+pub const DB_FILE_NAME: &str = "history.db";
+
+// 数据库及迁移留下的文件，不直接打包进备份与配置档案（索引以 history.json 的形式单独写入）
+pub const DB_ENTRIES: &[&str] = &["history.db", "history.db-journal", "history.json.migrated"];
+
+// 历史索引数据库 coredata/history.db
+pub fn db_path() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join(DB_FILE_NAME))
+}
+
+fn is_corrupt(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::NotADatabase) | Some(rusqlite::ErrorCode::DatabaseCorrupt)
+    )
+}
+
+// 打开数据库并读取结构版本
+fn connect(path: &std::path::Path) -> rusqlite::Result<(Connection, i64)> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    let version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok((conn, version))
+}
+
+// 打开历史索引数据库，必要时建表并导入旧的 history.json
+//
+// 文件已损坏时改名为 history.db.corrupt 备份后重新创建，与 storage::read_json_or_recover 的处理一致；
+// 之后可以用 rebuild_history_index 从任务文件重建索引。
+fn open() -> Result<Connection, String> {
+    let path = db_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建数据目录失败: {}", e))?;
+    }
+
+    let (conn, version) = match connect(&path) {
+        Err(e) if is_corrupt(&e) => {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".corrupt");
+            log::warn!("历史索引数据库已损坏（{}），备份到 {:?} 后重新开始", e, backup);
+            std::fs::rename(&path, &backup).map_err(|e| format!("备份损坏的历史索引失败: {}", e))?;
+            connect(&path)
+        }
+        other => other,
+    }
+    .map_err(|e| {
+        let error = format!("打开历史索引失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    if version < SCHEMA_VERSION {
+        init_schema(&conn, version)?;
+    }
+    Ok(conn)
+}
+
+// 建表或升级表结构，新建时把旧版本的 history.json 导入数据库（同一事务内完成，中途失败下次打开时重新执行）
+//
+// 版本 1 的表只有 seq、id、entry 三列，升级时补上其余列并从 entry 中回填。
+fn init_schema(conn: &Connection, version: i64) -> Result<(), String> {
+    let init_error = |e: rusqlite::Error| format!("初始化历史索引失败: {}", e);
+    let legacy_path = history::coredata_dir()?.join("history.json");
+    let legacy: Vec<serde_json::Value> = if version == 0 {
+        crate::storage::read_json_or_recover(&legacy_path)?
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let tx = conn.unchecked_transaction().map_err(init_error)?;
+    if version == 1 {
+        tx.execute_batch(
+            "ALTER TABLE history_index ADD COLUMN name TEXT NOT NULL DEFAULT '';
+             ALTER TABLE history_index ADD COLUMN name_lower TEXT NOT NULL DEFAULT '';
+             ALTER TABLE history_index ADD COLUMN group_name TEXT;
+             ALTER TABLE history_index ADD COLUMN ts INTEGER;
+             ALTER TABLE history_index ADD COLUMN total_count INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE history_index ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;",
+        )
+        .map_err(init_error)?;
+        backfill_columns(&tx)?;
+    } else {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history_index (
+                 seq INTEGER NOT NULL,
+                 id TEXT NOT NULL,
+                 name TEXT NOT NULL DEFAULT '',
+                 name_lower TEXT NOT NULL DEFAULT '',
+                 group_name TEXT,
+                 ts INTEGER,
+                 total_count INTEGER NOT NULL DEFAULT 0,
+                 favorite INTEGER NOT NULL DEFAULT 0,
+                 entry TEXT NOT NULL
+             );",
+        )
+        .map_err(init_error)?;
+    }
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS history_index_seq ON history_index (seq);
+         CREATE INDEX IF NOT EXISTS history_index_id ON history_index (id);
+         CREATE INDEX IF NOT EXISTS history_index_ts ON history_index (ts);
+         CREATE INDEX IF NOT EXISTS history_index_group ON history_index (group_name);",
+    )
+    .map_err(init_error)?;
+    if version == 0 {
+        sync_entries(&tx, &legacy)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(init_error)?;
+    tx.commit().map_err(init_error)?;
+
+    if version == 0 && legacy_path.exists() {
+        let migrated = legacy_path.with_extension("json.migrated");
+        match std::fs::rename(&legacy_path, &migrated) {
+            Ok(()) => log::info!("已将 history.json 中的 {} 条记录迁移到 {}，原文件保留为 {:?}", legacy.len(), DB_FILE_NAME, migrated),
+            Err(e) => log::warn!("已迁移 history.json，但改名原文件失败: {}", e),
+        }
+    }
+    Ok(())
+}
+
+// 从 entry 回填版本 1 的表中缺少的列，无法解析的行保持默认值
+fn backfill_columns(conn: &Connection) -> Result<(), String> {
+    let init_error = |e: rusqlite::Error| format!("初始化历史索引失败: {}", e);
+    let rows: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare("SELECT rowid, entry FROM history_index").map_err(init_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(init_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(init_error)?
+    };
+    for (rowid, text) in rows {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+        let columns = Columns::of(&entry);
+        conn.prepare_cached(
+            "UPDATE history_index SET name = ?1, name_lower = ?2, group_name = ?3, ts = ?4, total_count = ?5, favorite = ?6
+             WHERE rowid = ?7",
+        )
+        .and_then(|mut stmt| {
+            stmt.execute(params![
+                columns.name,
+                columns.name_lower,
+                columns.group_name,
+                columns.ts,
+                columns.total_count,
+                columns.favorite,
+                rowid
+            ])
+        })
+        .map_err(init_error)?;
+    }
+    Ok(())
+}
+
+// 从索引条目中取出单独存为列的字段
+struct Columns {
+    name: String,
+    name_lower: String,
+    group_name: Option<String>,
+    // 抽取时间（UTC 毫秒），无法解析时为 NULL
+    ts: Option<i64>,
+    total_count: i64,
+    favorite: bool,
+}
+
+impl Columns {
+    fn of(entry: &serde_json::Value) -> Self {
+        let name = entry.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        Columns {
+            name_lower: name.to_lowercase(),
+            name,
+            group_name: entry.get("groupName").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            ts: history::parse_timestamp(entry).map(|ts| ts.timestamp_millis()),
+            total_count: entry
+                .get("totalCount")
+                .and_then(|v| v.as_u64())
+                .map(|n| i64::try_from(n).unwrap_or(i64::MAX))
+                .unwrap_or(0),
+            favorite: history::is_favorite(entry),
+        }
+    }
+}
+
+// 插入一行索引条目
+fn insert_row(conn: &Connection, seq: i64, id: &str, entry: &serde_json::Value, text: &str) -> rusqlite::Result<usize> {
+    let columns = Columns::of(entry);
+    conn.prepare_cached(
+        "INSERT INTO history_index (seq, id, name, name_lower, group_name, ts, total_count, favorite, entry)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?
+    .execute(params![
+        seq,
+        id,
+        columns.name,
+        columns.name_lower,
+        columns.group_name,
+        columns.ts,
+        columns.total_count,
+        columns.favorite,
+        text
+    ])
+}
+
+// 改写已有的一行
+fn update_row(conn: &Connection, rowid: i64, seq: i64, entry: &serde_json::Value, text: &str) -> rusqlite::Result<usize> {
+    let columns = Columns::of(entry);
+    conn.prepare_cached(
+        "UPDATE history_index
+         SET seq = ?1, name = ?2, name_lower = ?3, group_name = ?4, ts = ?5, total_count = ?6, favorite = ?7, entry = ?8
+         WHERE rowid = ?9",
+    )?
+    .execute(params![
+        seq,
+        columns.name,
+        columns.name_lower,
+        columns.group_name,
+        columns.ts,
+        columns.total_count,
+        columns.favorite,
+        text,
+        rowid
+    ])
+}
+
+fn entry_id(entry: &serde_json::Value) -> String {
+    entry.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+}
+
+// 读取全部索引条目，最新的在最前面；无法解析的行记录日志后跳过
+fn load_entries(conn: &Connection) -> Result<Vec<serde_json::Value>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT entry FROM history_index ORDER BY seq DESC, rowid DESC")
+        .map_err(|e| format!("读取历史索引失败: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("读取历史索引失败: {}", e))?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let text = row.map_err(|e| format!("读取历史索引失败: {}", e))?;
+        match serde_json::from_str(&text) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!("跳过无法解析的历史索引条目: {}", e),
+        }
+    }
+    Ok(entries)
+}
+
+// 数据库中已有的一行
+struct StoredRow {
+    rowid: i64,
+    seq: i64,
+    entry: String,
+}
+
+// 把数据库中的索引改为 entries（最新的在最前面），只写入有变化的行，返回写入的行数
+//
+// 从最旧的条目开始按 id 匹配已有的行（同一 id 有多行时优先匹配内容相同的），
+// 已有行的 seq 仍满足顺序时保持不变，因此在最前面插入新任务只需写入一行。
+fn sync_entries(conn: &Connection, entries: &[serde_json::Value]) -> Result<usize, String> {
+    let db_error = |e: rusqlite::Error| format!("保存历史索引失败: {}", e);
+
+    let mut existing: HashMap<String, Vec<StoredRow>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare_cached("SELECT rowid, seq, id, entry FROM history_index ORDER BY seq ASC, rowid ASC")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(2)?,
+                    StoredRow {
+                        rowid: row.get(0)?,
+                        seq: row.get(1)?,
+                        entry: row.get(3)?,
+                    },
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (id, stored) = row.map_err(db_error)?;
+            existing.entry(id).or_default().push(stored);
+        }
+    }
+
+    let mut changed = 0;
+    let mut last_seq = 0;
+    for entry in entries.iter().rev() {
+        let id = entry_id(entry);
+        let text = serde_json::to_string(entry).map_err(|e| format!("序列化索引失败: {}", e))?;
+        let matched = existing.get_mut(&id).filter(|rows| !rows.is_empty()).map(|rows| {
+            let pos = rows.iter().position(|row| row.entry == text).unwrap_or(0);
+            rows.remove(pos)
+        });
+
+        match matched {
+            Some(row) => {
+                let seq = if row.seq > last_seq { row.seq } else { last_seq + 1 };
+                if seq != row.seq || row.entry != text {
+                    update_row(conn, row.rowid, seq, entry, &text).map_err(db_error)?;
+                    changed += 1;
+                }
+                last_seq = seq;
+            }
+            None => {
+                last_seq += 1;
+                insert_row(conn, last_seq, &id, entry, &text).map_err(db_error)?;
+                changed += 1;
+            }
+        }
+    }
+
+    for row in existing.into_values().flatten() {
+        conn.prepare_cached("DELETE FROM history_index WHERE rowid = ?1")
+            .and_then(|mut stmt| stmt.execute(params![row.rowid]))
+            .map_err(db_error)?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+// 在事务中把索引改为 entries
+fn save_entries(conn: &Connection, entries: &[serde_json::Value]) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("保存历史索引失败: {}", e))?;
+    if sync_entries(&tx, entries)? > 0 {
+        crate::watcher::note_own_write(&db_path()?);
+    }
+    tx.commit().map_err(|e| {
+        let error = format!("保存历史索引失败: {}", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 读取历史索引，最新的在最前面
+pub fn load() -> Result<Vec<serde_json::Value>, String> {
+    load_entries(&open()?)
+}
+
+// 用 entries 替换整个历史索引
+pub fn save(entries: &[serde_json::Value]) -> Result<(), String> {
+    save_entries(&open()?, entries)
+}
+
+// 读取、修改并保存历史索引，返回修改后的条目数（调用方须持有历史索引锁）
+pub fn update<F>(update: F) -> Result<usize, String>
+where
+    F: FnOnce(&mut Vec<serde_json::Value>),
+{
+    let conn = open()?;
+    let mut entries = load_entries(&conn)?;
+    update(&mut entries);
+    save_entries(&conn, &entries)?;
+    Ok(entries.len())
+}
+
+fn parse_entries(rows: Vec<String>) -> Vec<serde_json::Value> {
+    rows.into_iter()
+        .filter_map(|text| match serde_json::from_str(&text) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("跳过无法解析的历史索引条目: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+// 按任务 id 查找索引条目（同一 id 有多行时取最新的一行）
+pub fn find(id: &str) -> Result<Option<serde_json::Value>, String> {
+    let conn = open()?;
+    let text: Option<String> = conn
+        .query_row(
+            "SELECT entry FROM history_index WHERE id = ?1 ORDER BY seq DESC, rowid DESC LIMIT 1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("读取历史索引失败: {}", e))?;
+    Ok(parse_entries(text.into_iter().collect()).pop())
+}
+
+// 按任务 id 更新已有的一行（保留收藏标记与位置），或把新条目插入到最前面，再按保留规则修剪
+//
+// 在一个事务中完成，返回 (是否为更新, 被移出的条目, 修改后的条目数)；调用方须持有历史索引锁。
+pub fn upsert(
+    mut entry: serde_json::Value,
+    retention: &history::HistoryRetention,
+) -> Result<(bool, Vec<serde_json::Value>, usize), String> {
+    let db_error = |e: rusqlite::Error| format!("保存历史索引失败: {}", e);
+    let conn = open()?;
+    let tx = conn.unchecked_transaction().map_err(db_error)?;
+
+    let id = entry_id(&entry);
+    let existing: Option<(i64, i64, bool)> = tx
+        .query_row(
+            "SELECT rowid, seq, favorite FROM history_index WHERE id = ?1 ORDER BY seq DESC, rowid DESC LIMIT 1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(db_error)?;
+    let updated = match existing {
+        Some((rowid, seq, favorite)) => {
+            if favorite {
+                if let Some(obj) = entry.as_object_mut() {
+                    obj.insert("favorite".to_string(), serde_json::Value::Bool(true));
+                }
+            }
+            let text = serde_json::to_string(&entry).map_err(|e| format!("序列化索引失败: {}", e))?;
+            update_row(&tx, rowid, seq, &entry, &text).map_err(db_error)?;
+            true
+        }
+        None => {
+            let seq: i64 = tx
+                .query_row("SELECT COALESCE(MAX(seq), 0) + 1 FROM history_index", [], |row| row.get(0))
+                .map_err(db_error)?;
+            let text = serde_json::to_string(&entry).map_err(|e| format!("序列化索引失败: {}", e))?;
+            insert_row(&tx, seq, &id, &entry, &text).map_err(db_error)?;
+            false
+        }
+    };
+    let evicted = evict_rows(&tx, retention)?;
+    let total: i64 = tx
+        .query_row("SELECT COUNT(*) FROM history_index", [], |row| row.get(0))
+        .map_err(db_error)?;
+
+    crate::watcher::note_own_write(&db_path()?);
+    tx.commit().map_err(|e| {
+        let error = format!("保存历史索引失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    Ok((updated, evicted, total as usize))
+}
+
+// 删除给定 id 的全部索引条目，返回被删除的条目；调用方须持有历史索引锁
+pub fn remove(id: &str) -> Result<Vec<serde_json::Value>, String> {
+    let db_error = |e: rusqlite::Error| format!("保存历史索引失败: {}", e);
+    let conn = open()?;
+    let tx = conn.unchecked_transaction().map_err(db_error)?;
+    let rows: Vec<String> = {
+        let mut stmt = tx
+            .prepare_cached("SELECT entry FROM history_index WHERE id = ?1")
+            .map_err(db_error)?;
+        let rows = stmt.query_map(params![id], |row| row.get(0)).map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)?
+    };
+    if !rows.is_empty() {
+        tx.execute("DELETE FROM history_index WHERE id = ?1", params![id])
+            .map_err(db_error)?;
+        crate::watcher::note_own_write(&db_path()?);
+    }
+    tx.commit().map_err(db_error)?;
+    Ok(parse_entries(rows))
+}
+
+// 按保留规则移出索引条目并返回它们，规则与 history::apply_retention 相同；调用方须持有历史索引锁
+pub fn evict(retention: &history::HistoryRetention) -> Result<Vec<serde_json::Value>, String> {
+    let conn = open()?;
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("保存历史索引失败: {}", e))?;
+    let evicted = evict_rows(&tx, retention)?;
+    if !evicted.is_empty() {
+        crate::watcher::note_own_write(&db_path()?);
+    }
+    tx.commit().map_err(|e| format!("保存历史索引失败: {}", e))?;
+    Ok(evicted)
+}
+
+// 删除超出保留规则的未收藏行：早于 max_age_days 的行，以及其余行中按顺序排在 max_records 之后的行
+fn evict_rows(conn: &Connection, retention: &history::HistoryRetention) -> Result<Vec<serde_json::Value>, String> {
+    let db_error = |e: rusqlite::Error| format!("保存历史索引失败: {}", e);
+    if retention.is_unlimited() {
+        return Ok(vec![]);
+    }
+
+    // 无天数限制时 cutoff 取最小值，没有行会因时间过期
+    let cutoff = retention.cutoff().map_or(i64::MIN, |cutoff| cutoff.timestamp_millis());
+    let max_records = match retention.max_records {
+        0 => i64::MAX,
+        n => i64::try_from(n).unwrap_or(i64::MAX),
+    };
+
+    let rows: Vec<(i64, String)> = {
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT rowid, entry FROM history_index
+                 WHERE favorite = 0 AND ts IS NOT NULL AND ts < ?1
+                 UNION ALL
+                 SELECT row_id, entry FROM (
+                     SELECT rowid AS row_id, entry FROM history_index
+                     WHERE favorite = 0 AND (ts IS NULL OR ts >= ?1)
+                     ORDER BY seq DESC, rowid DESC
+                     LIMIT -1 OFFSET ?2
+                 )",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![cutoff, max_records], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(db_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(db_error)?
+    };
+    for (rowid, _) in &rows {
+        conn.prepare_cached("DELETE FROM history_index WHERE rowid = ?1")
+            .and_then(|mut stmt| stmt.execute(params![rowid]))
+            .map_err(db_error)?;
+    }
+    Ok(parse_entries(rows.into_iter().map(|(_, text)| text).collect()))
+}
+
+// 排序字段：Seq 为索引顺序（保存顺序），其余分别对应 ts、name、total_count 列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Seq,
+    Timestamp,
+    Name,
+    TotalCount,
+}
+
+impl SortKey {
+    fn column(self) -> &'static str {
+        match self {
+            SortKey::Seq => "seq",
+            SortKey::Timestamp => "ts",
+            SortKey::Name => "name",
+            SortKey::TotalCount => "total_count",
+        }
+    }
+}
+
+// 在数据库中执行的筛选条件，为空的条件不参与筛选
+//
+// name_terms 须全部出现在任务名称中（调用方传入小写形式）；since/until 含两端，时间无法解析的条目不满足时间条件。
+#[derive(Debug, Default)]
+pub struct IndexFilter<'a> {
+    pub name_terms: &'a [String],
+    pub group_name: Option<&'a str>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    pub favorite: Option<bool>,
+}
+
+impl IndexFilter<'_> {
+    // 生成 WHERE 子句及其参数
+    fn where_clause(&self) -> (String, Vec<rusqlite::types::Value>) {
+        use rusqlite::types::Value;
+        let mut clauses = Vec::new();
+        let mut values = Vec::new();
+        for term in self.name_terms {
+            clauses.push("instr(name_lower, ?) > 0");
+            values.push(Value::Text(term.clone()));
+        }
+        if let Some(group_name) = self.group_name {
+            clauses.push("group_name = ?");
+            values.push(Value::Text(group_name.to_string()));
+        }
+        if let Some(since) = self.since {
+            clauses.push("ts >= ?");
+            values.push(Value::Integer(since.timestamp_millis()));
+        }
+        if let Some(until) = self.until {
+            clauses.push("ts <= ?");
+            values.push(Value::Integer(until.timestamp_millis()));
+        }
+        if let Some(favorite) = self.favorite {
+            clauses.push("favorite = ?");
+            values.push(Value::Integer(favorite as i64));
+        }
+        if clauses.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!("WHERE {}", clauses.join(" AND ")), values)
+        }
+    }
+}
+
+// 在数据库中筛选、排序并分页，返回 (该页的索引条目, 筛选后的总条数)；limit 为 None 时返回 offset 之后的全部条目
+//
+// 排序字段相同的条目按索引顺序排列，与排序方向一致。
+pub fn query(
+    filter: &IndexFilter,
+    sort: SortKey,
+    descending: bool,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<(Vec<serde_json::Value>, usize), String> {
+    let db_error = |e: rusqlite::Error| format!("读取历史索引失败: {}", e);
+    let conn = open()?;
+    let (where_clause, mut values) = filter.where_clause();
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM history_index {}", where_clause),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )
+        .map_err(db_error)?;
+
+    let direction = if descending { "DESC" } else { "ASC" };
+    let sql = format!(
+        "SELECT entry FROM history_index {} ORDER BY {} {dir}, seq {dir}, rowid {dir} LIMIT ? OFFSET ?",
+        where_clause,
+        sort.column(),
+        dir = direction
+    );
+    values.push(rusqlite::types::Value::Integer(
+        limit.map(|l| i64::try_from(l).unwrap_or(i64::MAX)).unwrap_or(-1),
+    ));
+    values.push(rusqlite::types::Value::Integer(i64::try_from(offset).unwrap_or(i64::MAX)));
+
+    let mut stmt = conn.prepare(&sql).map_err(db_error)?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), |row| row.get::<_, String>(0))
+        .map_err(db_error)?;
+    let rows = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(db_error)?;
+    Ok((parse_entries(rows), total as usize))
+}
@@ -0,0 +1,570 @@
+// 参与者导入
+//
+// 名单文件可能有几十万行，这里逐行读取并解析，内存占用只与导入上限 max 有关，与文件大小无关。
+
+use std::collections::{HashSet, VecDeque};
+use std::io::BufRead;
+
+use calamine::Reader;
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::{history, roster, rules, settings};
+
+// 预览中展示的开头与结尾姓名数量
+const PREVIEW_SAMPLE_SIZE: usize = 5;
+
+// 解析一行 CSV：支持双引号包裹的字段和 "" 转义（字段内不能跨行）
+pub fn parse_csv_fields(line: &str) -> Vec<String> {
+    parse_delimited_fields(line, ',')
+}
+
+// 按指定分隔符解析一行，规则与 parse_csv_fields 相同
+pub fn parse_delimited_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// 流式导入参与者
+//
+// name_column 为姓名所在列（从 0 开始）。达到 max 人后停止读取，capOffset 为第一条未导入行在文件中的字节偏移；
+// 读完整个文件时 capOffset 为 null。空姓名和缺少该列的行会被跳过并计数，超长姓名按设置截断。
+#[tauri::command]
+pub async fn import_participants_streaming(
+    app_handle: tauri::AppHandle,
+    file_path: String,
+    name_column: usize,
+    has_header: bool,
+    max: usize,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("流式导入参与者: {} (第 {} 列, 上限 {})", file_path, name_column + 1, max);
+    crate::app_lock::ensure_unlocked()?;
+
+    if max == 0 {
+        return Err(AppError::InvalidInput("导入上限必须大于0".to_string()));
+    }
+
+    let max_length = rules::max_name_length(&settings::current_settings(&app_handle)?);
+    let file = std::fs::File::open(&file_path).map_err(|e| {
+        let error = format!("打开导入文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    let mut reader = std::io::BufReader::new(file);
+
+    let mut names = Vec::new();
+    let mut last_names: VecDeque<String> = VecDeque::with_capacity(PREVIEW_SAMPLE_SIZE);
+    let mut rows_read = 0usize;
+    let mut skipped = 0usize;
+    let mut offset = 0u64;
+    let mut cap_offset = None;
+    let mut line_number = 0usize;
+    let mut buffer = Vec::new();
+
+    loop {
+        buffer.clear();
+        let bytes_read = reader.read_until(b'\n', &mut buffer).map_err(|e| {
+            let error = format!("读取导入文件失败: {}", e);
+            log::error!("{}", error);
+            error
+        })?;
+        if bytes_read == 0 {
+            break;
+        }
+        let line_offset = offset;
+        offset += bytes_read as u64;
+        line_number += 1;
+
+        let mut line = String::from_utf8_lossy(&buffer).into_owned();
+        if line_number == 1 {
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_string();
+            }
+            if has_header {
+                continue;
+            }
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if names.len() >= max {
+            cap_offset = Some(line_offset);
+            break;
+        }
+        rows_read += 1;
+
+        let name = parse_csv_fields(line)
+            .into_iter()
+            .nth(name_column)
+            .map(|n| rules::truncate_name(n.trim(), max_length))
+            .unwrap_or_default();
+        if name.is_empty() {
+            skipped += 1;
+            continue;
+        }
+
+        if last_names.len() == PREVIEW_SAMPLE_SIZE {
+            last_names.pop_front();
+        }
+        last_names.push_back(name.clone());
+        names.push(name);
+    }
+
+    log::info!(
+        "流式导入完成: 读取 {} 行，导入 {} 人，跳过 {} 行，{}",
+        rows_read,
+        names.len(),
+        skipped,
+        if cap_offset.is_some() { "已达到上限" } else { "已读完文件" }
+    );
+
+    let first_names: Vec<&String> = names.iter().take(PREVIEW_SAMPLE_SIZE).collect();
+    Ok(serde_json::json!({
+        "rowsRead": rows_read,
+        "imported": names.len(),
+        "skipped": skipped,
+        "capReached": cap_offset.is_some(),
+        "capOffset": cap_offset,
+        "firstNames": first_names,
+        "lastNames": last_names,
+        "names": names
+    }))
+}
+
+// 读取 CSV 或 Excel（xlsx/xls）文件的所有行，Excel 只读取第一个工作表
+//
+// CSV 去掉 UTF-8 BOM，并兼容 CRLF 换行。
+fn read_table_rows(file_path: &str) -> Result<Vec<Vec<String>>, String> {
+    let extension = std::path::Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "xlsx" | "xlsm" | "xls" => {
+            let mut workbook = calamine::open_workbook_auto(file_path).map_err(|e| format!("打开Excel文件失败: {}", e))?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or("Excel文件中没有工作表")?
+                .map_err(|e| format!("读取工作表失败: {}", e))?;
+            Ok(range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+                .collect())
+        }
+        "csv" | "txt" => {
+            let bytes = std::fs::read(file_path).map_err(|e| format!("读取文件失败: {}", e))?;
+            let content = String::from_utf8_lossy(&bytes);
+            let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
+            Ok(content
+                .lines()
+                .map(|line| parse_csv_fields(line.trim_end_matches('\r')))
+                .collect())
+        }
+        other => Err(format!("不支持的文件类型: {}，仅支持 csv、xlsx 或 xls", other)),
+    }
+}
+
+// 表头行常见的姓名列标题
+const NAME_HEADERS: &[&str] = &["姓名", "名字", "名称", "name"];
+
+// 从 CSV 或 Excel 导入名单并保存为 coredata/groups 下的名单
+//
+// 第一列为姓名，可选的第二列为权重（必须是大于 0 的数字）。姓名去除首尾空白，空行直接忽略；
+// 第一行第一列是常见的表头标题（如“姓名”）时视为表头。权重无效的行会被跳过并在 skipped 中返回。
+// 已存在同名名单时覆盖其成员。
+#[tauri::command]
+pub async fn import_roster(
+    app_handle: tauri::AppHandle,
+    file_path: String,
+    group_name: String,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("导入名单: {} -> {}", file_path, group_name);
+    crate::app_lock::ensure_unlocked()?;
+
+    let group_name = group_name.trim().to_string();
+    if group_name.is_empty() {
+        return Err(AppError::InvalidInput("名单名称不能为空".to_string()));
+    }
+
+    let max_length = rules::max_name_length(&settings::current_settings(&app_handle)?);
+    let rows = read_table_rows(&file_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+
+    let mut members = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let row_number = i + 1;
+        let name = row.first().map(|n| n.trim()).unwrap_or_default();
+        let weight = row.get(1).map(|w| w.trim()).filter(|w| !w.is_empty());
+        if name.is_empty() && weight.is_none() {
+            continue;
+        }
+        if i == 0 && NAME_HEADERS.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+
+        let mut skip = |reason: String| {
+            skipped.push(serde_json::json!({ "row": row_number, "content": row.join(","), "reason": reason }));
+        };
+        if name.is_empty() {
+            skip("姓名为空".to_string());
+            continue;
+        }
+        let weight = match weight.map(|w| w.parse::<f64>()) {
+            None => None,
+            Some(Ok(w)) if w.is_finite() && w > 0.0 => Some(w),
+            Some(_) => {
+                skip(format!("权重无效: {}", weight.unwrap_or_default()));
+                continue;
+            }
+        };
+
+        members.push(roster::RosterMember {
+            name: rules::truncate_name(name, max_length),
+            weight,
+        });
+    }
+
+    if members.is_empty() {
+        return Err(AppError::InvalidInput("文件中没有可导入的姓名".to_string()));
+    }
+
+    let _roster_guard = roster::ROSTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let now = chrono::Utc::now().to_rfc3339();
+    let saved = match roster::find_roster_by_name(&group_name)? {
+        Some(mut existing) => {
+            existing.members = members;
+            existing.updated_time = now;
+            existing
+        }
+        None => roster::Roster {
+            id: roster::new_roster_id(&group_name)?,
+            name: group_name,
+            members,
+            created_time: now.clone(),
+            updated_time: now,
+        },
+    };
+    roster::save_roster(&saved)?;
+
+    log::info!("名单导入完成: {}，导入 {} 人，跳过 {} 行", saved.name, saved.members.len(), skipped.len());
+    Ok(serde_json::json!({
+        "id": saved.id,
+        "name": saved.name,
+        "count": saved.members.len(),
+        "skipped": skipped
+    }))
+}
+
+// import_names_csv 的选项，均可省略
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CsvImportOptions {
+    // 字段分隔符（单个字符），默认为逗号；"\\t" 或 "tab" 表示制表符
+    pub delimiter: Option<String>,
+    // 文件编码："utf-8"、"gbk" 或 "auto"（默认，按 BOM 与内容自动识别）
+    pub encoding: Option<String>,
+    // 姓名所在列（从 0 开始），默认第 0 列
+    pub name_column: usize,
+    // 权重所在列，省略时不读取权重
+    pub weight_column: Option<usize>,
+    // 第一行是否为表头；省略时第一行姓名列是常见表头标题（如“姓名”）才视为表头
+    pub has_header: Option<bool>,
+}
+
+fn parse_delimiter(delimiter: Option<&str>) -> Result<char, AppError> {
+    match delimiter {
+        None | Some("") => Ok(','),
+        Some("\\t") | Some("tab") => Ok('\t'),
+        Some(other) => {
+            let mut chars = other.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c != '"' && c != '\n' && c != '\r' => Ok(c),
+                _ => Err(AppError::InvalidInput(format!("无效的分隔符: {}", other))),
+            }
+        }
+    }
+}
+
+// 按指定编码解码文件内容，返回文本与实际使用的编码名称
+fn decode_csv_bytes(bytes: &[u8], encoding: Option<&str>) -> Result<(String, &'static str), String> {
+    let encoding = match encoding.map(|e| e.trim().to_lowercase()).as_deref() {
+        None | Some("") | Some("auto") => {
+            let decoded = settings::decode_settings_bytes(bytes);
+            return Ok((decoded.text, decoded.encoding));
+        }
+        Some("utf-8") | Some("utf8") => encoding_rs::UTF_8,
+        Some("gbk") | Some("gb2312") | Some("gb18030") => encoding_rs::GBK,
+        Some(other) => return Err(format!("不支持的编码: {}，仅支持 utf-8 或 gbk", other)),
+    };
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        log::warn!("按 {} 解码时遇到无效字节，已替换为 �", encoding.name());
+    }
+    Ok((text.into_owned(), encoding.name()))
+}
+
+// 导入名单文件时整理出的成员与跳过的行
+struct ParsedMembers {
+    members: Vec<roster::RosterMember>,
+    skipped: Vec<serde_json::Value>,
+    rows_read: usize,
+}
+
+// 从 (行号, 字段) 中整理成员（表头行应已去掉）
+//
+// 姓名去除首尾空白，超长姓名按设置截断；空姓名、含控制字符的姓名、重复（规范化后相同）的姓名
+// 与无效权重所在的行会被跳过，并记录行号、原始内容与原因。空行直接忽略。
+fn collect_members(
+    rows: impl IntoIterator<Item = (usize, Vec<String>)>,
+    name_column: usize,
+    weight_column: Option<usize>,
+    max_length: usize,
+) -> ParsedMembers {
+    let mut members = Vec::new();
+    let mut seen = HashSet::new();
+    let mut skipped = Vec::new();
+    let mut rows_read = 0usize;
+    for (row_number, fields) in rows {
+        if fields.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        rows_read += 1;
+
+        let mut skip = |reason: String| {
+            skipped.push(serde_json::json!({ "row": row_number, "content": fields.join(","), "reason": reason }));
+        };
+        let name = fields.get(name_column).map(|n| n.trim()).unwrap_or_default();
+        if name.is_empty() {
+            skip("姓名为空".to_string());
+            continue;
+        }
+        if name.chars().any(char::is_control) {
+            skip("姓名包含控制字符".to_string());
+            continue;
+        }
+        let weight_field = weight_column
+            .and_then(|column| fields.get(column))
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty());
+        let weight = match weight_field.map(|w| w.parse::<f64>()) {
+            None => None,
+            Some(Ok(w)) if w.is_finite() && w > 0.0 => Some(w),
+            Some(_) => {
+                skip(format!("权重无效: {}", weight_field.unwrap_or_default()));
+                continue;
+            }
+        };
+        let name = rules::truncate_name(name, max_length);
+        if !seen.insert(history::normalize_name(&name)) {
+            skip(format!("重复的姓名: {}", name));
+            continue;
+        }
+
+        members.push(roster::RosterMember { name, weight });
+    }
+    ParsedMembers { members, skipped, rows_read }
+}
+
+// 解析 Excel 导出的 CSV 名单，返回整理后的成员与跳过的行，不保存名单
+//
+// 整理规则见 collect_members。
+#[tauri::command]
+pub async fn import_names_csv(
+    app_handle: tauri::AppHandle,
+    path: String,
+    options: Option<CsvImportOptions>,
+) -> Result<serde_json::Value, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let options = options.unwrap_or_default();
+    log::info!("解析CSV名单: {} {:?}", path, options);
+
+    let delimiter = parse_delimiter(options.delimiter.as_deref())?;
+    let max_length = rules::max_name_length(&settings::current_settings(&app_handle)?);
+    let bytes = std::fs::read(&path).map_err(|e| {
+        let error = format!("读取文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    let (content, encoding) = decode_csv_bytes(&bytes, options.encoding.as_deref())?;
+
+    let mut rows: Vec<(usize, Vec<String>)> = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, parse_delimited_fields(line.trim_end_matches('\r'), delimiter)))
+        .collect();
+    if let Some((_, fields)) = rows.first() {
+        let name = fields.get(options.name_column).map(|n| n.trim()).unwrap_or_default();
+        let is_header = options
+            .has_header
+            .unwrap_or_else(|| NAME_HEADERS.contains(&name.to_lowercase().as_str()));
+        if is_header {
+            rows.remove(0);
+        }
+    }
+
+    let parsed = collect_members(rows, options.name_column, options.weight_column, max_length);
+    log::info!(
+        "CSV名单解析完成: 编码 {}，读取 {} 行，有效 {} 人，跳过 {} 行",
+        encoding,
+        parsed.rows_read,
+        parsed.members.len(),
+        parsed.skipped.len()
+    );
+    Ok(serde_json::json!({
+        "members": parsed.members,
+        "encoding": encoding,
+        "rowsRead": parsed.rows_read,
+        "skipped": parsed.skipped
+    }))
+}
+
+// 表头行常见的权重列标题
+const WEIGHT_HEADERS: &[&str] = &["权重", "weight"];
+
+// 指定列：列号（从 0 开始）或表头标题
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    Index(usize),
+    Header(String),
+}
+
+// import_names_xlsx 的列映射，省略的列按表头自动识别
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    // 姓名列，无法识别时为第 0 列
+    pub name: Option<ColumnRef>,
+    // 权重列，无法识别时不读取权重
+    pub weight: Option<ColumnRef>,
+}
+
+// 在表头行中查找标题（忽略大小写与首尾空白）
+fn find_header(header: &[String], titles: &[&str]) -> Option<usize> {
+    header
+        .iter()
+        .position(|cell| titles.iter().any(|title| cell.trim().eq_ignore_ascii_case(title)))
+}
+
+fn open_workbook(path: &str) -> Result<calamine::Sheets<std::io::BufReader<std::fs::File>>, String> {
+    calamine::open_workbook_auto(path).map_err(|e| {
+        let error = format!("打开Excel文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 列出 Excel 文件中的工作表名称
+#[tauri::command]
+pub async fn list_xlsx_sheets(path: String) -> Result<Vec<String>, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    Ok(open_workbook(&path)?.sheet_names())
+}
+
+// 从 Excel 工作表导入名单，返回整理后的成员与跳过的行，不保存名单
+//
+// sheet 为工作表名称，省略时使用第一个工作表。第一行非空行中有常见的姓名/权重标题或
+// column_mapping 中按标题指定的列时视为表头，按标题确定列号；整理规则见 collect_members。
+#[tauri::command]
+pub async fn import_names_xlsx(
+    app_handle: tauri::AppHandle,
+    path: String,
+    sheet: Option<String>,
+    column_mapping: Option<ColumnMapping>,
+) -> Result<serde_json::Value, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let mapping = column_mapping.unwrap_or_default();
+    log::info!("解析Excel名单: {} 工作表 {:?} {:?}", path, sheet, mapping);
+
+    let max_length = rules::max_name_length(&settings::current_settings(&app_handle)?);
+    let mut workbook = open_workbook(&path)?;
+    let sheets = workbook.sheet_names();
+    let sheet_name = match sheet.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()) {
+        Some(name) if sheets.contains(&name) => name,
+        Some(name) => return Err(AppError::NotFound(format!("工作表不存在: {}", name))),
+        None => sheets.first().cloned().ok_or("Excel文件中没有工作表")?,
+    };
+    let range = workbook
+        .worksheet_range(&sheet_name)
+        .map_err(|e| format!("读取工作表失败: {}", e))?;
+
+    let mut rows: Vec<(usize, Vec<String>)> = range
+        .rows()
+        .enumerate()
+        .map(|(i, row)| (i + 1, row.iter().map(|cell| cell.to_string()).collect::<Vec<String>>()))
+        .filter(|(_, fields)| fields.iter().any(|f| !f.trim().is_empty()))
+        .collect();
+
+    let mapped_title = |column: &Option<ColumnRef>| match column {
+        Some(ColumnRef::Header(title)) => Some(title.clone()),
+        _ => None,
+    };
+    let name_title = mapped_title(&mapping.name);
+    let weight_title = mapped_title(&mapping.weight);
+    let header: Option<Vec<String>> = rows.first().and_then(|(_, fields)| {
+        let mut titles: Vec<&str> = NAME_HEADERS.iter().chain(WEIGHT_HEADERS).copied().collect();
+        titles.extend(name_title.as_deref());
+        titles.extend(weight_title.as_deref());
+        find_header(fields, &titles).map(|_| fields.clone())
+    });
+    if header.is_some() {
+        rows.remove(0);
+    }
+
+    let resolve = |column: &Option<ColumnRef>, defaults: &[&str]| -> Result<Option<usize>, String> {
+        match column {
+            Some(ColumnRef::Index(index)) => Ok(Some(*index)),
+            Some(ColumnRef::Header(title)) => header
+                .as_deref()
+                .and_then(|h| find_header(h, &[title.as_str()]))
+                .map(Some)
+                .ok_or_else(|| format!("找不到列: {}", title)),
+            None => Ok(header.as_deref().and_then(|h| find_header(h, defaults))),
+        }
+    };
+    let name_column = resolve(&mapping.name, NAME_HEADERS)?.unwrap_or(0);
+    let weight_column = resolve(&mapping.weight, WEIGHT_HEADERS)?;
+
+    let parsed = collect_members(rows, name_column, weight_column, max_length);
+    log::info!(
+        "Excel名单解析完成: 工作表 {}，读取 {} 行，有效 {} 人，跳过 {} 行",
+        sheet_name,
+        parsed.rows_read,
+        parsed.members.len(),
+        parsed.skipped.len()
+    );
+    Ok(serde_json::json!({
+        "members": parsed.members,
+        "sheet": sheet_name,
+        "sheets": sheets,
+        "hasHeader": header.is_some(),
+        "nameColumn": name_column,
+        "weightColumn": weight_column,
+        "rowsRead": parsed.rows_read,
+        "skipped": parsed.skipped
+    }))
+}
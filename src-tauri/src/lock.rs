@@ -0,0 +1,175 @@
+// 实例锁
+//
+// 启动时在 coredata/.lock 写入当前进程 PID，正常退出时删除。
+// 若程序崩溃导致锁文件残留，下次启动会检查记录的进程是否仍在运行，
+// 进程已不存在时自动清除残留锁并记录恢复日志；进程仍在运行时说明已有其他实例，返回 AppError::Conflict。
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::AppError;
+use crate::history;
+
+// 当前进程是否持有锁文件（只删除自己写入的锁）
+static LOCK_OWNED: AtomicBool = AtomicBool::new(false);
+
+// 锁文件路径 coredata/.lock
+pub fn lock_path() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join(".lock"))
+}
+
+// 检查指定 PID 的进程是否仍在运行
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use std::os::windows::process::CommandExt;
+
+    match std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .creation_flags(crate::CREATE_NO_WINDOW)
+        .output()
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)),
+        // 无法检查时按存活处理，避免误删其他实例的锁
+        Err(_) => true,
+    }
+}
+
+// 信号 0 只检查进程是否存在：EPERM 表示进程存在但属于其他用户，只有 ESRCH 说明进程已退出
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        // 0 与超出范围的值不是有效的进程 ID
+        _ => return false,
+    };
+    // SAFETY: kill 的信号为 0 时不会向进程发送任何信号
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+// 其他平台无法检查，按存活处理
+#[cfg(not(any(windows, unix)))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+// 启动时获取实例锁，必要时清除残留的过期锁
+//
+// 锁被正在运行的其他实例持有时返回 AppError::Conflict，调用方不应在无锁状态下继续运行。
+pub fn acquire_instance_lock() -> Result<(), AppError> {
+    let path = lock_path()?;
+    let current_pid = std::process::id();
+
+    if path.exists() {
+        let recorded = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| content.trim().parse::<u32>().ok());
+
+        match recorded {
+            Some(pid) if pid != current_pid && process_alive(pid) => {
+                let error = AppError::Conflict(format!("已有其他实例在运行（进程 {}）", pid));
+                log::warn!("{}", error);
+                return Err(error);
+            }
+            Some(pid) => {
+                log::warn!("检测到过期的锁文件（进程 {} 已退出），自动恢复", pid);
+            }
+            None => {
+                log::warn!("锁文件内容无效，自动恢复: {:?}", path);
+            }
+        }
+
+        std::fs::remove_file(&path).map_err(|e| AppError::io("删除过期锁文件失败", e))?;
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| AppError::io("创建目录失败", e))?;
+    }
+    std::fs::write(&path, current_pid.to_string()).map_err(|e| AppError::io("写入锁文件失败", e))?;
+    LOCK_OWNED.store(true, Ordering::SeqCst);
+
+    log::info!("已获取实例锁: {:?} (PID {})", path, current_pid);
+    Ok(())
+}
+
+// 退出时释放自己持有的锁
+pub fn release_instance_lock() {
+    if !LOCK_OWNED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    if let Ok(path) = lock_path() {
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::error!("释放实例锁失败: {}", e);
+        }
+    }
+}
+
+// 手动强制释放锁文件（应急手段）
+#[tauri::command]
+pub async fn force_release_lock() -> Result<(), AppError> {
+    log::info!("强制释放实例锁");
+
+    let path = lock_path()?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| {
+            let error = format!("删除锁文件失败: {}", e);
+            log::error!("{}", error);
+            error
+        })?;
+        log::info!("锁文件已删除: {:?}", path);
+    }
+
+    LOCK_OWNED.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDataRoot;
+
+    #[test]
+    fn current_process_is_alive() {
+        assert!(process_alive(std::process::id()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exited_process_is_not_alive() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!process_alive(pid));
+        assert!(!process_alive(0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lock_held_by_running_instance_is_refused() {
+        let _root = TempDataRoot::new("instance-lock");
+        let path = lock_path().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        // PID 1 总在运行；普通用户检查它时 kill 返回 EPERM，同样视为存活
+        std::fs::write(&path, "1").unwrap();
+
+        assert!(matches!(acquire_instance_lock(), Err(AppError::Conflict(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1");
+        assert!(!LOCK_OWNED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn stale_lock_is_recovered() {
+        let _root = TempDataRoot::new("stale-lock");
+        let path = lock_path().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "not a pid").unwrap();
+
+        acquire_instance_lock().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), std::process::id().to_string());
+        release_instance_lock();
+        assert!(!path.exists());
+    }
+}
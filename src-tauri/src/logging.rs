@@ -0,0 +1,312 @@
+// 日志系统
+//
+// log 宏的输出由 flexi_logger 写入应用日志目录（与 Tauri 的 app_log_dir 相同，Windows 下为
+// %LOCALAPPDATA%\<identifier>\logs）中的 starandom_debug_rCURRENT.log，安装在只读目录下也能写入。
+// 当前日志跨天或超过 logMaxBytes 时轮转为 starandom_debug_r00000.log、starandom_debug_r00001.log……，
+// 只保留最近 logMaxFiles 个旧日志。
+// 日志级别由设置 logLevel 决定（off、error、warn、info、debug、trace），可以用 set_log_level 在运行中修改。
+// get_recent_logs 读取最近的日志，export_logs 把全部日志打包，便于用户在程序内取得诊断信息。
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use flexi_logger::{Age, Cleanup, Criterion, FileSpec, Logger, LoggerHandle, Naming};
+
+use crate::error::AppError;
+use crate::{backup, export, settings};
+
+// 日志文件名（不含轮转序号与扩展名）
+const LOG_BASENAME: &str = "starandom_debug";
+
+// 未配置 logLevel 时的日志级别
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+// 可用的日志级别
+pub const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+// 单个日志文件超过该大小时轮转（可通过设置 logMaxBytes 修改）
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+// 保留的旧日志文件数（可通过设置 logMaxFiles 修改）
+pub const DEFAULT_LOG_MAX_FILES: u64 = 5;
+
+// get_recent_logs 未指定行数时返回的条数
+const DEFAULT_RECENT_LINES: usize = 200;
+
+// get_recent_logs 一次最多返回的条数
+const MAX_RECENT_LINES: usize = 5000;
+
+// 运行中的日志器，程序退出前须一直保留
+static LOGGER: OnceLock<LoggerHandle> = OnceLock::new();
+
+// 实际使用的日志目录
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// 应用日志目录，规则与 Tauri 的 app_log_dir 相同（日志在创建 App 之前初始化，无法使用 PathResolver）
+fn app_log_dir(identifier: &str) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = std::env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join(identifier).join("logs"));
+    #[cfg(target_os = "macos")]
+    let dir = std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Logs").join(identifier));
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map(|dir| dir.join(identifier).join("logs"));
+
+    dir
+}
+
+// 选择日志目录：优先使用应用日志目录，无法创建时退回工作目录下的 logs
+fn choose_log_dir(identifier: &str) -> Result<PathBuf, String> {
+    if let Some(dir) = app_log_dir(identifier) {
+        match std::fs::create_dir_all(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) => eprintln!("创建应用日志目录 {:?} 失败，改用工作目录: {}", dir, e),
+        }
+    }
+
+    let dir = std::env::current_dir()
+        .map_err(|e| format!("获取工作目录失败: {}", e))?
+        .join("logs");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    Ok(dir)
+}
+
+// 当前使用的日志目录
+pub fn log_dir() -> Result<&'static Path, String> {
+    LOG_DIR.get().map(|dir| dir.as_path()).ok_or_else(|| "日志系统未初始化".to_string())
+}
+
+// 日志目录中的全部日志文件，从新到旧排列（当前日志在最前面，旧日志的序号越大越新）
+fn log_files() -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(log_dir()?).map_err(|e| format!("读取日志目录失败: {}", e))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(LOG_BASENAME) && n.ends_with(".log"))
+        })
+        .collect();
+    files.sort_by_key(|p| {
+        let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        (!name.contains("rCURRENT"), std::cmp::Reverse(name))
+    });
+    Ok(files)
+}
+
+// 写入缓冲中的日志，读取或打包日志文件前调用
+fn flush() {
+    if let Some(handle) = LOGGER.get() {
+        handle.flush();
+    }
+}
+
+// 日志行的级别；不以“[时间] 级别”开头的行（多行消息的后续行）返回 None
+fn line_level(line: &str) -> Option<log::Level> {
+    if !line.starts_with('[') {
+        return None;
+    }
+    line.split_once("] ")
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .and_then(|level| level.parse().ok())
+}
+
+// 把日志文件拆成条目（多行消息的后续行归入上一条），返回每条的级别与内容
+fn read_entries(path: &Path) -> Vec<(Option<log::Level>, String)> {
+    let Ok(content) = std::fs::read(path) else {
+        return vec![];
+    };
+    let mut entries: Vec<(Option<log::Level>, String)> = Vec::new();
+    for line in String::from_utf8_lossy(&content).lines() {
+        match (line_level(line), entries.last_mut()) {
+            (None, Some((_, text))) => {
+                text.push('\n');
+                text.push_str(line);
+            }
+            (level, _) => entries.push((level, line.to_string())),
+        }
+    }
+    entries
+}
+
+// 日志级别，忽略大小写，无效时返回 None
+pub fn parse_level(level: &str) -> Option<&'static str> {
+    let level = level.trim().to_ascii_lowercase();
+    LOG_LEVELS.iter().copied().find(|known| *known == level)
+}
+
+// 写入 dir 的日志器：追加到当前日志，跨天或超过 max_bytes 时轮转，只保留 max_files 个旧日志
+fn file_logger(level: &str, dir: &Path, max_bytes: u64, max_files: u64) -> Result<Logger, String> {
+    Ok(Logger::try_with_str(level)
+        .map_err(|e| format!("日志级别无效: {}", e))?
+        .log_to_file(FileSpec::default().directory(dir).basename(LOG_BASENAME).suppress_timestamp())
+        .format_for_files(flexi_logger::detailed_format)
+        .rotate(
+            Criterion::AgeOrSize(Age::Day, max_bytes),
+            Naming::Numbers,
+            Cleanup::KeepLogFiles(max_files as usize),
+        )
+        .append())
+}
+
+// 初始化日志系统，返回日志目录；在 main 中尽早调用，此时设置只能从磁盘读取
+pub fn init(identifier: &str) -> Result<PathBuf, String> {
+    let early_settings = settings::early_settings(identifier);
+    let get_u64 = |key: &str, default: u64| early_settings.get(key).and_then(|v| v.as_u64()).unwrap_or(default);
+    let max_bytes = get_u64("logMaxBytes", DEFAULT_LOG_MAX_BYTES).max(1);
+    let max_files = get_u64("logMaxFiles", DEFAULT_LOG_MAX_FILES);
+    let level = early_settings
+        .get("logLevel")
+        .and_then(|v| v.as_str())
+        .and_then(parse_level)
+        .unwrap_or(DEFAULT_LOG_LEVEL);
+
+    let dir = choose_log_dir(identifier)?;
+    let _ = LOG_DIR.set(dir.clone());
+
+    let handle = file_logger(level, &dir, max_bytes, max_files)?
+        .start()
+        .map_err(|e| format!("启动日志失败: {}", e))?;
+    let _ = LOGGER.set(handle);
+
+    log::info!("==> StarRandom 星抽奖系统 v{} 启动", env!("CARGO_PKG_VERSION"));
+    log::info!("© 2025 河南星熠寻光科技有限公司 & vistamin. All rights reserved.");
+    log::info!("当前工作目录: {:?}", std::env::current_dir().ok());
+    log::info!("日志目录: {:?}，级别 {}，单个文件上限 {} 字节，保留 {} 个旧日志", dir, level, max_bytes, max_files);
+    Ok(dir)
+}
+
+// 修改日志级别并保存到设置 logLevel，立即生效
+#[tauri::command]
+pub async fn set_log_level(app_handle: tauri::AppHandle, level: String) -> Result<(), AppError> {
+    let Some(level) = parse_level(&level) else {
+        return Err(AppError::InvalidInput(format!(
+            "日志级别必须是 {} 之一，当前值: {}",
+            LOG_LEVELS.join("、"),
+            level
+        )));
+    };
+
+    let handle = LOGGER.get().ok_or("日志系统未初始化")?;
+    handle
+        .parse_new_spec(level)
+        .map_err(|e| AppError::InvalidInput(format!("日志级别无效: {}", e)))?;
+    settings::update_setting(&app_handle, "logLevel", serde_json::json!(level))?;
+    log::warn!("日志级别已改为 {}", level);
+    Ok(())
+}
+
+// 最近的日志条目，按时间先后排列，最多 lines 条（0 表示默认 200 条）
+//
+// level_filter 为最低级别，例如 "warn" 只返回警告与错误；多行消息算作一条。当前日志不够时继续读取较新的旧日志。
+#[tauri::command]
+pub async fn get_recent_logs(lines: usize, level_filter: Option<String>) -> Result<Vec<String>, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let limit = match lines {
+        0 => DEFAULT_RECENT_LINES,
+        lines => lines.min(MAX_RECENT_LINES),
+    };
+    let min_level = match level_filter.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        None => log::LevelFilter::Trace,
+        Some(filter) => parse_level(filter).and_then(|level| level.parse().ok()).ok_or_else(|| {
+            AppError::InvalidInput(format!("日志级别必须是 {} 之一，当前值: {}", LOG_LEVELS.join("、"), filter))
+        })?,
+    };
+
+    flush();
+    let mut recent = Vec::new();
+    'files: for path in log_files()? {
+        for (level, text) in read_entries(&path).into_iter().rev() {
+            if recent.len() >= limit {
+                break 'files;
+            }
+            if level.is_none_or(|level| level <= min_level) {
+                recent.push(text);
+            }
+        }
+    }
+    recent.reverse();
+    Ok(recent)
+}
+
+// 把全部日志文件与一份运行环境信息打包为 zip，返回打包的日志文件数
+#[tauri::command]
+pub async fn export_logs(zip_path: String) -> Result<usize, AppError> {
+    log::info!("导出日志: {}", zip_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    flush();
+    let files = log_files()?;
+    let environment = serde_json::json!({
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "platform": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "logDir": log_dir()?,
+        "exportedAt": chrono::Utc::now().to_rfc3339(),
+    });
+
+    let file = std::fs::File::create(&zip_path).map_err(|e| AppError::io("创建日志归档失败", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    backup::add_bytes(&mut zip, "environment.json", environment.to_string().as_bytes())?;
+    for path in &files {
+        let data = std::fs::read(path).map_err(|e| AppError::io("读取日志文件失败", e))?;
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        backup::add_bytes(&mut zip, &name, &data)?;
+    }
+    zip.finish().map_err(|e| {
+        let error = format!("完成日志归档写入失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    export::record_export(&zip_path, "logs", Some(files.len()), None);
+    log::info!("日志导出完成: {}，包含 {} 个日志文件", zip_path, files.len());
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDir;
+
+    const TEST_MAX_BYTES: u64 = 1024;
+
+    // 在已有大小为 existing_bytes 的当前日志的目录中启动日志器并写入几条日志，返回目录中的日志文件名
+    fn log_after_start(name: &str, existing_bytes: usize) -> Vec<String> {
+        let dir = TempDir::new(name);
+        let current = dir.path().join(format!("{}_rCURRENT.log", LOG_BASENAME));
+        std::fs::write(&current, "x".repeat(existing_bytes)).unwrap();
+
+        let (logger, handle) = file_logger("info", dir.path(), TEST_MAX_BYTES, DEFAULT_LOG_MAX_FILES)
+            .unwrap()
+            .build()
+            .unwrap();
+        for _ in 0..2 {
+            logger.log(&log::Record::builder().level(log::Level::Info).args(format_args!("启动")).build());
+        }
+        handle.flush();
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn oversized_log_is_rotated() {
+        let names = log_after_start("log-rotate", TEST_MAX_BYTES as usize * 2);
+        assert!(names.contains(&format!("{}_r00000.log", LOG_BASENAME)), "{:?}", names);
+    }
+
+    #[test]
+    fn small_log_is_kept() {
+        let names = log_after_start("log-keep", 16);
+        assert_eq!(names, vec![format!("{}_rCURRENT.log", LOG_BASENAME)]);
+    }
+}
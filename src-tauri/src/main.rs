@@ -3,12 +3,11 @@
 
 use tauri::Manager;
 use std::io::Write;
-use std::fs;
-use std::path::PathBuf;
 use chrono::Datelike;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::process::Command;
+#[cfg(windows)]
 use std::os::windows::process::CommandExt;
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 use log;
@@ -18,9 +17,50 @@ use tauri_plugin_window_state;
 use tauri_plugin_store;
 use std::time::Duration;
 
+mod app_lock;
+mod audit;
+mod auto_backup;
+mod background;
+mod backup;
+mod draw;
+mod encryption;
+mod error;
+mod export;
+mod history;
+mod history_db;
+mod import;
+mod lock;
+mod logging;
+mod notification;
+mod password;
+mod poster;
+mod protection;
+mod query;
+mod report;
+mod roster;
+mod rules;
+mod scheduler;
+mod settings;
+mod sound;
+mod stats;
+mod storage;
+mod tray;
+mod ui_state;
+mod update;
+mod watcher;
+mod window;
+
+use error::AppError;
+use settings::SettingsWriter;
+
+// 编译进程序的版本号，get_app_info 与检查更新都使用它
+pub const APP_VERSION: &str = "v1.0.7";
+
+#[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 // 检查是否具有管理员权限
+#[cfg(windows)]
 fn is_admin() -> bool {
     if let Ok(output) = Command::new("net")
         .args(&["session"])
@@ -32,7 +72,19 @@ fn is_admin() -> bool {
     }
 }
 
+// Unix 下以 root 身份运行即视为管理员
+#[cfg(unix)]
+fn is_admin() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(any(windows, unix)))]
+fn is_admin() -> bool {
+    false
+}
+
 // 以管理员权限重启应用
+#[cfg(windows)]
 fn restart_as_admin() -> Result<(), Box<dyn std::error::Error>> {
     let exe_path = std::env::current_exe()?;
     let exit_code = Command::new("powershell")
@@ -53,9 +105,84 @@ fn restart_as_admin() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+// 在 PATH 中查找可执行文件
+#[cfg(all(unix, not(target_os = "macos")))]
+fn find_in_path(program: &str) -> Option<std::path::PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+// 以管理员权限重启应用：优先使用 pkexec（图形化授权），其次是配置了 SUDO_ASKPASS 的 sudo
+//
+// pkexec 会清空环境变量，需要显式传递图形会话相关的变量。提权后的进程退出后本进程随之退出；
+// 授权被拒绝或取消时 pkexec 返回 126/127。
+#[cfg(all(unix, not(target_os = "macos")))]
+fn restart_as_admin() -> Result<(), Box<dyn std::error::Error>> {
+    let exe_path = std::env::current_exe()?;
+
+    let mut command = if let Some(pkexec) = find_in_path("pkexec") {
+        let mut command = Command::new(pkexec);
+        command.arg("env");
+        for key in ["DISPLAY", "WAYLAND_DISPLAY", "XAUTHORITY", "XDG_RUNTIME_DIR"] {
+            if let Ok(value) = std::env::var(key) {
+                command.arg(format!("{}={}", key, value));
+            }
+        }
+        command.arg(&exe_path);
+        command
+    } else if let (Some(sudo), true) = (find_in_path("sudo"), std::env::var_os("SUDO_ASKPASS").is_some()) {
+        let mut command = Command::new(sudo);
+        command.arg("-A").arg("-E").arg(&exe_path);
+        command
+    } else {
+        return Err("未找到可用的提权工具（需要 pkexec，或配置了 SUDO_ASKPASS 的 sudo）".into());
+    };
+
+    let status = command
+        .current_dir(std::env::current_dir()?)
+        .spawn()?
+        .wait()?;
+
+    match status.code() {
+        Some(126) | Some(127) => Err("授权被拒绝或已取消".into()),
+        _ => std::process::exit(0),
+    }
+}
+
+// 以管理员权限重启应用：macOS 没有 pkexec，通过 osascript 的 with administrator privileges 弹出系统授权框，
+// 在后台启动提权后的进程后本进程退出；用户取消授权时 osascript 返回非零退出码
+#[cfg(target_os = "macos")]
+fn restart_as_admin() -> Result<(), Box<dyn std::error::Error>> {
+    let exe_path = std::env::current_exe()?;
+    let escaped = exe_path.display().to_string().replace('\\', "\\\\").replace('"', "\\\"");
+    let script = format!(
+        "do shell script quoted form of \"{}\" & \" > /dev/null 2>&1 &\" with administrator privileges",
+        escaped
+    );
+
+    let status = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .current_dir(std::env::current_dir()?)
+        .status()?;
+
+    if status.success() {
+        std::process::exit(0);
+    }
+    Err("授权被拒绝或已取消".into())
+}
+
+#[cfg(not(any(windows, unix)))]
+fn restart_as_admin() -> Result<(), Box<dyn std::error::Error>> {
+    Err("当前平台不支持以管理员权限重启".into())
+}
+
 // 检查并申请管理员权限
 #[tauri::command]
-async fn request_admin_privileges(window: tauri::WebviewWindow) -> Result<bool, String> {
+async fn request_admin_privileges(window: tauri::WebviewWindow) -> Result<bool, AppError> {
     if is_admin() {
         return Ok(true);
     }
@@ -71,34 +198,13 @@ async fn request_admin_privileges(window: tauri::WebviewWindow) -> Result<bool,
     if result {
         if let Err(e) = restart_as_admin() {
             log::error!("以管理员权限重启失败: {}", e);
-            return Err("无法获取管理员权限".to_string());
+            return Err(format!("无法获取管理员权限: {}", e).into());
         }
     }
 
     Ok(false)
 }
 
-// 初始化日志系统
-fn init_logging() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let log_dir = std::env::current_dir()?.join("logs");
-    fs::create_dir_all(&log_dir)?;
-    let log_file = log_dir.join("starandom_debug.log");
-    
-    // 写入启动日志
-    let mut file = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_file)?;
-    
-    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
-    writeln!(file, "\n[{}] ==> StarRandom 星抽奖系统 v1.0.7 启动", timestamp)?;
-    writeln!(file, "[{}] © 2025 河南星熠寻光科技有限公司 & vistamin. All rights reserved.", timestamp)?;
-    writeln!(file, "[{}] 当前工作目录: {:?}", timestamp, std::env::current_dir()?)?;
-    writeln!(file, "[{}] 日志文件位置: {:?}", timestamp, log_file)?;
-    
-    Ok(log_file)
-}
-
 // 抽奖命令
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -108,8 +214,9 @@ fn greet(name: &str) -> String {
 
 // 保存抽奖结果到文件
 #[tauri::command]
-async fn save_lottery_result(app_handle: tauri::AppHandle, result: String) -> Result<(), String> {
+async fn save_lottery_result(app_handle: tauri::AppHandle, result: String) -> Result<(), AppError> {
     log::info!("保存抽奖结果: {}", result);
+    app_lock::ensure_unlocked()?;
     
     let app_dir = app_handle.path().app_data_dir().map_err(|e| {
         let error = format!("获取应用数据目录失败: {}", e);
@@ -129,18 +236,18 @@ async fn save_lottery_result(app_handle: tauri::AppHandle, result: String) -> Re
         })?;
     }
     
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&file_path)
-        .map_err(|e| {
-            let error = format!("打开文件失败: {}", e);
-            log::error!("{}", error);
-            error.to_string()
-        })?;
-    
+    // 网络盘上偶发的占用错误会自动重试
+    let attempts = settings::current_settings(&app_handle)
+        .map(|s| storage::retry_attempts(&s))
+        .unwrap_or(storage::DEFAULT_RETRY_ATTEMPTS);
     let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
-    writeln!(file, "[{}] {}", timestamp, result).map_err(|e| {
+    storage::with_retry(attempts, || {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)?;
+        writeln!(file, "[{}] {}", timestamp, result)
+    }).map_err(|e| {
         let error = format!("写入文件失败: {}", e);
         log::error!("{}", error);
         error.to_string()
@@ -151,8 +258,9 @@ async fn save_lottery_result(app_handle: tauri::AppHandle, result: String) -> Re
 
 // 读取抽奖历史
 #[tauri::command]
-async fn load_lottery_history(app_handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+async fn load_lottery_history(app_handle: tauri::AppHandle) -> Result<Vec<String>, AppError> {
     log::info!("加载抽奖历史");
+    app_lock::ensure_unlocked()?;
     
     let app_dir = app_handle.path().app_data_dir().map_err(|e| {
         let error = format!("获取应用数据目录失败: {}", e);
@@ -182,7 +290,7 @@ async fn load_lottery_history(app_handle: tauri::AppHandle) -> Result<Vec<String
 
 // 获取应用程序路径信息
 #[tauri::command]
-async fn get_app_paths(app_handle: tauri::AppHandle) -> Result<serde_json::Value, String> {
+async fn get_app_paths(app_handle: tauri::AppHandle) -> Result<serde_json::Value, AppError> {
     use serde_json::json;
     
     log::info!("获取应用程序路径信息");
@@ -203,45 +311,53 @@ async fn get_app_paths(app_handle: tauri::AppHandle) -> Result<serde_json::Value
     Ok(paths)
 }
 
-// 保存应用设置
+// 保存应用设置（频繁保存会被合并，静默期过后只写入最后一次的值）
 #[tauri::command]
-async fn save_settings(app_handle: tauri::AppHandle, settings: serde_json::Value) -> Result<(), String> {
+async fn save_settings(
+    app_handle: tauri::AppHandle,
+    writer: tauri::State<'_, SettingsWriter>,
+    settings: serde_json::Value,
+) -> Result<(), AppError> {
     log::info!("保存设置: {}", settings);
+    app_lock::ensure_unlocked()?;
     
-    let config_dir = app_handle.path().app_config_dir().map_err(|e| e.to_string())?;
-    let settings_path = config_dir.join("settings.json");
+    // 按 Settings 解析，拒绝类型或取值错误的设置（一次列出全部问题），避免写入后破坏读取设置的地方
+    let settings = settings::Settings::from_value(settings).map_err(|issues| {
+        let error = settings::describe_issues(&issues);
+        log::error!("设置校验失败: {}", error);
+        AppError::InvalidInput(error)
+    })?;
     
-    // 确保配置目录存在
-    if let Some(parent) = settings_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
+    let settings_path = settings::settings_path(&app_handle)?;
+    writer.schedule(settings_path, settings.to_value());
     
-    let settings_str = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    std::fs::write(&settings_path, settings_str).map_err(|e| e.to_string())?;
+    // 静默期后尝试落盘，若期间又有新的保存则由新的任务负责写入
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(settings::SETTINGS_QUIET_PERIOD).await;
+        // 后台任务暂停期间保留待写入的设置，恢复时再补写
+        if !background::enabled() {
+            return;
+        }
+        let writer = handle.state::<SettingsWriter>();
+        match writer.flush_if_quiet() {
+            Ok(Some(written)) => settings::emit_settings_changed(&handle, &written),
+            Ok(None) => {}
+            Err(e) => log::error!("延迟写入设置失败: {}", e),
+        }
+    });
     
-    log::info!("设置保存成功");
+    log::info!("设置已加入写入队列");
     Ok(())
 }
 
 // 加载应用设置
 #[tauri::command]
-async fn load_settings(app_handle: tauri::AppHandle) -> Result<serde_json::Value, String> {
+async fn load_settings(app_handle: tauri::AppHandle) -> Result<serde_json::Value, AppError> {
     log::info!("加载应用设置");
     
-    let config_dir = app_handle.path().app_config_dir().map_err(|e| e.to_string())?;
-    let settings_path = config_dir.join("settings.json");
-    
-    if !settings_path.exists() {
-        log::info!("设置文件不存在，返回默认设置");
-        return Ok(serde_json::json!({
-            "theme": "light",
-            "autoSave": true,
-            "soundEnabled": true
-        }));
-    }
-    
-    let content = std::fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
-    let settings: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    // 尚未落盘的设置是最新值；设置文件不存在时返回默认设置
+    let settings = settings::current_settings(&app_handle)?;
     
     log::info!("加载的设置: {}", settings);
     Ok(settings)
@@ -251,26 +367,29 @@ async fn load_settings(app_handle: tauri::AppHandle) -> Result<serde_json::Value
 
 // 保存JSON文件
 #[tauri::command]
-async fn save_json_file(file_path: String, data: String) -> Result<(), String> {
+async fn save_json_file(file_path: String, data: String) -> Result<(), AppError> {
     log::info!("保存JSON文件: {}", file_path);
+    app_lock::ensure_unlocked()?;
     
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let full_path = current_dir.join(&file_path);
+    let full_path = storage::resolve_data_path(&file_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
     
     // 确保目录存在
     if let Some(parent) = full_path.parent() {
         std::fs::create_dir_all(parent).map_err(|e| {
-            let error = format!("创建目录失败: {}", e);
+            let error = AppError::io("创建目录失败", e);
             log::error!("{}", error);
             error
         })?;
     }
     
     // 写入文件
-    std::fs::write(&full_path, data).map_err(|e| {
-        let error = format!("写入JSON文件失败: {}", e);
+    storage::write_atomic(&full_path, data.as_bytes()).map_err(|e| {
+        let error = AppError::io("写入JSON文件失败", e);
         log::error!("{}", error);
-        error.to_string()
+        error
     })?;
     
     log::info!("JSON文件保存成功: {:?}", full_path);
@@ -279,11 +398,14 @@ async fn save_json_file(file_path: String, data: String) -> Result<(), String> {
 
 // 加载JSON文件
 #[tauri::command]
-async fn load_json_file(file_path: String) -> Result<String, String> {
+async fn load_json_file(file_path: String) -> Result<String, AppError> {
     log::info!("加载JSON文件: {}", file_path);
+    app_lock::ensure_unlocked()?;
     
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let full_path = current_dir.join(&file_path);
+    let full_path = storage::resolve_data_path(&file_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
     
     if !full_path.exists() {
         log::info!("JSON文件不存在: {:?}", full_path);
@@ -291,9 +413,9 @@ async fn load_json_file(file_path: String) -> Result<String, String> {
     }
     
     let content = std::fs::read_to_string(&full_path).map_err(|e| {
-        let error = format!("读取JSON文件失败: {}", e);
+        let error = AppError::io("读取JSON文件失败", e);
         log::error!("{}", error);
-        error.to_string()
+        error
     })?;
     
     log::info!("JSON文件加载成功: {:?}, 大小: {} 字节", full_path, content.len());
@@ -302,25 +424,31 @@ async fn load_json_file(file_path: String) -> Result<String, String> {
 
 // 检查文件是否存在
 #[tauri::command]
-async fn file_exists(file_path: String) -> Result<bool, String> {
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let full_path = current_dir.join(&file_path);
+async fn file_exists(file_path: String) -> Result<bool, AppError> {
+    app_lock::ensure_unlocked()?;
+    let full_path = storage::resolve_data_path(&file_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
     Ok(full_path.exists())
 }
 
 // 删除文件
 #[tauri::command]
-async fn delete_file(file_path: String) -> Result<(), String> {
+async fn delete_file(file_path: String) -> Result<(), AppError> {
     log::info!("删除文件: {}", file_path);
+    app_lock::ensure_unlocked()?;
     
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let full_path = current_dir.join(&file_path);
+    let full_path = storage::resolve_data_path(&file_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
     
     if full_path.exists() {
         std::fs::remove_file(&full_path).map_err(|e| {
-            let error = format!("删除文件失败: {}", e);
+            let error = AppError::io("删除文件失败", e);
             log::error!("{}", error);
-            error.to_string()
+            error
         })?;
         
         log::info!("文件删除成功: {:?}", full_path);
@@ -333,35 +461,41 @@ async fn delete_file(file_path: String) -> Result<(), String> {
 
 // 获取文件大小
 #[tauri::command]
-async fn get_file_size(file_path: String) -> Result<u64, String> {
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let full_path = current_dir.join(&file_path);
+async fn get_file_size(file_path: String) -> Result<u64, AppError> {
+    app_lock::ensure_unlocked()?;
+    let full_path = storage::resolve_data_path(&file_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
     
     if !full_path.exists() {
         return Ok(0);
     }
     
-    let metadata = std::fs::metadata(&full_path).map_err(|e| e.to_string())?;
+    let metadata = std::fs::metadata(&full_path).map_err(|e| AppError::io("读取文件信息失败", e))?;
     Ok(metadata.len())
 }
 
 // 列出目录内容
 #[tauri::command]
-async fn list_directory(dir_path: String) -> Result<Vec<String>, String> {
+async fn list_directory(dir_path: String) -> Result<Vec<String>, AppError> {
     log::info!("列出目录内容: {}", dir_path);
+    app_lock::ensure_unlocked()?;
     
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let full_path = current_dir.join(&dir_path);
+    let full_path = storage::resolve_data_path(&dir_path).map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
     
     if !full_path.exists() {
         return Ok(vec![]);
     }
     
-    let entries = std::fs::read_dir(&full_path).map_err(|e| e.to_string())?;
+    let entries = std::fs::read_dir(&full_path).map_err(|e| AppError::io("读取目录失败", e))?;
     let mut files = Vec::new();
     
     for entry in entries {
-        let entry = entry.map_err(|e| e.to_string())?;
+        let entry = entry.map_err(|e| AppError::io("读取目录失败", e))?;
         if let Some(name) = entry.file_name().to_str() {
             files.push(name.to_string());
         }
@@ -373,7 +507,7 @@ async fn list_directory(dir_path: String) -> Result<Vec<String>, String> {
 
 // 获取调试信息
 #[tauri::command]
-async fn get_debug_info() -> Result<serde_json::Value, String> {
+async fn get_debug_info() -> Result<serde_json::Value, AppError> {
     use serde_json::json;
     
     let debug_info = json!({
@@ -391,7 +525,7 @@ async fn get_debug_info() -> Result<serde_json::Value, String> {
 
 // 获取当前exe文件路径
 #[tauri::command]
-async fn get_current_exe_path() -> Result<String, String> {
+async fn get_current_exe_path() -> Result<String, AppError> {
     log::info!("获取当前exe文件路径");
     
     let exe_path = std::env::current_exe().map_err(|e| {
@@ -408,12 +542,12 @@ async fn get_current_exe_path() -> Result<String, String> {
 
 // 获取应用程序版本和版权信息
 #[tauri::command]
-async fn get_app_info() -> Result<serde_json::Value, String> {
+async fn get_app_info() -> Result<serde_json::Value, AppError> {
     use serde_json::json;
     
     let app_info = json!({
         "name": "StarRandom 星抽奖系统",
-        "version": "v1.0.7",
+        "version": APP_VERSION,
         "copyright": "© 2025 河南星熠寻光科技有限公司 & vistamin. All rights reserved.",
         "company": "河南星熠寻光科技有限公司",
         "author": "vistamin",
@@ -430,25 +564,82 @@ async fn get_app_info() -> Result<serde_json::Value, String> {
 
 // 保存历史任务到分年月文件夹结构
 #[tauri::command]
-async fn save_history_task(task_data: serde_json::Value) -> Result<(), String> {
-    log::info!("保存历史任务: {}", task_data);
+async fn save_history_task(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_data: serde_json::Value,
+) -> Result<(), AppError> {
+    app_lock::ensure_unlocked()?;
+    store_history_task(app_handle, index_lock, task_data).await
+}
+
+// save_history_task 的实现，不检查应用锁；定时抽取在应用锁定时也要保存结果
+async fn store_history_task(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_data: serde_json::Value,
+) -> Result<(), AppError> {
+    log::info!(
+        "保存历史任务: {} ({})",
+        task_data.get("id").and_then(|v| v.as_str()).unwrap_or_default(),
+        task_data.get("name").and_then(|v| v.as_str()).unwrap_or("未命名任务")
+    );
+    
+    let current_settings = settings::current_settings(&app_handle)?;
+    // 查找已有任务、检查 id 冲突、写任务文件和更新索引都在索引锁内完成，避免并发保存同一 id 时都通过冲突检查
+    let _index_guard = index_lock.lock();
+    write_history_task(task_data, &current_settings)
+}
+
+// 按当前设置写入任务文件、更新索引并追加账本记录（调用方须持有历史索引锁）
+fn write_history_task(mut task_data: serde_json::Value, current_settings: &serde_json::Value) -> Result<(), AppError> {
+    // 先校验并规范化时间戳（统一保存为 UTC，原时区偏移另存）
+    let datetime = history::normalize_task_timestamp(&mut task_data).map_err(|e| {
+        log::error!("{}", e);
+        AppError::InvalidInput(e)
+    })?;
+    
+    // 截断超长姓名，避免异常输入撑大任务文件
+    let max_name_length = rules::max_name_length(current_settings);
+    rules::truncate_name_array(task_data.get_mut("results"), max_name_length);
+    rules::truncate_name_array(task_data.get_mut("participants"), max_name_length);
+
+    // 编辑密码只保存哈希；重新保存受保护的任务时沿用已有哈希
+    encryption::ensure_unlocked()?;
+    let existing_task = task_data
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(history::find_task)
+        .transpose()?
+        .flatten();
+    // 设置了任务密码的任务须先解锁，否则无法重新加密
+    if existing_task.as_ref().is_some_and(encryption::is_password_summary) {
+        let error = AppError::PermissionDenied("任务已设置密码，请先输入任务密码解锁".to_string());
+        log::warn!("{}", error);
+        return Err(error);
+    }
+    protection::protect_task_data(&mut task_data, existing_task.as_ref())?;
+    
+    // 保存前校验结果数量与参与人数，避免写入自相矛盾的记录
+    let issues = history::check_task_consistency(&task_data);
+    if !issues.is_empty() {
+        let error = AppError::InvalidInput(format!("任务数据不一致: {}", issues.join("; ")));
+        log::error!("{}", error);
+        return Err(error);
+    }
     
     // 解析任务数据
     let task_id = task_data.get("id")
         .and_then(|v| v.as_str())
-        .ok_or("缺少任务ID")?;
+        .ok_or_else(|| AppError::InvalidInput("缺少任务ID".to_string()))?;
     let task_name = task_data.get("name")
         .and_then(|v| v.as_str())
         .unwrap_or("未命名任务");
-    let timestamp = task_data.get("timestamp")
-        .and_then(|v| v.as_str())
-        .ok_or("缺少时间戳")?;
     
-    // 解析年月信息
-    let datetime = chrono::DateTime::parse_from_rfc3339(timestamp)
-        .map_err(|e| format!("时间戳解析失败: {}", e))?;
-    let year = datetime.year();
-    let month = datetime.month();
+    // 解析年月信息：按抽取时的本地日期划分目录，避免跨月的深夜抽取落入 UTC 的另一个月
+    let local_datetime = history::task_local_datetime(&task_data, &datetime);
+    let year = local_datetime.year();
+    let month = local_datetime.month();
     
     log::info!("解析时间: {}年{}月", year, month);
     
@@ -460,108 +651,103 @@ async fn save_history_task(task_data: serde_json::Value) -> Result<(), String> {
     let file_name = format!("{}_{}.json", clean_name, task_id);
     
     // 创建年月目录结构
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+    let current_dir = storage::data_base_dir()?;
     let month_str = format!("{:02}", month);
     let year_dir = current_dir.join("coredata").join("history").join(year.to_string());
     let month_dir = year_dir.join(&month_str);
     
     // 确保目录存在
     std::fs::create_dir_all(&month_dir).map_err(|e| {
-        let error = format!("创建年月目录失败: {}", e);
+        let error = AppError::io("创建年月目录失败", e);
         log::error!("{}", error);
         error
     })?;
     
     // 保存任务文件
     let file_path = month_dir.join(&file_name);
-    let task_file_data = serde_json::json!({
-        "task-data": task_data,
+    
+    // id 相同但属于另一次抽取时拒绝保存，避免覆盖它的任务文件和索引条目
+    let conflicting = existing_task
+        .clone()
+        .or_else(|| history::read_task_file(&file_path))
+        .filter(|existing| !history::is_same_logical_task(existing, &task_data));
+    if let Some(existing) = conflicting {
+        let error = AppError::Conflict(format!(
+            "任务ID冲突: {} 已被另一个任务使用（{}，{}），已拒绝覆盖",
+            task_id,
+            existing.get("name").and_then(|v| v.as_str()).unwrap_or("未命名任务"),
+            existing.get("timestamp").and_then(|v| v.as_str()).unwrap_or_default()
+        ));
+        log::warn!("{}", error);
+        return Err(error);
+    }
+    let mut task_file_data = serde_json::json!({
         "created-time": chrono::Utc::now().to_rfc3339(),
         "year": year,
         "month": month
     });
+    // 启用加密时 task-data 以 encrypted-task-data 的形式保存
+    encryption::store_task_data(&mut task_file_data, task_data.clone(), encryption::is_enabled(current_settings))?;
     
     let task_file_content = serde_json::to_string_pretty(&task_file_data)
         .map_err(|e| format!("序列化任务数据失败: {}", e))?;
     
-    std::fs::write(&file_path, task_file_content).map_err(|e| {
-        let error = format!("写入任务文件失败: {}", e);
+    let attempts = storage::retry_attempts(current_settings);
+    storage::with_retry(attempts, || storage::write_atomic(&file_path, task_file_content.as_bytes())).map_err(|e| {
+        let error = AppError::io("写入任务文件失败", e);
         log::error!("{}", error);
         error
     })?;
     
     log::info!("任务文件保存成功: {:?}", file_path);
     
-    // 更新history.json索引
-    let history_index_path = current_dir.join("coredata").join("history.json");
-    let mut history_index: Vec<serde_json::Value> = if history_index_path.exists() {
-        let content = std::fs::read_to_string(&history_index_path)
-            .map_err(|e| format!("读取历史索引失败: {}", e))?;
-        serde_json::from_str(&content).unwrap_or_else(|_| vec![])
-    } else {
-        vec![]
-    };
-    
-    // 创建新的索引条目
-    let index_entry = serde_json::json!({
-        "id": task_id,
-        "name": task_name,
-        "timestamp": timestamp,
-        "fileName": file_name,
-        "relativePath": format!("{}/{}/{}", year, month_str, file_name),
-        "totalCount": task_data.get("total_count").unwrap_or(&serde_json::Value::Number(serde_json::Number::from(0))),
-        "groupName": task_data.get("group_name").and_then(|v| v.as_str()).unwrap_or("未知小组"),
-        "year": year,
-        "month": month
-    });
-    
-    // 检查是否已存在，更新或添加
-    if let Some(pos) = history_index.iter().position(|item| {
-        item.get("id").and_then(|v| v.as_str()) == Some(task_id)
-    }) {
-        history_index[pos] = index_entry;
+    // 更新历史索引（账本追加也在锁内完成）
+    let index_entry = history::build_index_entry(&task_data, &file_name, year, month);
+    let retention = history::history_retention(current_settings);
+    // 更新或添加这一条，并按 historyRetention / historyRetentionDays 修剪（收藏的记录不计入、不会被移出）
+    let (updated, evicted, total) = history::save_index_entry(index_entry, &retention)?;
+    if updated {
         log::info!("更新现有历史记录索引");
     } else {
-        history_index.insert(0, index_entry);
         log::info!("添加新历史记录索引");
     }
     
-    // 保留最近100个记录
-    history_index.truncate(100);
+    log::info!("历史记录索引已更新，总数: {}", total);
     
-    // 保存索引文件
-    let index_content = serde_json::to_string_pretty(&history_index)
-        .map_err(|e| format!("序列化索引失败: {}", e))?;
+    // 删除被移出索引的任务文件，避免留下孤立文件
+    history::delete_evicted_task_files(&evicted);
     
-    std::fs::write(&history_index_path, index_content).map_err(|e| {
-        let error = format!("保存历史索引失败: {}", e);
-        log::error!("{}", error);
-        error
-    })?;
+    // 追加哈希链账本记录
+    audit::append_ledger_entry(task_id, &history::task_winners(&task_data))?;
     
-    log::info!("历史记录索引已更新，总数: {}", history_index.len());
     Ok(())
 }
 
+// 保存前校验任务数据的一致性
+#[tauri::command]
+async fn validate_task_consistency(task_data: serde_json::Value) -> Result<serde_json::Value, AppError> {
+    let issues = history::check_task_consistency(&task_data);
+    
+    Ok(serde_json::json!({
+        "valid": issues.is_empty(),
+        "issues": issues,
+        "resultCount": task_data.get("results").and_then(|v| v.as_array()).map(|a| a.len()),
+        "totalCount": task_data.get("total_count"),
+        "participantCount": task_data.get("participants").and_then(|v| v.as_array()).map(|a| a.len())
+    }))
+}
+
 // 获取历史记录数据
 #[tauri::command]
-async fn get_history_data() -> Result<Vec<serde_json::Value>, String> {
+async fn get_history_data() -> Result<Vec<serde_json::Value>, AppError> {
     log::info!("获取历史记录数据");
+    app_lock::ensure_unlocked()?;
+    encryption::ensure_unlocked()?;
     
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let history_index_path = current_dir.join("coredata").join("history.json");
-    
-    if !history_index_path.exists() {
-        log::info!("历史索引文件不存在，返回空列表");
-        return Ok(vec![]);
-    }
+    let current_dir = storage::data_base_dir()?;
     
     // 读取历史索引
-    let index_content = std::fs::read_to_string(&history_index_path)
-        .map_err(|e| format!("读取历史索引失败: {}", e))?;
-    
-    let history_index: Vec<serde_json::Value> = serde_json::from_str(&index_content)
-        .unwrap_or_else(|_| vec![]);
+    let history_index = history::load_history_index()?;
     
     log::info!("从索引加载了 {} 条历史记录", history_index.len());
     
@@ -576,8 +762,9 @@ async fn get_history_data() -> Result<Vec<serde_json::Value>, String> {
                 match std::fs::read_to_string(&task_file_path) {
                     Ok(task_content) => {
                         if let Ok(task_file_data) = serde_json::from_str::<serde_json::Value>(&task_content) {
-                            if let Some(task_data) = task_file_data.get("task-data") {
-                                history_data.push(task_data.clone());
+                            if let Ok(mut task_data) = encryption::task_data_of(&task_file_data) {
+                                protection::redact(&mut task_data);
+                                history_data.push(task_data);
                                 continue;
                             }
                         }
@@ -589,17 +776,7 @@ async fn get_history_data() -> Result<Vec<serde_json::Value>, String> {
             }
             
             // 如果无法加载完整数据，使用索引信息生成备用数据
-            let backup_data = serde_json::json!({
-                "id": index_item.get("id"),
-                "name": index_item.get("name"),
-                "timestamp": index_item.get("timestamp"),
-                "total_count": index_item.get("totalCount"),
-                "group_name": index_item.get("groupName"),
-                "results": [],
-                "file_path": index_item.get("fileName"),
-                "edit_protected": false,
-                "edit_password": ""
-            });
+            let backup_data = history::fallback_task_data(index_item);
             history_data.push(backup_data);
         }
     }
@@ -610,42 +787,16 @@ async fn get_history_data() -> Result<Vec<serde_json::Value>, String> {
 
 // 获取单个历史任务
 #[tauri::command]
-async fn get_history_task(task_id: String) -> Result<Option<serde_json::Value>, String> {
+async fn get_history_task(task_id: String) -> Result<Option<serde_json::Value>, AppError> {
     log::info!("获取历史任务: {}", task_id);
-    
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let history_index_path = current_dir.join("coredata").join("history.json");
-    
-    if !history_index_path.exists() {
-        return Ok(None);
-    }
-    
-    // 读取历史索引
-    let index_content = std::fs::read_to_string(&history_index_path)
-        .map_err(|e| format!("读取历史索引失败: {}", e))?;
-    
-    let history_index: Vec<serde_json::Value> = serde_json::from_str(&index_content)
-        .unwrap_or_else(|_| vec![]);
+    app_lock::ensure_unlocked()?;
+    encryption::ensure_unlocked()?;
     
     // 查找指定任务
-    if let Some(index_item) = history_index.iter().find(|item| {
-        item.get("id").and_then(|v| v.as_str()) == Some(&task_id)
-    }) {
-        if let Some(relative_path) = index_item.get("relativePath").and_then(|v| v.as_str()) {
-            let task_file_path = current_dir.join("coredata").join("history").join(relative_path);
-            
-            if task_file_path.exists() {
-                let task_content = std::fs::read_to_string(&task_file_path)
-                    .map_err(|e| format!("读取任务文件失败: {}", e))?;
-                
-                if let Ok(task_file_data) = serde_json::from_str::<serde_json::Value>(&task_content) {
-                    if let Some(task_data) = task_file_data.get("task-data") {
-                        log::info!("成功加载历史任务: {}", task_id);
-                        return Ok(Some(task_data.clone()));
-                    }
-                }
-            }
-        }
+    if let Some(mut task_data) = history::find_task(&task_id)? {
+        protection::redact(&mut task_data);
+        log::info!("成功加载历史任务: {}", task_id);
+        return Ok(Some(task_data));
     }
     
     log::info!("未找到历史任务: {}", task_id);
@@ -654,129 +805,87 @@ async fn get_history_task(task_id: String) -> Result<Option<serde_json::Value>,
 
 // 删除历史任务
 #[tauri::command]
-async fn delete_history_task(task_id: String) -> Result<(), String> {
+async fn delete_history_task(
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_id: String,
+) -> Result<(), AppError> {
     log::info!("删除历史任务: {}", task_id);
+    app_lock::ensure_unlocked()?;
     
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let history_index_path = current_dir.join("coredata").join("history.json");
-    
-    if !history_index_path.exists() {
-        return Ok(());
-    }
-    
-    // 读取历史索引
-    let index_content = std::fs::read_to_string(&history_index_path)
-        .map_err(|e| format!("读取历史索引失败: {}", e))?;
-    
-    let mut history_index: Vec<serde_json::Value> = serde_json::from_str(&index_content)
-        .unwrap_or_else(|_| vec![]);
-    
-    // 查找并删除任务文件
-    if let Some(index_item) = history_index.iter().find(|item| {
-        item.get("id").and_then(|v| v.as_str()) == Some(&task_id)
-    }) {
-        if let Some(relative_path) = index_item.get("relativePath").and_then(|v| v.as_str()) {
-            let task_file_path = current_dir.join("coredata").join("history").join(relative_path);
-            
-            if task_file_path.exists() {
-                std::fs::remove_file(&task_file_path).map_err(|e| {
-                    format!("删除任务文件失败: {}", e)
-                })?;
-                log::info!("任务文件已删除: {:?}", task_file_path);
-            }
-        }
-    }
+    // 读-改-写索引期间持有索引锁，避免与并发的保存互相覆盖
+    let _index_guard = index_lock.lock();
     
     // 从索引中移除
-    history_index.retain(|item| {
-        item.get("id").and_then(|v| v.as_str()) != Some(&task_id)
-    });
-    
-    // 保存更新后的索引
-    let index_content = serde_json::to_string_pretty(&history_index)
-        .map_err(|e| format!("序列化索引失败: {}", e))?;
+    let removed = history::remove_index_entry(&task_id)?;
     
-    std::fs::write(&history_index_path, index_content).map_err(|e| {
-        format!("保存历史索引失败: {}", e)
-    })?;
+    // 删除任务文件
+    for index_item in &removed {
+        history::delete_task_file(index_item)?;
+        log::info!("任务文件已删除: {:?}", index_item.get("relativePath"));
+    }
     
     log::info!("历史任务已删除: {}", task_id);
     Ok(())
 }
 
 // 清空所有历史记录
+//
+// backup 缺省为 true：先把全部数据打包到 coredata/backups/before-clear-<时间>.zip（可用 restore_backup 恢复），
+// 备份成功后才删除，备份失败时取消清空并保留原数据。返回备份文件路径，不备份时返回 None。
 #[tauri::command]
-async fn clear_history_data() -> Result<(), String> {
+async fn clear_history_data(
+    app_handle: tauri::AppHandle,
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    backup: Option<bool>,
+) -> Result<Option<String>, AppError> {
     log::info!("清空所有历史记录");
-    
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let history_dir = current_dir.join("coredata").join("history");
+    app_lock::ensure_unlocked()?;
+    
+    let _index_guard = index_lock.lock();
+    
+    let backup_path = if backup.unwrap_or(true) {
+        // 批量导入中缓存的索引先落盘，保证备份内容完整
+        history::finish_bulk_import()?;
+        let backups_dir = history::coredata_dir()?.join("backups");
+        std::fs::create_dir_all(&backups_dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+        let backup_path = backups_dir.join(format!(
+            "before-clear-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        backup::write_full_backup(&app_handle, &backup_path).map_err(|e| {
+            let _ = std::fs::remove_file(&backup_path);
+            let error = format!("备份历史记录失败，已取消清空: {}", e);
+            log::error!("{}", error);
+            error
+        })?;
+        log::info!("清空前已备份到: {:?}", backup_path);
+        Some(backup_path.to_string_lossy().to_string())
+    } else {
+        None
+    };
     
     // 删除所有历史文件
-    if history_dir.exists() {
-        // 遍历年份目录
-        if let Ok(year_entries) = std::fs::read_dir(&history_dir) {
-            for year_entry in year_entries.flatten() {
-                if year_entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                    // 遍历月份目录
-                    if let Ok(month_entries) = std::fs::read_dir(year_entry.path()) {
-                        for month_entry in month_entries.flatten() {
-                            if month_entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                                // 删除月份目录中的所有JSON文件
-                                if let Ok(file_entries) = std::fs::read_dir(month_entry.path()) {
-                                    for file_entry in file_entries.flatten() {
-                                        if let Some(ext) = file_entry.path().extension() {
-                                            if ext == "json" {
-                                                let _ = std::fs::remove_file(file_entry.path());
-                                                log::info!("删除历史文件: {:?}", file_entry.path());
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    for task_file in history::history_task_files()? {
+        watcher::note_own_write(&task_file);
+        let _ = std::fs::remove_file(&task_file);
+        log::info!("删除历史文件: {:?}", task_file);
     }
     
-    // 清空索引文件
-    let history_index_path = current_dir.join("coredata").join("history.json");
-    let empty_index = serde_json::to_string_pretty(&serde_json::Value::Array(vec![]))
-        .map_err(|e| format!("序列化空索引失败: {}", e))?;
-    
-    std::fs::write(&history_index_path, empty_index).map_err(|e| {
-        format!("保存空索引失败: {}", e)
-    })?;
+    // 清空历史索引
+    history::update_history_index(|history_index| history_index.clear())?;
     
     log::info!("所有历史记录已清空");
-    Ok(())
+    Ok(backup_path)
 }
 
 // 获取历史记录统计信息
 #[tauri::command]
-async fn get_history_stats() -> Result<serde_json::Value, String> {
+async fn get_history_stats() -> Result<serde_json::Value, AppError> {
     log::info!("获取历史记录统计信息");
-    
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-    let history_index_path = current_dir.join("coredata").join("history.json");
-    
-    if !history_index_path.exists() {
-        return Ok(serde_json::json!({
-            "total_tasks": 0,
-            "total_results": 0,
-            "years": [],
-            "months": {}
-        }));
-    }
+    app_lock::ensure_unlocked()?;
     
     // 读取历史索引
-    let index_content = std::fs::read_to_string(&history_index_path)
-        .map_err(|e| format!("读取历史索引失败: {}", e))?;
-    
-    let history_index: Vec<serde_json::Value> = serde_json::from_str(&index_content)
-        .unwrap_or_else(|_| vec![]);
+    let history_index = history::load_history_index()?;
     
     let mut total_results = 0;
     let mut years = std::collections::HashSet::new();
@@ -793,7 +902,7 @@ async fn get_history_stats() -> Result<serde_json::Value, String> {
             years.insert(year as i32);
         }
         
-        // 统计月份
+        // 统计月份（索引中的年月与任务文件所在目录一致）
         if let (Some(year), Some(month)) = (
             item.get("year").and_then(|v| v.as_i64()),
             item.get("month").and_then(|v| v.as_i64())
@@ -818,23 +927,77 @@ async fn get_history_stats() -> Result<serde_json::Value, String> {
 }
 
 fn main() {
+    let context = tauri::generate_context!();
+    
     // 初始化日志系统
-    if let Err(e) = init_logging() {
+    if let Err(e) = logging::init(&context.config().identifier) {
         eprintln!("日志系统初始化失败: {}", e);
     }
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .manage(SettingsWriter::default())
+        .manage(history::HistoryIndexLock::default())
         .setup(|app| {
+            // 检查数据目录是否可写，只读时改用用户目录
+            if let Err(e) = storage::init_data_root(app.handle()) {
+                log::error!("初始化数据目录失败: {}", e);
+            }
+            
+            // 获取实例锁，并清除崩溃后残留的过期锁
+            match lock::acquire_instance_lock() {
+                Ok(()) => {}
+                Err(AppError::Conflict(message)) => {
+                    // 已有其他实例在运行：不在无锁状态下继续运行，提示后退出
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.hide();
+                    }
+                    let app_handle = app.handle().clone();
+                    app.dialog()
+                        .message(format!("{}，请切换到已打开的窗口。", message))
+                        .title("星抽奖已在运行")
+                        .kind(MessageDialogKind::Warning)
+                        .show(move |_| app_handle.exit(0));
+                    return Ok(());
+                }
+                Err(e) => log::error!("获取实例锁失败: {}", e),
+            }
+            
             let main_window = app.get_webview_window("main").unwrap();
             
             // 设置窗口最小尺寸
             main_window.set_min_size(Some(tauri::LogicalSize::new(800.0, 600.0))).unwrap();
             
+            // 恢复设置中固定的显示器
+            window::restore_kiosk_monitor(app.handle(), &main_window);
+            
+            // 监视历史数据的外部修改
+            if let Err(e) = watcher::start(app.handle()) {
+                log::error!("启动文件监视失败: {}", e);
+            }
+            
+            // 启动定时自动备份
+            auto_backup::spawn(app.handle().clone());
+            scheduler::spawn(app.handle().clone());
+            
+            // 创建托盘图标
+            if let Err(e) = tray::init_tray(app) {
+                log::error!("创建托盘图标失败: {}", e);
+            }
+            
+            // 启动时在后台校验程序完整性，不一致时仅记录警告
+            tauri::async_runtime::spawn_blocking(|| {
+                if let Err(e) = audit::check_self_integrity() {
+                    log::error!("程序完整性校验出错: {}", e);
+                }
+            });
+            
             Ok(())
         })
+        .on_window_event(tray::handle_window_event)
         .invoke_handler(tauri::generate_handler![
             greet,
             save_lottery_result,
@@ -852,13 +1015,345 @@ fn main() {
             get_current_exe_path,
             get_app_info,
             save_history_task,
+            validate_task_consistency,
             get_history_data,
             get_history_task,
             delete_history_task,
             clear_history_data,
             get_history_stats,
             request_admin_privileges,
+            settings::flush_settings,
+            settings::diagnose_settings,
+            audit::verify_self_integrity,
+            audit::export_history_manifest,
+            audit::verify_ledger,
+            audit::audit_seed_reuse,
+            export::export_participant_history,
+            export::export_roster_checklist,
+            export::export_monthly_csv_zip,
+            export::export_history,
+            export::export_history_xlsx,
+            export::list_exports,
+            export::open_export,
+            poster::render_result_poster,
+            report::export_report_pdf,
+            report::generate_draw_report_pdf,
+            lock::force_release_lock,
+            background::set_background_tasks_enabled,
+            backup::export_profile,
+            backup::import_profile,
+            backup::list_backup_contents,
+            backup::restore_backup_entry,
+            backup::create_backup,
+            backup::restore_backup,
+            backup::backup_data,
+            backup::restore_data,
+            auto_backup::trigger_backup_now,
+            auto_backup::get_last_backup_time,
+            query::query_history_page,
+            query::search_history,
+            query::get_history_page,
+            stats::roster_stats,
+            stats::never_picked,
+            stats::usage_by_hour,
+            stats::compare_tasks,
+            stats::group_leaderboard,
+            stats::get_win_frequency,
+            stats::expected_unique_winners,
+            sound::import_sound,
+            sound::play_sound,
+            import::import_participants_streaming,
+            import::import_roster,
+            import::import_names_csv,
+            import::list_xlsx_sheets,
+            import::import_names_xlsx,
+            roster::list_groups,
+            roster::get_group,
+            roster::create_group,
+            roster::rename_group,
+            roster::delete_group,
+            roster::add_members,
+            roster::remove_members,
+            draw::draw_winners,
+            draw::draw_with_entropy,
+            draw::redraw_slot,
+            draw::draw_tiered,
+            draw::draw_lottery,
+            draw::draw_lottery_with_seed,
+            draw::draw_weighted,
+            draw::draw_without_replacement,
+            draw::draw_names,
+            draw::draw_with_seed,
+            draw::mark_drawn,
+            draw::get_remaining,
+            draw::reset_pool,
+            draw::reset_draw_pool,
+            encryption::unlock_history,
+            app_lock::set_master_password,
+            app_lock::verify_master_password,
+            app_lock::lock_app,
+            app_lock::get_app_lock_status,
+            logging::set_log_level,
+            logging::get_recent_logs,
+            logging::export_logs,
+            notification::notify_result,
+            scheduler::schedule_draw,
+            scheduler::list_schedules,
+            scheduler::cancel_schedule,
+            encryption::unlock_history_task,
+            encryption::set_task_password,
+            encryption::set_encryption_passphrase,
+            update::check_for_update,
+            window::move_to_monitor,
+            window::open_projector_window,
+            window::push_to_projector,
+            tray::set_close_to_tray,
+            watcher::start_watching,
+            watcher::stop_watching,
+            storage::get_writable_root,
+            ui_state::save_ui_state,
+            ui_state::load_ui_state,
+            rules::check_win_caps,
+            rules::find_overlong_names,
+            rules::export_rules,
+            rules::import_rules,
+            history::begin_bulk_import,
+            history::end_bulk_import,
+            history::get_history_data_paged,
+            history::rebuild_history_index,
+            history::count_history_files,
+            history::compact_history,
+            history::set_task_favorite,
+            history::set_history_retention,
+            protection::set_task_protection,
+            protection::verify_task_protection,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-} 
\ No newline at end of file
+        .build(context)
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            // 退出前写入尚未落盘的设置和历史索引，并释放实例锁
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = app_handle.state::<SettingsWriter>().flush() {
+                    log::error!("退出时写入设置失败: {}", e);
+                }
+                // 批量导入未正常结束时，退出前写入缓存的索引
+                let index_lock = app_handle.state::<history::HistoryIndexLock>();
+                let _index_guard = index_lock.lock();
+                if let Err(e) = history::finish_bulk_import() {
+                    log::error!("退出时写入历史索引失败: {}", e);
+                }
+                lock::release_instance_lock();
+            }
+        });
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use storage::test_support::TempDataRoot;
+
+    fn task(id: &str, name: &str, timestamp: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "name": name,
+            "timestamp": timestamp,
+            "results": ["张三"],
+            "total_count": 1,
+            "participants": ["张三", "李四", "王五"]
+        })
+    }
+
+    fn save(task_data: serde_json::Value) -> Result<(), AppError> {
+        write_history_task(task_data, &serde_json::json!({}))
+    }
+
+    fn task_file(task_id: &str) -> std::path::PathBuf {
+        history::find_task_file(task_id).unwrap().expect("任务文件应存在")
+    }
+
+    #[test]
+    fn edit_password_is_verified_by_backend() {
+        let _root = TempDataRoot::new("protection");
+        let mut protected = task("task-1", "期末抽奖", "2024-06-01T10:00:00+08:00");
+        protected["edit_protected"] = serde_json::json!(true);
+        protected["edit_password"] = serde_json::json!("correct horse");
+        save(protected).unwrap();
+
+        let content = std::fs::read_to_string(task_file("task-1")).unwrap();
+        assert!(!content.contains("correct horse"), "任务文件不应保存明文密码");
+        assert!(!protection::check_task_protection("task-1", "wrong").unwrap());
+        assert!(protection::check_task_protection("task-1", "correct horse").unwrap());
+    }
+
+    #[test]
+    fn changing_protection_requires_current_password() {
+        let _root = TempDataRoot::new("protection-change");
+        let mut protected = task("task-3", "期末抽奖", "2024-06-01T10:00:00+08:00");
+        protected["edit_protected"] = serde_json::json!(true);
+        protected["edit_password"] = serde_json::json!("secret");
+        save(protected).unwrap();
+
+        // 不提供当前密码时不能取消保护，也不能换成新密码
+        let mut unprotected = task("task-3", "期末抽奖", "2024-06-01T10:00:00+08:00");
+        unprotected["edit_protected"] = serde_json::json!(false);
+        assert!(matches!(save(unprotected.clone()), Err(AppError::PermissionDenied(_))));
+        let mut replaced = task("task-3", "期末抽奖", "2024-06-01T10:00:00+08:00");
+        replaced["edit_protected"] = serde_json::json!(true);
+        replaced["edit_password"] = serde_json::json!("hijacked");
+        replaced["current_edit_password"] = serde_json::json!("wrong");
+        assert!(matches!(save(replaced), Err(AppError::PermissionDenied(_))));
+        assert!(protection::check_task_protection("task-3", "secret").unwrap());
+
+        // 不改动保护设置的重新保存沿用原哈希
+        let mut resaved = task("task-3", "期末抽奖", "2024-06-01T10:00:00+08:00");
+        resaved["edit_protected"] = serde_json::json!(true);
+        save(resaved).unwrap();
+        assert!(protection::check_task_protection("task-3", "secret").unwrap());
+
+        unprotected["current_edit_password"] = serde_json::json!("secret");
+        save(unprotected).unwrap();
+        let saved = history::find_task("task-3").unwrap().unwrap();
+        assert!(saved.get("edit_password_hash").is_none());
+        assert!(saved.get("current_edit_password").is_none());
+        assert!(protection::check_task_protection("task-3", "").unwrap());
+    }
+
+    #[test]
+    fn legacy_plaintext_password_is_rehashed() {
+        let _root = TempDataRoot::new("protection-legacy");
+        let mut protected = task("task-2", "旧任务", "2024-06-01T10:00:00+08:00");
+        protected["edit_protected"] = serde_json::json!(true);
+        protected["edit_password"] = serde_json::json!("secret");
+        save(protected).unwrap();
+
+        // 改写成旧版本保存明文密码的格式
+        let path = task_file("task-2");
+        let mut file_data: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let task_data = file_data["task-data"].as_object_mut().unwrap();
+        task_data.remove("edit_password_hash");
+        task_data.insert("edit_password".to_string(), serde_json::json!("secret"));
+        std::fs::write(&path, serde_json::to_string(&file_data).unwrap()).unwrap();
+
+        assert!(!protection::check_task_protection("task-2", "wrong").unwrap());
+        assert!(protection::check_task_protection("task-2", "secret").unwrap());
+        let migrated: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(migrated["task-data"].get("edit_password").is_none());
+        assert!(password::is_current(migrated["task-data"]["edit_password_hash"].as_str().unwrap()));
+        assert!(protection::check_task_protection("task-2", "secret").unwrap());
+    }
+
+    #[test]
+    fn paged_history_reads_only_the_requested_page() {
+        let _root = TempDataRoot::new("paged");
+        for i in 0..100 {
+            let timestamp = format!("2024-06-01T{:02}:{:02}:00+08:00", i / 60, i % 60);
+            save(task(&format!("task-{:03}", i), &format!("抽奖{}", i), &timestamp)).unwrap();
+        }
+
+        history::TASK_FILE_READS.store(0, std::sync::atomic::Ordering::SeqCst);
+        let page = tauri::async_runtime::block_on(history::get_history_data_paged(0, 10)).unwrap();
+        assert!(history::TASK_FILE_READS.load(std::sync::atomic::Ordering::SeqCst) <= 10);
+
+        assert_eq!(page["total"], 100);
+        let ids: Vec<&str> = page["items"].as_array().unwrap().iter().map(|item| item["id"].as_str().unwrap()).collect();
+        let expected: Vec<String> = (90..100).rev().map(|i| format!("task-{:03}", i)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    fn save_with_retention(task_data: serde_json::Value, retention: u64) {
+        write_history_task(task_data, &serde_json::json!({ "historyRetention": retention })).unwrap();
+    }
+
+    fn task_files(root: &TempDataRoot) -> Vec<std::path::PathBuf> {
+        let history_dir = root.coredata().join("history").join("2024").join("06");
+        let mut files: Vec<_> = std::fs::read_dir(history_dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn retention_evicts_and_deletes_oldest_task() {
+        let root = TempDataRoot::new("retention");
+        for i in 1..=5 {
+            save_with_retention(task(&format!("task-{}", i), "抽奖", &format!("2024-06-0{}T10:00:00+08:00", i)), 5);
+        }
+        let oldest = task_file("task-1");
+        assert_eq!(task_files(&root).len(), 5);
+
+        save_with_retention(task("task-6", "抽奖", "2024-06-06T10:00:00+08:00"), 5);
+        assert!(history::find_index_entry("task-1").unwrap().is_none());
+        assert!(!oldest.exists(), "被移出索引的任务文件应被删除");
+        assert_eq!(task_files(&root).len(), 5);
+        assert_eq!(history::load_history_index().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn retention_zero_keeps_everything() {
+        let root = TempDataRoot::new("retention-unlimited");
+        for i in 1..=8 {
+            save_with_retention(task(&format!("task-{}", i), "抽奖", &format!("2024-06-0{}T10:00:00+08:00", i)), 0);
+        }
+        assert_eq!(task_files(&root).len(), 8);
+        assert_eq!(history::load_history_index().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn concurrent_saves_keep_every_record() {
+        let _root = TempDataRoot::new("concurrent");
+        let index_lock = history::HistoryIndexLock::default();
+        std::thread::scope(|scope| {
+            for window in 0..8 {
+                let index_lock = &index_lock;
+                scope.spawn(move || {
+                    for i in 0..5 {
+                        let task_data = task(&format!("task-{}-{}", window, i), "并发抽奖", &format!("2024-06-01T1{}:0{}:00+08:00", window, i));
+                        let _index_guard = index_lock.lock();
+                        save(task_data).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(history::load_history_index().unwrap().len(), 40);
+        for window in 0..8 {
+            for i in 0..5 {
+                assert!(history::find_task(&format!("task-{}-{}", window, i)).unwrap().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn colliding_task_id_is_rejected() {
+        let _root = TempDataRoot::new("collision");
+        let mut first = task("dup", "期末 抽奖", "2024-06-01T10:00:00+08:00");
+        first["results"] = serde_json::json!(["张三"]);
+        save(first.clone()).unwrap();
+        let path = task_file("dup");
+
+        // 另一次抽取：id 相同，名称清理后也相同，时间戳不同
+        let mut second = task("dup", "期末_抽奖", "2024-06-02T10:00:00+08:00");
+        second["results"] = serde_json::json!(["李四"]);
+        assert!(matches!(save(second), Err(AppError::Conflict(_))));
+
+        let kept = history::read_task_file(&path).unwrap();
+        assert_eq!(kept["results"], serde_json::json!(["张三"]));
+        assert_eq!(history::load_history_index().unwrap().len(), 1);
+
+        // 同一次抽取重新保存不算冲突
+        save(first).unwrap();
+    }
+
+    #[test]
+    fn folder_month_follows_local_date_across_month_boundary() {
+        let root = TempDataRoot::new("month-boundary");
+        // UTC+8 的 6 月 1 日 00:30 在 UTC 仍是 5 月 31 日；UTC-5 的 5 月 31 日 23:30 在 UTC 已是 6 月 1 日
+        save(task("east", "午夜抽奖", "2024-06-01T00:30:00+08:00")).unwrap();
+        save(task("west", "午夜抽奖", "2024-05-31T23:30:00-05:00")).unwrap();
+
+        let history_dir = root.coredata().join("history");
+        for (task_id, month) in [("east", 6), ("west", 5)] {
+            let entry = history::find_index_entry(task_id).unwrap().unwrap();
+            assert_eq!((entry["year"].as_i64(), entry["month"].as_i64()), (Some(2024), Some(month)));
+            assert!(task_file(task_id).starts_with(history_dir.join("2024").join(format!("{:02}", month))));
+        }
+    }
+}
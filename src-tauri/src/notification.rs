@@ -0,0 +1,67 @@
+// 系统通知
+//
+// 通过 Tauri 的通知插件显示原生桌面通知。前端可以用 notify_result 显示任意通知；
+// 后台完成的抽取（定时抽取）由后端调用 notify_draw_result 自动通知，设置 drawNotifications 为 false 时不通知。
+
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::AppError;
+use crate::settings;
+
+// 设置中记录是否自动通知抽取结果的键
+const DRAW_NOTIFICATIONS_KEY: &str = "drawNotifications";
+
+// 通知中最多列出的中奖者数，其余以“等 N 人”概括
+const MAX_LISTED_WINNERS: usize = 10;
+
+// 显示一条系统通知
+pub fn show(app_handle: &tauri::AppHandle, title: &str, body: &str) -> Result<(), String> {
+    app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+        .map_err(|e| format!("显示系统通知失败: {}", e))
+}
+
+// 通知正文：列出中奖者，人数较多时只列出前几位
+fn winners_text(winners: &[String]) -> String {
+    if winners.is_empty() {
+        return "没有抽出结果".to_string();
+    }
+    let listed = winners[..winners.len().min(MAX_LISTED_WINNERS)].join("、");
+    if winners.len() > MAX_LISTED_WINNERS {
+        format!("{} 等 {} 人", listed, winners.len())
+    } else {
+        listed
+    }
+}
+
+// 后台抽取完成后自动通知结果；source 说明抽取来源（如“定时抽取”），未启用 drawNotifications 时不通知
+pub fn notify_draw_result(app_handle: &tauri::AppHandle, source: &str, name: &str, winners: &[String]) {
+    let enabled = settings::current_settings(app_handle)
+        .ok()
+        .and_then(|s| s.get(DRAW_NOTIFICATIONS_KEY).and_then(|v| v.as_bool()))
+        .unwrap_or(true);
+    if !enabled {
+        return;
+    }
+
+    let title = format!("{}：{}", source, name);
+    if let Err(e) = show(app_handle, &title, &winners_text(winners)) {
+        log::warn!("{}", e);
+    }
+}
+
+// 显示一条系统通知，例如抽取结果
+#[tauri::command]
+pub async fn notify_result(app_handle: tauri::AppHandle, title: String, body: String) -> Result<(), AppError> {
+    if title.trim().is_empty() {
+        return Err(AppError::InvalidInput("通知标题不能为空".to_string()));
+    }
+    show(&app_handle, &title, &body).map_err(|e| {
+        log::error!("{}", e);
+        AppError::from(e)
+    })
+}
@@ -0,0 +1,126 @@
+// 密码哈希与密钥派生
+//
+// 应用锁的主密码、任务的编辑密码、历史记录加密密码和任务密码统一使用 Argon2id，参数与盐以 PHC 字符串保存：
+// 只需校验的密码保存完整的 PHC 哈希（$argon2id$v=19$m=..,t=..,p=..$<盐>$<哈希>），见 hash / verify；
+// 用于加密的密码只保存不含哈希部分的参数串（$argon2id$v=19$m=..,t=..,p=..$<盐>），按参数串派生 32 字节密钥，见 derive_key。
+//
+// 旧版本的编辑密码与加密密码使用 PBKDF2-SHA256（pbkdf2-sha256$<迭代次数>$<盐>$<哈希>），仍可校验和解锁，
+// 调用方在校验成功后改写为 Argon2id。
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use sha2::Sha256;
+
+// 派生的密钥长度
+const KEY_LENGTH: usize = 32;
+
+const SALT_LENGTH: usize = 16;
+
+// 旧版本 PBKDF2 哈希的前缀
+const LEGACY_SCHEME: &str = "pbkdf2-sha256";
+
+// 用 Argon2id（默认参数、随机盐）计算密码的 PHC 哈希
+pub fn hash(password: &str) -> Result<String, String> {
+    let mut salt = [0u8; SALT_LENGTH];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).map_err(|e| format!("生成盐失败: {}", e))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("计算密码哈希失败: {}", e))
+}
+
+// 校验密码与保存的哈希是否匹配，支持 Argon2id PHC 哈希与旧的 PBKDF2 哈希；哈希格式无效时返回错误
+pub fn verify(password: &str, stored: &str) -> Result<bool, String> {
+    if stored.starts_with(LEGACY_SCHEME) {
+        return verify_legacy(password, stored);
+    }
+    let hash = PasswordHash::new(stored).map_err(|e| format!("密码哈希无效: {}", e))?;
+    Ok(Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+// 保存的哈希是否为当前格式（Argon2id），否则应在校验成功后用 hash 重新计算
+pub fn is_current(stored: &str) -> bool {
+    PasswordHash::new(stored).is_ok_and(|hash| hash.algorithm == Algorithm::Argon2id.ident())
+}
+
+// 为新的加密密码生成 Argon2id 参数串（默认参数、随机盐），派生密钥时传给 derive_key
+pub fn new_key_params() -> Result<String, String> {
+    let mut salt = [0u8; SALT_LENGTH];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    key_params(Params::DEFAULT_M_COST, Params::DEFAULT_T_COST, Params::DEFAULT_P_COST, &salt)
+}
+
+// 由 Argon2id 参数与原始盐生成参数串；旧版本任务密码单独保存的参数与盐转换后派生出的密钥不变
+pub fn key_params(m_cost: u32, t_cost: u32, p_cost: u32, salt: &[u8]) -> Result<String, String> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_LENGTH)).map_err(|e| format!("密钥参数无效: {}", e))?;
+    let params = argon2::password_hash::ParamsString::try_from(&params).map_err(|e| format!("密钥参数无效: {}", e))?;
+    let salt = SaltString::encode_b64(salt).map_err(|e| format!("盐无效: {}", e))?;
+    Ok(format!(
+        "${}$v={}${}${}",
+        Algorithm::Argon2id.ident(),
+        u32::from(Version::V0x13),
+        params,
+        salt.as_str()
+    ))
+}
+
+// 按参数串中的 Argon2id 参数与盐，从密码派生 32 字节密钥
+pub fn derive_key(password: &str, key_params: &str) -> Result<[u8; 32], String> {
+    let parsed = PasswordHash::new(key_params).map_err(|e| format!("密钥参数无效: {}", e))?;
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return Err(format!("不支持的密钥派生算法: {}", parsed.algorithm));
+    }
+    let salt = parsed.salt.ok_or("密钥参数缺少盐")?;
+    let version = Version::try_from(parsed.version.unwrap_or(u32::from(Version::V0x13)))
+        .map_err(|e| format!("密钥参数无效: {}", e))?;
+    let stored = Params::try_from(&parsed).map_err(|e| format!("密钥参数无效: {}", e))?;
+    let params = Params::new(stored.m_cost(), stored.t_cost(), stored.p_cost(), Some(KEY_LENGTH))
+        .map_err(|e| format!("密钥参数无效: {}", e))?;
+
+    let hash = Argon2::new(Algorithm::Argon2id, version, params)
+        .hash_password(password.as_bytes(), salt)
+        .map_err(|e| format!("派生密钥失败: {}", e))?;
+    let output = hash.hash.ok_or("派生密钥失败")?;
+    let mut key = [0u8; KEY_LENGTH];
+    key.copy_from_slice(output.as_bytes());
+    Ok(key)
+}
+
+// 旧版本的 PBKDF2-SHA256 密钥派生，只用于解锁旧的加密配置
+pub fn derive_legacy_key(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut output = [0u8; KEY_LENGTH];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut output);
+    output
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// 逐字节比较全部内容，耗时与匹配位置无关
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// 校验旧格式 pbkdf2-sha256$<迭代次数>$<盐>$<哈希>（十六进制）
+fn verify_legacy(password: &str, stored: &str) -> Result<bool, String> {
+    let parts: Vec<&str> = stored.split('$').collect();
+    let [LEGACY_SCHEME, iterations, salt, hash] = parts.as_slice() else {
+        return Err("密码哈希无效".to_string());
+    };
+    let (Ok(iterations), Some(salt), Some(hash)) = (iterations.parse::<u32>(), from_hex(salt), from_hex(hash)) else {
+        return Err("密码哈希无效".to_string());
+    };
+    if iterations == 0 {
+        return Err("密码哈希无效".to_string());
+    }
+    Ok(constant_time_eq(&derive_legacy_key(password, &salt, iterations), &hash))
+}
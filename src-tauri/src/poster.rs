@@ -0,0 +1,196 @@
+// 结果海报
+//
+// 把中奖名单绘制到主办方提供的背景图片上并保存为 PNG。
+// 程序不内置字体，绘制时使用系统自带的中文字体。
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+use image::{ImageFormat, Rgba, RgbaImage};
+
+use crate::error::AppError;
+use crate::export;
+
+// 依次尝试的系统中文字体
+const CJK_FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+];
+
+const TEXT_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const SHADOW_COLOR: Rgba<u8> = Rgba([0, 0, 0, 160]);
+
+// 支持的背景图片格式
+const SUPPORTED_FORMATS: &[ImageFormat] = &[ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Bmp];
+
+// 加载第一个可用的系统中文字体（字体集合取其中第一个字体）
+fn load_cjk_font() -> Result<FontVec, String> {
+    for path in CJK_FONT_CANDIDATES {
+        let Ok(data) = std::fs::read(path) else {
+            continue;
+        };
+        match FontVec::try_from_vec_and_index(data, 0) {
+            Ok(font) => {
+                log::info!("使用字体: {}", path);
+                return Ok(font);
+            }
+            Err(e) => log::warn!("加载字体失败 {}: {}", path, e),
+        }
+    }
+    Err("未找到可用的中文字体".to_string())
+}
+
+// 一行文字的宽度（像素）
+fn text_width(font: &FontVec, scale: PxScale, text: &str) -> f32 {
+    let scaled = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// 把颜色按覆盖率混合到像素上
+fn blend(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let alpha = coverage.clamp(0.0, 1.0) * color[3] as f32 / 255.0;
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    for i in 0..3 {
+        pixel[i] = (pixel[i] as f32 * (1.0 - alpha) + color[i] as f32 * alpha).round() as u8;
+    }
+    pixel[3] = pixel[3].max((alpha * 255.0).round() as u8);
+}
+
+// 从 (x, top) 开始绘制一行文字，top 为这一行的顶部
+fn draw_line(image: &mut RgbaImage, font: &FontVec, scale: PxScale, text: &str, x: f32, top: f32, color: Rgba<u8>) {
+    let scaled = font.as_scaled(scale);
+    let baseline = top + scaled.ascent();
+    let mut caret = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, ab_glyph::point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                blend(
+                    image,
+                    bounds.min.x as i64 + gx as i64,
+                    bounds.min.y as i64 + gy as i64,
+                    color,
+                    coverage,
+                );
+            });
+        }
+    }
+}
+
+// 把中奖名单绘制到背景图片上，保存为 PNG
+//
+// 每位中奖者占一行；未指定 x 时每行水平居中，未指定 y 时整体垂直居中。
+// 文字超出图片范围时返回错误，不会生成被裁切的海报。
+#[tauri::command]
+pub async fn render_result_poster(
+    winners: Vec<String>,
+    background_path: String,
+    dest_path: String,
+    font_size: f32,
+    x: Option<f32>,
+    y: Option<f32>,
+) -> Result<(), AppError> {
+    log::info!("生成结果海报: {} 名中奖者 -> {}", winners.len(), dest_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    if !font_size.is_finite() || font_size <= 0.0 {
+        return Err(AppError::InvalidInput(format!("字号无效: {}", font_size)));
+    }
+
+    let lines: Vec<&str> = winners.iter().map(|w| w.trim()).filter(|w| !w.is_empty()).collect();
+    if lines.is_empty() {
+        return Err(AppError::InvalidInput("中奖名单为空".to_string()));
+    }
+
+    let background = std::path::Path::new(&background_path);
+    if !background.is_file() {
+        return Err(AppError::NotFound(format!("背景图片不存在: {}", background_path)));
+    }
+
+    let reader = image::ImageReader::open(background)
+        .and_then(|r| r.with_guessed_format())
+        .map_err(|e| format!("读取背景图片失败: {}", e))?;
+    match reader.format() {
+        Some(format) if SUPPORTED_FORMATS.contains(&format) => {}
+        _ => return Err(AppError::InvalidInput("不支持的背景图片格式，仅支持 PNG、JPEG、BMP".to_string())),
+    }
+    let mut image = reader
+        .decode()
+        .map_err(|e| format!("解码背景图片失败: {}", e))?
+        .to_rgba8();
+
+    let font = load_cjk_font()?;
+    let scale = PxScale::from(font_size);
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let block_height = line_height * lines.len() as f32;
+    let top = y.unwrap_or((height - block_height) / 2.0);
+    if top < 0.0 || top + block_height > height {
+        return Err(AppError::InvalidInput(format!(
+            "文字超出图片范围：共 {} 行需要 {:.0} 像素高度，图片高度为 {:.0}",
+            lines.len(),
+            block_height,
+            height
+        )));
+    }
+
+    // 先计算全部位置并检查越界，再开始绘制
+    let mut placements = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line_width = text_width(&font, scale, line);
+        let left = x.unwrap_or((width - line_width) / 2.0);
+        if left < 0.0 || left + line_width > width {
+            return Err(AppError::InvalidInput(format!(
+                "文字超出图片范围：\"{}\" 需要 {:.0} 像素宽度，图片宽度为 {:.0}",
+                line, line_width, width
+            )));
+        }
+        placements.push((*line, left, top + line_height * i as f32));
+    }
+
+    // 文字带一层半透明阴影，保证在浅色背景上也能看清
+    let shadow_offset = (font_size / 20.0).max(1.0);
+    for (line, left, line_top) in placements {
+        draw_line(&mut image, &font, scale, line, left + shadow_offset, line_top + shadow_offset, SHADOW_COLOR);
+        draw_line(&mut image, &font, scale, line, left, line_top, TEXT_COLOR);
+    }
+
+    image.save_with_format(&dest_path, ImageFormat::Png).map_err(|e| {
+        let error = format!("保存海报失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    export::record_export(&dest_path, "result-poster", Some(lines.len()), None);
+    log::info!("结果海报已保存: {}", dest_path);
+    Ok(())
+}
@@ -0,0 +1,205 @@
+// 历史任务的编辑保护
+//
+// 任务文件只保存编辑密码的 Argon2id 哈希（edit_password_hash，PHC 字符串，见 password.rs），不保存明文密码，
+// 返回给前端的任务数据会去掉哈希和旧的 edit_password 字段，密码只能通过 verify_task_protection 校验。
+// 旧任务中的 edit_password（明文或前端 encryptPassword 的编码）以及旧版本的 PBKDF2 哈希在第一次验证成功后改写为 Argon2id 哈希。
+// 已受保护的任务要更换密码或取消保护时，须提供当前密码（保存时放在 current_edit_password 字段中）。
+
+use base64::Engine;
+
+use crate::error::AppError;
+use crate::password::constant_time_eq;
+use crate::{encryption, history, password, storage};
+
+const HASH_FIELD: &str = "edit_password_hash";
+const LEGACY_FIELD: &str = "edit_password";
+const CURRENT_PASSWORD_FIELD: &str = "current_edit_password";
+
+// 还原前端 encryptPassword 的编码：Base64 -> 每个字符减 3 -> Base64 -> UTF-8
+fn decode_legacy_password(stored: &str) -> Option<String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let shifted = engine.decode(stored).ok()?;
+    let inner: Vec<u8> = shifted.iter().map(|b| b.checked_sub(3)).collect::<Option<_>>()?;
+    String::from_utf8(engine.decode(inner).ok()?).ok()
+}
+
+// 旧格式的密码校验：保存的可能是明文，也可能是 encryptPassword 的编码
+fn verify_legacy_password(password: &str, stored: &str) -> bool {
+    constant_time_eq(password.as_bytes(), stored.as_bytes())
+        || decode_legacy_password(stored).is_some_and(|decoded| constant_time_eq(password.as_bytes(), decoded.as_bytes()))
+}
+
+fn task_str<'a>(task_data: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    task_data.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty())
+}
+
+// 已受保护的任务更换密码或取消保护前校验当前密码（调用方须持有历史索引锁）
+fn ensure_current_password(existing: &serde_json::Value, current_password: Option<&str>) -> Result<(), AppError> {
+    if task_str(existing, HASH_FIELD).is_none() && task_str(existing, LEGACY_FIELD).is_none() {
+        return Ok(());
+    }
+    let task_id = task_str(existing, "id").unwrap_or_default();
+    if check_task_protection(task_id, current_password.unwrap_or_default())? {
+        return Ok(());
+    }
+    let error = AppError::PermissionDenied(format!("任务 {} 已启用编辑保护，更换密码或取消保护需要输入当前密码", task_id));
+    log::warn!("{}", error);
+    Err(error)
+}
+
+// 保存任务前处理编辑保护：把传入的明文密码替换为哈希
+//
+// 前端拿到的任务数据不含哈希，重新保存受保护的任务时沿用原任务文件中的哈希；
+// 取消保护或传入新密码时先用 current_edit_password 校验原任务的密码（调用方须持有历史索引锁）。
+pub fn protect_task_data(task_data: &mut serde_json::Value, existing: Option<&serde_json::Value>) -> Result<(), AppError> {
+    let protected = task_data.get("edit_protected").and_then(|v| v.as_bool()).unwrap_or(false);
+    let password = task_str(task_data, LEGACY_FIELD).map(|s| s.to_string());
+    let current_password = task_str(task_data, CURRENT_PASSWORD_FIELD).map(|s| s.to_string());
+    let existing_hash = existing.and_then(|e| task_str(e, HASH_FIELD)).map(|s| s.to_string());
+    let existing_legacy = existing.and_then(|e| task_str(e, LEGACY_FIELD)).map(|s| s.to_string());
+
+    if let Some(existing) = existing.filter(|_| !protected || password.is_some()) {
+        ensure_current_password(existing, current_password.as_deref())?;
+    }
+
+    let Some(obj) = task_data.as_object_mut() else {
+        return Ok(());
+    };
+    obj.remove(LEGACY_FIELD);
+    obj.remove(CURRENT_PASSWORD_FIELD);
+
+    if !protected {
+        obj.remove(HASH_FIELD);
+        return Ok(());
+    }
+    if let Some(password) = password {
+        obj.insert(HASH_FIELD.to_string(), serde_json::Value::String(password::hash(&password)?));
+    } else if !obj.contains_key(HASH_FIELD) {
+        if let Some(hash) = existing_hash {
+            obj.insert(HASH_FIELD.to_string(), serde_json::Value::String(hash));
+        } else if let Some(legacy) = existing_legacy {
+            // 尚未迁移的旧任务保留原字段，等待第一次验证成功后迁移
+            obj.insert(LEGACY_FIELD.to_string(), serde_json::Value::String(legacy));
+        }
+    }
+    Ok(())
+}
+
+// 去掉返回给前端的任务数据中的密码相关字段
+pub fn redact(task_data: &mut serde_json::Value) {
+    if let Some(obj) = task_data.as_object_mut() {
+        obj.remove(HASH_FIELD);
+        obj.remove(LEGACY_FIELD);
+    }
+}
+
+// 修改任务文件中的 task-data 并原子写回（调用方须持有历史索引锁）
+fn update_task_file<F>(task_id: &str, update: F) -> Result<(), AppError>
+where
+    F: FnOnce(&mut serde_json::Value),
+{
+    let path = history::find_task_file(task_id)?.ok_or_else(|| AppError::NotFound(format!("任务不存在: {}", task_id)))?;
+    let content = std::fs::read_to_string(&path).map_err(|e| AppError::io("读取任务文件失败", e))?;
+    let mut task_file_data: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| AppError::Parse(format!("解析任务文件失败: {}", e)))?;
+    // 加密的任务解密后修改，再以加密形式写回
+    let mut task_data = encryption::task_data_of(&task_file_data)?;
+    update(&mut task_data);
+    let encrypted = encryption::is_encrypted(&task_file_data);
+    encryption::store_task_data(&mut task_file_data, task_data, encrypted)?;
+
+    let content = serde_json::to_string_pretty(&task_file_data)
+        .map_err(|e| format!("序列化任务数据失败: {}", e))?;
+    storage::write_atomic(&path, content.as_bytes()).map_err(|e| {
+        let error = AppError::io("写入任务文件失败", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 设置任务的编辑密码，密码为空时取消编辑保护；任务已受保护时须提供正确的当前密码
+#[tauri::command]
+pub async fn set_task_protection(
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_id: String,
+    password: String,
+    current_password: Option<String>,
+) -> Result<(), AppError> {
+    log::info!("设置任务编辑保护: {}", task_id);
+    crate::app_lock::ensure_unlocked()?;
+
+    let hash = if password.is_empty() { None } else { Some(password::hash(&password)?) };
+    let _index_guard = index_lock.lock();
+    let existing = history::find_task(&task_id)?.ok_or_else(|| AppError::NotFound(format!("任务不存在: {}", task_id)))?;
+    ensure_current_password(&existing, current_password.as_deref())?;
+    update_task_file(&task_id, |task_data| {
+        if let Some(obj) = task_data.as_object_mut() {
+            obj.remove(LEGACY_FIELD);
+            match hash {
+                None => {
+                    obj.remove(HASH_FIELD);
+                    obj.insert("edit_protected".to_string(), serde_json::Value::Bool(false));
+                }
+                Some(hash) => {
+                    obj.insert(HASH_FIELD.to_string(), serde_json::Value::String(hash));
+                    obj.insert("edit_protected".to_string(), serde_json::Value::Bool(true));
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+// 校验任务的编辑密码，未设置保护的任务总是返回 true
+//
+// 旧任务的 edit_password 与 PBKDF2 哈希校验成功后会改写为 Argon2id 哈希。
+#[tauri::command]
+pub async fn verify_task_protection(
+    index_lock: tauri::State<'_, history::HistoryIndexLock>,
+    task_id: String,
+    password: String,
+) -> Result<bool, AppError> {
+    log::info!("校验任务编辑密码: {}", task_id);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _index_guard = index_lock.lock();
+    check_task_protection(&task_id, &password)
+}
+
+// verify_task_protection 的实现（调用方须持有历史索引锁）
+pub fn check_task_protection(task_id: &str, password: &str) -> Result<bool, AppError> {
+    let task_data = history::find_task(task_id)?.ok_or_else(|| AppError::NotFound(format!("任务不存在: {}", task_id)))?;
+
+    if !task_data.get("edit_protected").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return Ok(true);
+    }
+
+    if let Some(hash) = task_str(&task_data, HASH_FIELD) {
+        let matches = password::verify(password, hash).unwrap_or_else(|e| {
+            log::warn!("任务 {} 的{}", task_id, e);
+            false
+        });
+        if !matches || password::is_current(hash) {
+            return Ok(matches);
+        }
+    } else {
+        let Some(legacy) = task_str(&task_data, LEGACY_FIELD) else {
+            log::warn!("任务 {} 已启用编辑保护但没有保存密码", task_id);
+            return Ok(false);
+        };
+        if !verify_legacy_password(password, legacy) {
+            return Ok(false);
+        }
+    }
+
+    // 旧的明文、编码或 PBKDF2 哈希校验通过后改写为 Argon2id 哈希
+    let hash = password::hash(password)?;
+    update_task_file(task_id, |task_data| {
+        if let Some(obj) = task_data.as_object_mut() {
+            obj.remove(LEGACY_FIELD);
+            obj.insert(HASH_FIELD.to_string(), serde_json::Value::String(hash));
+        }
+    })?;
+    log::info!("任务 {} 的旧密码已迁移为 Argon2id 哈希", task_id);
+    Ok(true)
+}
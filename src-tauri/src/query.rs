@@ -0,0 +1,236 @@
+// 历史记录查询
+//
+// 列表页一次调用即可完成筛选、排序与分页。除中奖者外的筛选条件、排序与分页都在历史索引数据库中执行，
+// 只有按中奖者筛选时才需要读取任务文件。
+
+use crate::error::AppError;
+use crate::history;
+use crate::history_db;
+
+// 未指定 limit 时每页的条数
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+// 每页最多返回的条数
+const MAX_PAGE_SIZE: usize = 500;
+
+// 历史筛选条件
+//
+// { "name"?: 任务名称包含（按空白分成多个词时须全部包含，忽略大小写）, "groupName"?: 小组名称等于, "since"?/"until"?: RFC3339 时间范围（含两端）,
+//   "winner"?: 中奖者姓名（规范化后比较，需要读取任务文件）, "favorite"?: 是否收藏 }
+pub struct HistoryFilter {
+    name_terms: Vec<String>,
+    group_name: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    winner: Option<String>,
+    favorite: Option<bool>,
+}
+
+// 把任务名称搜索文本拆成小写的词
+fn search_terms(text: Option<&str>) -> Vec<String> {
+    text.map(|t| t.split_whitespace().map(|term| term.to_lowercase()).collect())
+        .unwrap_or_default()
+}
+
+fn filter_str(filter: &serde_json::Value, key: &str) -> Option<String> {
+    filter
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+impl HistoryFilter {
+    pub fn parse(filter: Option<&serde_json::Value>) -> Result<Self, AppError> {
+        let empty = serde_json::Value::Null;
+        let filter = filter.unwrap_or(&empty);
+        if !filter.is_null() && !filter.is_object() {
+            return Err(AppError::InvalidInput("筛选条件必须是 JSON 对象".to_string()));
+        }
+
+        Ok(HistoryFilter {
+            name_terms: search_terms(filter_str(filter, "name").as_deref()),
+            group_name: filter_str(filter, "groupName"),
+            since: history::parse_since(filter_str(filter, "since").as_deref())?,
+            until: history::parse_since(filter_str(filter, "until").as_deref())?,
+            winner: filter_str(filter, "winner").map(|s| history::normalize_name(&s)),
+            favorite: filter.get("favorite").and_then(|v| v.as_bool()),
+        })
+    }
+
+    // 任务的中奖者中是否有筛选的姓名；任务文件无法读取或解密时返回错误，而不是悄悄排除
+    fn matches_winner(item: &serde_json::Value, winner: &str) -> Result<bool, AppError> {
+        let task_data = history::read_task_data(item)?;
+        Ok(history::task_winners(&task_data)
+            .iter()
+            .any(|w| history::normalize_name(w) == winner))
+    }
+
+    // 筛选、排序并分页，返回 (该页的索引条目, 筛选后的总条数)；limit 为 None 时不限条数
+    //
+    // 不按中奖者筛选时全部在数据库中完成；按中奖者筛选时先由数据库筛选和排序其余条件，
+    // 再逐个读取任务文件比较中奖者，历史记录已加密时须先解锁。
+    pub fn query(
+        &self,
+        sort: history_db::SortKey,
+        descending: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<(Vec<serde_json::Value>, usize), AppError> {
+        let index_filter = history_db::IndexFilter {
+            name_terms: &self.name_terms,
+            group_name: self.group_name.as_deref(),
+            since: self.since,
+            until: self.until,
+            favorite: self.favorite,
+        };
+        let Some(winner) = &self.winner else {
+            return Ok(history_db::query(&index_filter, sort, descending, offset, limit)?);
+        };
+
+        crate::encryption::ensure_unlocked()?;
+        let (candidates, _) = history_db::query(&index_filter, sort, descending, 0, None)?;
+        let mut matched = Vec::new();
+        for item in candidates {
+            if Self::matches_winner(&item, winner)? {
+                matched.push(item);
+            }
+        }
+        let total = matched.len();
+        let page = matched
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok((page, total))
+    }
+}
+
+// 解析排序字段 "timestamp" | "name" | "totalCount"
+fn sort_key(sort_by: &str) -> Result<history_db::SortKey, AppError> {
+    match sort_by {
+        "timestamp" => Ok(history_db::SortKey::Timestamp),
+        "name" => Ok(history_db::SortKey::Name),
+        "totalCount" => Ok(history_db::SortKey::TotalCount),
+        other => Err(AppError::InvalidInput(format!("不支持的排序字段: {}", other))),
+    }
+}
+
+// 按参数查询一页历史记录
+//
+// params: { offset?, limit?, filter?, sortBy?: "timestamp" | "name" | "totalCount", sortDir?: "asc" | "desc" }
+// 默认按时间倒序；先筛选、再排序、最后分页，返回 { items, total }，total 为筛选后的总条数。
+#[tauri::command]
+pub async fn query_history_page(params: serde_json::Value) -> Result<serde_json::Value, AppError> {
+    log::info!("查询历史记录: {}", params);
+    crate::app_lock::ensure_unlocked()?;
+
+    let offset = params.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    let limit = params
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|l| (l as usize).min(MAX_PAGE_SIZE))
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+    let sort_by = params.get("sortBy").and_then(|v| v.as_str()).unwrap_or("timestamp");
+    let descending = match params.get("sortDir").and_then(|v| v.as_str()).unwrap_or("desc") {
+        "desc" => true,
+        "asc" => false,
+        other => return Err(AppError::InvalidInput(format!("无效的排序方向: {}", other))),
+    };
+    let sort = sort_key(sort_by)?;
+    let filter = HistoryFilter::parse(params.get("filter"))?;
+
+    let (page, total) = filter.query(sort, descending, offset, Some(limit))?;
+
+    Ok(serde_json::json!({
+        "items": page,
+        "total": total
+    }))
+}
+
+// 按任务名称（自由文本，所有词都须出现）、小组名称（等于）、中奖者姓名和 RFC3339 时间范围搜索历史，只返回匹配的索引条目
+//
+// 未提供的条件不参与筛选，start/end 缺省时视为不限；时间格式无效时返回错误。
+// 按中奖者搜索需要读取任务文件，其余条件只检查索引。
+#[tauri::command]
+pub async fn search_history(
+    query: Option<String>,
+    group: Option<String>,
+    start: Option<String>,
+    end: Option<String>,
+    winner: Option<String>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    log::info!(
+        "搜索历史记录: query={:?}, group={:?}, start={:?}, end={:?}, winner={:?}",
+        query,
+        group,
+        start,
+        end,
+        winner
+    );
+    crate::app_lock::ensure_unlocked()?;
+
+    let non_empty = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let winner = non_empty(winner);
+    let filter = HistoryFilter {
+        name_terms: search_terms(query.as_deref()),
+        group_name: non_empty(group),
+        since: history::parse_since(start.as_deref())?,
+        until: history::parse_since(end.as_deref())?,
+        winner: winner.map(|s| history::normalize_name(&s)),
+        favorite: None,
+    };
+
+    // 与历史列表相同，按索引顺序（最新的在最前面）返回
+    let (items, _) = filter.query(history_db::SortKey::Seq, true, 0, None)?;
+
+    log::info!("搜索到 {} 条历史记录", items.len());
+    Ok(items)
+}
+
+// 分页获取完整的历史任务数据，返回 { items, total }
+//
+// sort 为 "<字段>" 或 "<字段>:<asc|desc>"，字段同 query_history_page 的 sortBy，默认 "timestamp:desc"。
+// 只读取该页的任务文件；limit 为 0 时使用默认页大小，超过上限时按上限返回。
+#[tauri::command]
+pub async fn get_history_page(offset: usize, limit: usize, sort: Option<String>) -> Result<serde_json::Value, AppError> {
+    log::info!("分页获取历史记录: offset={}, limit={}, sort={:?}", offset, limit, sort);
+    crate::app_lock::ensure_unlocked()?;
+    crate::encryption::ensure_unlocked()?;
+
+    let sort = sort.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let (sort_by, direction) = match sort.as_deref() {
+        None => ("timestamp", "desc"),
+        Some(sort) => sort.split_once(':').unwrap_or((sort, "desc")),
+    };
+    let descending = match direction {
+        "desc" => true,
+        "asc" => false,
+        other => return Err(AppError::InvalidInput(format!("无效的排序方向: {}", other))),
+    };
+    let limit = match limit {
+        0 => DEFAULT_PAGE_SIZE,
+        limit => limit.min(MAX_PAGE_SIZE),
+    };
+
+    let (items, total) = history_db::query(
+        &history_db::IndexFilter::default(),
+        sort_key(sort_by)?,
+        descending,
+        offset,
+        Some(limit),
+    )?;
+
+    let page: Vec<serde_json::Value> = items
+        .iter()
+        .map(|index_item| {
+            let mut task_data =
+                history::load_task_data(index_item).unwrap_or_else(|| history::fallback_task_data(index_item));
+            crate::protection::redact(&mut task_data);
+            task_data
+        })
+        .collect();
+
+    log::info!("返回 {} 条历史记录，共 {} 条", page.len(), total);
+    Ok(serde_json::json!({ "items": page, "total": total }))
+}
@@ -0,0 +1,337 @@
+// PDF 报告
+//
+// 生成某一时间段的汇总报告：标题页、任务汇总表以及每月抽取次数柱状图；
+// 也可以为单次抽取生成用于打印张贴的结果公示。
+// PDF 中嵌入系统自带的中文字体（仅 TTF/OTF，字体集合 TTC 无法直接嵌入），只保留用到的字形。
+
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+use printpdf::{Color, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point, Rect, Rgb};
+
+use crate::error::AppError;
+use crate::{export, history};
+
+// 依次尝试嵌入的系统中文字体
+const PDF_FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttf",
+    "C:\\Windows\\Fonts\\simkai.ttf",
+    "C:\\Windows\\Fonts\\Deng.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansSC-Regular.otf",
+];
+
+// A4 纸张尺寸（毫米）
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+
+// 汇总表每行高度与各列横坐标
+const ROW_HEIGHT: f32 = 8.0;
+const TABLE_COLUMNS: [f32; 4] = [20.0, 62.0, 132.0, 178.0];
+
+// 报告中的一个任务
+struct ReportTask {
+    timestamp: chrono::DateTime<chrono::Local>,
+    name: String,
+    group_name: String,
+    winners: usize,
+}
+
+// 截断过长的文字，避免表格列重叠
+fn clip_text(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max_chars - 1).collect::<String>())
+    }
+}
+
+fn gray(level: f32) -> Color {
+    Color::Rgb(Rgb::new(level, level, level, None))
+}
+
+fn draw_line(layer: &PdfLayerReference, from: (f32, f32), to: (f32, f32)) {
+    layer.add_line(Line {
+        points: vec![
+            (Point::new(Mm(from.0), Mm(from.1)), false),
+            (Point::new(Mm(to.0), Mm(to.1)), false),
+        ],
+        is_closed: false,
+    });
+}
+
+// 加载第一个可用的中文字体
+fn load_pdf_font(doc: &printpdf::PdfDocumentReference) -> Result<IndirectFontRef, String> {
+    for path in PDF_FONT_CANDIDATES {
+        let Ok(data) = std::fs::read(path) else {
+            continue;
+        };
+        match doc.add_external_font(data.as_slice()) {
+            Ok(font) => {
+                log::info!("报告使用字体: {}", path);
+                return Ok(font);
+            }
+            Err(e) => log::warn!("加载字体失败 {}: {}", path, e),
+        }
+    }
+    Err("未找到可嵌入的中文字体".to_string())
+}
+
+// 汇总表，一页放不下时自动分页
+fn draw_task_table(doc: &printpdf::PdfDocumentReference, font: &IndirectFontRef, tasks: &[ReportTask]) {
+    let header = ["时间", "任务名称", "小组", "中奖人数"];
+    let rows_per_page = ((PAGE_HEIGHT - 2.0 * MARGIN - 20.0) / ROW_HEIGHT) as usize;
+
+    for (page_index, chunk) in tasks.chunks(rows_per_page.max(1)).enumerate() {
+        let (page, layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "任务汇总");
+        let layer = doc.get_page(page).get_layer(layer);
+
+        let mut y = PAGE_HEIGHT - MARGIN;
+        if page_index == 0 {
+            layer.use_text("任务汇总", 16.0, Mm(MARGIN), Mm(y), font);
+        }
+        y -= 12.0;
+
+        for (column, title) in TABLE_COLUMNS.iter().zip(header) {
+            layer.use_text(title, 10.0, Mm(*column), Mm(y), font);
+        }
+        layer.set_outline_color(gray(0.5));
+        draw_line(&layer, (MARGIN, y - 2.5), (PAGE_WIDTH - MARGIN, y - 2.5));
+
+        for task in chunk {
+            y -= ROW_HEIGHT;
+            let cells = [
+                task.timestamp.format("%Y-%m-%d %H:%M").to_string(),
+                clip_text(&task.name, 18),
+                clip_text(&task.group_name, 12),
+                task.winners.to_string(),
+            ];
+            for (column, cell) in TABLE_COLUMNS.iter().zip(cells) {
+                layer.use_text(cell, 9.0, Mm(*column), Mm(y), font);
+            }
+        }
+    }
+}
+
+// 每月抽取次数柱状图，月份从第一个任务所在月份连续排到最后一个任务所在月份
+fn draw_monthly_chart(doc: &printpdf::PdfDocumentReference, font: &IndirectFontRef, tasks: &[ReportTask]) {
+    let (page, layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "每月抽取次数");
+    let layer = doc.get_page(page).get_layer(layer);
+    layer.use_text("每月抽取次数", 16.0, Mm(MARGIN), Mm(PAGE_HEIGHT - MARGIN), font);
+
+    let mut counts: BTreeMap<(i32, u32), usize> = BTreeMap::new();
+    for task in tasks {
+        *counts.entry((task.timestamp.year(), task.timestamp.month())).or_insert(0) += 1;
+    }
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        layer.use_text("该时间段内没有抽取记录", 12.0, Mm(MARGIN), Mm(PAGE_HEIGHT - 40.0), font);
+        return;
+    };
+
+    let mut months = Vec::new();
+    let (mut year, mut month) = first;
+    while (year, month) <= last {
+        months.push(((year, month), counts.get(&(year, month)).copied().unwrap_or(0)));
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    }
+
+    let origin = (MARGIN + 5.0, 60.0);
+    let chart_width = PAGE_WIDTH - 2.0 * MARGIN - 5.0;
+    let chart_height = 170.0;
+    let max_count = months.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1);
+
+    layer.set_outline_color(gray(0.3));
+    draw_line(&layer, origin, (origin.0 + chart_width, origin.1));
+    draw_line(&layer, origin, (origin.0, origin.1 + chart_height));
+
+    let slot = chart_width / months.len() as f32;
+    let bar_width = slot * 0.7;
+    // 月份较多时每隔几个月标注一次
+    let label_every = months.len().div_ceil(24).max(1);
+
+    layer.set_fill_color(Color::Rgb(Rgb::new(0.26, 0.47, 0.85, None)));
+    for (i, ((year, month), count)) in months.iter().enumerate() {
+        let left = origin.0 + slot * i as f32 + (slot - bar_width) / 2.0;
+        let height = chart_height * *count as f32 / max_count as f32;
+        if *count > 0 {
+            layer.add_rect(Rect::new(Mm(left), Mm(origin.1), Mm(left + bar_width), Mm(origin.1 + height)));
+            layer.use_text(count.to_string(), 7.0, Mm(left), Mm(origin.1 + height + 2.0), font);
+        }
+        if i % label_every == 0 {
+            layer.use_text(format!("{}-{:02}", year, month), 6.0, Mm(left), Mm(origin.1 - 6.0), font);
+        }
+    }
+}
+
+// 导出某一时间段的 PDF 汇总报告，返回报告包含的任务数
+//
+// start、end 为 RFC3339 时间，包含两端；报告中的时间按系统本地时区显示。
+#[tauri::command]
+pub async fn export_report_pdf(start: String, end: String, dest_path: String) -> Result<usize, AppError> {
+    log::info!("导出 PDF 报告: {} ~ {} -> {}", start, end, dest_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let start_time = history::parse_since(Some(&start))?
+        .ok_or_else(|| AppError::InvalidInput("开始时间不能为空".to_string()))?;
+    let end_time = history::parse_since(Some(&end))?
+        .ok_or_else(|| AppError::InvalidInput("结束时间不能为空".to_string()))?;
+    if start_time > end_time {
+        return Err(AppError::InvalidInput("开始时间不能晚于结束时间".to_string()));
+    }
+
+    let mut tasks = Vec::new();
+    for index_item in history::load_history_index()? {
+        let Some(ts) = history::parse_timestamp(&index_item) else {
+            continue;
+        };
+        if ts < start_time || ts > end_time {
+            continue;
+        }
+        let winners = history::load_task_data(&index_item)
+            .map(|task_data| history::task_winners(&task_data).len())
+            .unwrap_or(0);
+        tasks.push(ReportTask {
+            timestamp: ts.with_timezone(&chrono::Local),
+            name: index_item.get("name").and_then(|v| v.as_str()).unwrap_or("未命名任务").to_string(),
+            group_name: index_item.get("groupName").and_then(|v| v.as_str()).unwrap_or("未知小组").to_string(),
+            winners,
+        });
+    }
+    tasks.sort_by_key(|t| t.timestamp);
+
+    let (doc, page, layer) = PdfDocument::new("抽奖记录报告", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "标题页");
+    let font = load_pdf_font(&doc)?;
+
+    // 标题页
+    let title_layer = doc.get_page(page).get_layer(layer);
+    let local_start = start_time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    let local_end = end_time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    let total_winners: usize = tasks.iter().map(|t| t.winners).sum();
+    title_layer.use_text("抽奖记录报告", 28.0, Mm(MARGIN), Mm(240.0), &font);
+    title_layer.use_text(format!("统计时间：{} ~ {}", local_start, local_end), 12.0, Mm(MARGIN), Mm(220.0), &font);
+    title_layer.use_text(format!("抽取任务：{} 次", tasks.len()), 12.0, Mm(MARGIN), Mm(210.0), &font);
+    title_layer.use_text(format!("中奖人次：{}", total_winners), 12.0, Mm(MARGIN), Mm(200.0), &font);
+    title_layer.use_text(
+        format!("生成时间：{}", chrono::Local::now().format("%Y-%m-%d %H:%M")),
+        10.0,
+        Mm(MARGIN),
+        Mm(MARGIN),
+        &font,
+    );
+
+    draw_task_table(&doc, &font, &tasks);
+    draw_monthly_chart(&doc, &font, &tasks);
+
+    let file = std::fs::File::create(&dest_path).map_err(|e| {
+        let error = format!("创建报告文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    doc.save(&mut std::io::BufWriter::new(file)).map_err(|e| {
+        let error = format!("写入报告失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    export::record_export(&dest_path, "report-pdf", Some(tasks.len()), None);
+    log::info!("PDF 报告已导出: {}，共 {} 个任务", dest_path, tasks.len());
+    Ok(tasks.len())
+}
+
+// 结果公示中中奖者列表的行高与两列的横坐标
+const WINNER_ROW_HEIGHT: f32 = 9.0;
+const WINNER_COLUMNS: [f32; 2] = [MARGIN, 110.0];
+
+// 签名栏占用的高度
+const SIGNATURE_HEIGHT: f32 = 40.0;
+
+// 为一次抽取生成可打印张贴的 PDF 结果公示：标题、时间、小组、中奖名单与签名栏
+//
+// 中奖者按抽取顺序分两列编号排列，一页放不下时自动分页，签名栏位于最后一页底部。
+#[tauri::command]
+pub async fn generate_draw_report_pdf(task_id: String, output_path: String) -> Result<(), AppError> {
+    log::info!("生成抽取结果公示: {} -> {}", task_id, output_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    crate::encryption::ensure_unlocked()?;
+    let task_data = history::find_task(&task_id)?.ok_or_else(|| AppError::NotFound(format!("任务不存在: {}", task_id)))?;
+    let task_name = task_data.get("name").and_then(|v| v.as_str()).unwrap_or("未命名任务");
+    let group_name = task_data.get("group_name").and_then(|v| v.as_str()).unwrap_or("未知小组");
+    let time = history::parse_timestamp(&task_data)
+        .map(|ts| ts.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "未知时间".to_string());
+    let winners = history::task_winners(&task_data);
+
+    let (doc, page, layer) = PdfDocument::new("抽取结果公示", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "结果公示");
+    let font = load_pdf_font(&doc)?;
+    let mut layer = doc.get_page(page).get_layer(layer);
+
+    layer.use_text("抽取结果公示", 24.0, Mm(MARGIN), Mm(PAGE_HEIGHT - MARGIN - 10.0), &font);
+    layer.use_text(clip_text(task_name, 30), 16.0, Mm(MARGIN), Mm(PAGE_HEIGHT - MARGIN - 24.0), &font);
+    layer.use_text(format!("抽取时间：{}", time), 12.0, Mm(MARGIN), Mm(PAGE_HEIGHT - MARGIN - 36.0), &font);
+    layer.use_text(format!("小组：{}", group_name), 12.0, Mm(MARGIN), Mm(PAGE_HEIGHT - MARGIN - 44.0), &font);
+    layer.use_text(format!("中奖人数：{}", winners.len()), 12.0, Mm(MARGIN), Mm(PAGE_HEIGHT - MARGIN - 52.0), &font);
+    layer.set_outline_color(gray(0.5));
+    let mut y = PAGE_HEIGHT - MARGIN - 58.0;
+    draw_line(&layer, (MARGIN, y), (PAGE_WIDTH - MARGIN, y));
+    y -= 12.0;
+
+    if winners.is_empty() {
+        layer.use_text("本次抽取没有中奖者", 12.0, Mm(MARGIN), Mm(y), &font);
+        y -= WINNER_ROW_HEIGHT;
+    }
+    for (i, winner) in winners.iter().enumerate() {
+        let column = i % WINNER_COLUMNS.len();
+        if column == 0 && y < MARGIN {
+            let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "结果公示");
+            layer = doc.get_page(page).get_layer(new_layer);
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        let entry = format!("{}. {}", i + 1, clip_text(winner, 20));
+        layer.use_text(entry, 12.0, Mm(WINNER_COLUMNS[column]), Mm(y), &font);
+        if column == WINNER_COLUMNS.len() - 1 {
+            y -= WINNER_ROW_HEIGHT;
+        }
+    }
+    if !winners.len().is_multiple_of(WINNER_COLUMNS.len()) {
+        y -= WINNER_ROW_HEIGHT;
+    }
+
+    // 签名栏放不下时另起一页
+    if y < MARGIN + SIGNATURE_HEIGHT {
+        let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "签名");
+        layer = doc.get_page(page).get_layer(new_layer);
+    }
+    layer.set_outline_color(gray(0.3));
+    let signature_y = MARGIN + 20.0;
+    for (label, x) in [("抽取人：", MARGIN), ("监督人：", 80.0), ("日期：", 140.0)] {
+        layer.use_text(label, 11.0, Mm(x), Mm(signature_y), &font);
+        draw_line(&layer, (x + 14.0, signature_y - 1.0), (x + 50.0, signature_y - 1.0));
+    }
+    layer.use_text(
+        format!("生成时间：{}", chrono::Local::now().format("%Y-%m-%d %H:%M")),
+        9.0,
+        Mm(MARGIN),
+        Mm(MARGIN),
+        &font,
+    );
+
+    let file = std::fs::File::create(&output_path).map_err(|e| {
+        let error = format!("创建报告文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    doc.save(&mut std::io::BufWriter::new(file)).map_err(|e| {
+        let error = format!("写入报告失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    export::record_export(&output_path, "draw-report-pdf", Some(winners.len()), Some(&task_id));
+    log::info!("抽取结果公示已生成: {}", output_path);
+    Ok(())
+}
@@ -0,0 +1,339 @@
+// 名单（小组）存储
+//
+// 每个名单保存为 coredata/groups/<id>.json，结构如下：
+// { "id", "name", "members": [{ "name", "weight"? }], "createdTime", "updatedTime" }
+// 名单的增删改都通过本模块的命令进行，成员姓名与权重在保存前校验。
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::{draw, history, rules, settings};
+
+// 名单文件的读-改-写锁
+pub static ROSTER_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RosterMember {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Roster {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<RosterMember>,
+    #[serde(default)]
+    pub created_time: String,
+    #[serde(default)]
+    pub updated_time: String,
+}
+
+impl Roster {
+    // 成员姓名（保持名单顺序）
+    pub fn member_names(&self) -> Vec<String> {
+        self.members.iter().map(|m| m.name.clone()).collect()
+    }
+}
+
+// 名单目录 coredata/groups
+pub fn groups_dir() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join("groups"))
+}
+
+// new_roster_id 不会生成、文件名中也不允许出现的字符；其中 ':' 在 Windows 上会被当作盘符（如 C:evil）
+const INVALID_ID_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+// 名单文件路径，拒绝包含路径分隔符、盘符等字符的 id，并确认文件直接位于 groups 目录中
+pub fn roster_path(roster_id: &str) -> Result<PathBuf, AppError> {
+    let id = roster_id.trim();
+    if id.is_empty() || id.contains(INVALID_ID_CHARS) || id.contains("..") || id.chars().any(char::is_control) {
+        return Err(AppError::InvalidInput(format!("无效的名单ID: {}", roster_id)));
+    }
+    let dir = groups_dir()?;
+    let path = dir.join(format!("{}.json", id));
+    if path.parent() != Some(dir.as_path()) {
+        return Err(AppError::InvalidInput(format!("无效的名单ID: {}", roster_id)));
+    }
+    Ok(path)
+}
+
+// 读取名单，不存在时返回错误
+pub fn load_roster(roster_id: &str) -> Result<Roster, AppError> {
+    let path = roster_path(roster_id)?;
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("名单不存在: {}", roster_id)));
+    }
+
+    let content = std::fs::read_to_string(&path).map_err(|e| AppError::io("读取名单失败", e))?;
+    serde_json::from_str(&content).map_err(|e| AppError::Parse(format!("解析名单失败: {}", e)))
+}
+
+// 原子写入名单文件
+pub fn save_roster(roster: &Roster) -> Result<(), AppError> {
+    let path = roster_path(&roster.id)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| AppError::io("创建名单目录失败", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(roster).map_err(|e| format!("序列化名单失败: {}", e))?;
+    crate::storage::write_atomic(&path, content.as_bytes()).map_err(|e| {
+        let error = AppError::io("保存名单失败", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 读取 coredata/groups 下的所有名单，无法解析的文件记录日志后跳过
+pub fn load_all_rosters() -> Result<Vec<Roster>, AppError> {
+    let dir = groups_dir()?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let entries = std::fs::read_dir(&dir).map_err(|e| AppError::io("读取名单目录失败", e))?;
+    let mut rosters = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Roster>(&content).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(roster) => rosters.push(roster),
+            Err(e) => log::warn!("跳过无法解析的名单文件 {:?}: {}", path, e),
+        }
+    }
+    rosters.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(rosters)
+}
+
+// 按名称查找名单
+pub fn find_roster_by_name(name: &str) -> Result<Option<Roster>, AppError> {
+    let name = name.trim();
+    Ok(load_all_rosters()?.into_iter().find(|roster| roster.name == name))
+}
+
+// 为新名单生成 id：名称中的非法文件名字符替换为 _，与已有名单冲突时追加序号
+pub fn new_roster_id(name: &str) -> Result<String, AppError> {
+    let base: String = name
+        .trim()
+        .replace(INVALID_ID_CHARS, "_")
+        .replace(['.', ' '], "_")
+        .replace(char::is_control, "_")
+        .chars()
+        .take(50)
+        .collect();
+    let base = if base.is_empty() { "group".to_string() } else { base };
+
+    let mut id = base.clone();
+    let mut suffix = 2;
+    while roster_path(&id)?.exists() {
+        id = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+    Ok(id)
+}
+
+// 列出所有已保存的名单概要
+#[tauri::command]
+pub async fn list_groups() -> Result<Vec<serde_json::Value>, AppError> {
+    log::info!("列出名单");
+    crate::app_lock::ensure_unlocked()?;
+
+    Ok(load_all_rosters()?
+        .into_iter()
+        .map(|roster| {
+            serde_json::json!({
+                "id": roster.id,
+                "name": roster.name,
+                "memberCount": roster.members.len(),
+                "updatedTime": roster.updated_time
+            })
+        })
+        .collect())
+}
+
+// 按名称查找名单，找不到时再按 id 查找
+pub fn resolve_roster(name: &str) -> Result<Roster, AppError> {
+    match find_roster_by_name(name)? {
+        Some(roster) => Ok(roster),
+        None => load_roster(name),
+    }
+}
+
+// 按名称获取名单（找不到时再按 id 查找）
+#[tauri::command]
+pub async fn get_group(name: String) -> Result<Roster, AppError> {
+    log::info!("获取名单: {}", name);
+    crate::app_lock::ensure_unlocked()?;
+    resolve_roster(&name)
+}
+
+// 校验并整理成员：姓名去除首尾空白并按设置截断，权重必须是大于 0 的数字；
+// 与 existing 中或列表中靠前的成员重名（规范化后相同）的成员会被去掉
+fn validate_members(
+    app_handle: &tauri::AppHandle,
+    existing: &[RosterMember],
+    members: Vec<RosterMember>,
+) -> Result<Vec<RosterMember>, AppError> {
+    let max_length = rules::max_name_length(&settings::current_settings(app_handle)?);
+    let mut seen: HashSet<String> = existing.iter().map(|m| history::normalize_name(&m.name)).collect();
+    let mut validated = Vec::with_capacity(members.len());
+    for member in members {
+        let name = member.name.trim();
+        if name.is_empty() {
+            return Err(AppError::InvalidInput("成员姓名不能为空".to_string()));
+        }
+        if let Some(weight) = member.weight {
+            if !weight.is_finite() || weight <= 0.0 {
+                return Err(AppError::InvalidInput(format!("成员 {} 的权重无效: {}", name, weight)));
+            }
+        }
+        let name = rules::truncate_name(name, max_length);
+        if !seen.insert(history::normalize_name(&name)) {
+            log::warn!("成员 {} 已存在，已忽略", name);
+            continue;
+        }
+        validated.push(RosterMember { name, weight: member.weight });
+    }
+    Ok(validated)
+}
+
+// 校验名单名称，并确认没有其他名单（except_id 以外）使用该名称
+fn validate_group_name(name: &str, except_id: Option<&str>) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("名单名称不能为空".to_string()));
+    }
+    if let Some(other) = find_roster_by_name(name)? {
+        if Some(other.id.as_str()) != except_id {
+            return Err(AppError::Conflict(format!("名单已存在: {}", name)));
+        }
+    }
+    Ok(name.to_string())
+}
+
+// 新建名单，members 可以为空
+#[tauri::command]
+pub async fn create_group(
+    app_handle: tauri::AppHandle,
+    name: String,
+    members: Option<Vec<RosterMember>>,
+) -> Result<Roster, AppError> {
+    log::info!("新建名单: {}", name);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _roster_guard = ROSTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let name = validate_group_name(&name, None)?;
+    let members = validate_members(&app_handle, &[], members.unwrap_or_default())?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let roster = Roster {
+        id: new_roster_id(&name)?,
+        name,
+        members,
+        created_time: now.clone(),
+        updated_time: now,
+    };
+    save_roster(&roster)?;
+    Ok(roster)
+}
+
+// 重命名名单（按名称或 id 查找），id 与文件名保持不变
+#[tauri::command]
+pub async fn rename_group(name: String, new_name: String) -> Result<Roster, AppError> {
+    log::info!("重命名名单: {} -> {}", name, new_name);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _roster_guard = ROSTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut roster = resolve_roster(&name)?;
+    roster.name = validate_group_name(&new_name, Some(&roster.id))?;
+    roster.updated_time = chrono::Utc::now().to_rfc3339();
+    save_roster(&roster)?;
+    Ok(roster)
+}
+
+// 删除名单（按名称或 id 查找）及其不重复抽取状态
+#[tauri::command]
+pub async fn delete_group(name: String) -> Result<(), AppError> {
+    log::info!("删除名单: {}", name);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _roster_guard = ROSTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let roster = resolve_roster(&name)?;
+    let path = roster_path(&roster.id)?;
+    std::fs::remove_file(&path).map_err(|e| {
+        let error = format!("删除名单失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    Ok(draw::clear_drawn_state(&roster)?)
+}
+
+// 向名单追加成员，已在名单中的姓名会被忽略
+#[tauri::command]
+pub async fn add_members(
+    app_handle: tauri::AppHandle,
+    name: String,
+    members: Vec<RosterMember>,
+) -> Result<Roster, AppError> {
+    log::info!("添加成员: {} 共 {} 人", name, members.len());
+    crate::app_lock::ensure_unlocked()?;
+
+    let _roster_guard = ROSTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut roster = resolve_roster(&name)?;
+    let added = validate_members(&app_handle, &roster.members, members)?;
+    roster.members.extend(added);
+    roster.updated_time = chrono::Utc::now().to_rfc3339();
+    save_roster(&roster)?;
+    Ok(roster)
+}
+
+// 从名单中移除成员（按规范化后的姓名匹配），不在名单中的姓名会被忽略
+#[tauri::command]
+pub async fn remove_members(name: String, names: Vec<String>) -> Result<Roster, AppError> {
+    log::info!("移除成员: {} {:?}", name, names);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _roster_guard = ROSTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut roster = resolve_roster(&name)?;
+    let removed: HashSet<String> = names.iter().map(|n| history::normalize_name(n)).collect();
+    roster.members.retain(|m| !removed.contains(&history::normalize_name(&m.name)));
+    roster.updated_time = chrono::Utc::now().to_rfc3339();
+    save_roster(&roster)?;
+    Ok(roster)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDataRoot;
+
+    #[test]
+    fn roster_path_stays_in_groups_dir() {
+        let _root = TempDataRoot::new("roster-path");
+        for id in ["C:evil", "../evil", "a/b", "a\\b", "..", "a\tb", " "] {
+            assert!(roster_path(id).is_err(), "{:?} 应被拒绝", id);
+        }
+        let path = roster_path("一班_2").unwrap();
+        assert_eq!(path, groups_dir().unwrap().join("一班_2.json"));
+    }
+
+    #[test]
+    fn new_roster_id_is_always_accepted() {
+        let _root = TempDataRoot::new("roster-id");
+        for name in ["C:evil", "../高三.1班", "a\tb<c>", "   "] {
+            let id = new_roster_id(name).unwrap();
+            assert!(roster_path(&id).is_ok(), "{:?} 生成的 {:?} 应可使用", name, id);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+// 抽奖公平规则
+//
+// 姓名长度上限：设置中的 maxNameLength（默认 200 个字符），超长姓名会被截断并加省略号，
+// 防止误粘贴的超长文本撑大任务文件或破坏界面布局。
+//
+// 中奖次数上限：设置中的 maxWinsPerPerson 表示同一人在 maxWinsPeriodDays 天内
+// 最多中奖几次（0 表示不限制；天数为 0 表示统计全部历史）。
+// 抽奖命令在抽取前调用 apply_win_caps 排除已达上限的人。
+//
+// 禁抽名单：设置中的 denylist 列出的人不会参与抽取；enforceUniqueNames 为 true 时，
+// 规范化后同名的参与者只保留第一个。两者与中奖次数上限一起可通过 export_rules/import_rules 在多台电脑间共享。
+
+use crate::error::AppError;
+use crate::{export, history, settings, storage};
+
+// 默认的姓名长度上限（字符数）
+pub const DEFAULT_MAX_NAME_LENGTH: usize = 200;
+
+// 读取设置中的姓名长度上限
+pub fn max_name_length(settings: &serde_json::Value) -> usize {
+    settings
+        .get("maxNameLength")
+        .and_then(|v| v.as_u64())
+        .filter(|&n| n > 0)
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_MAX_NAME_LENGTH)
+}
+
+// 超过上限时截断姓名（保留 max - 1 个字符并追加省略号）
+pub fn truncate_name(name: &str, max: usize) -> String {
+    let length = name.chars().count();
+    if length <= max {
+        return name.to_string();
+    }
+
+    let truncated: String = name.chars().take(max.saturating_sub(1)).chain(['…']).collect();
+    log::warn!("姓名过长已截断: {} 个字符 -> {} 个字符", length, max);
+    truncated
+}
+
+// 截断 JSON 数组中的超长字符串姓名
+pub fn truncate_name_array(value: Option<&mut serde_json::Value>, max: usize) {
+    if let Some(items) = value.and_then(|v| v.as_array_mut()) {
+        for item in items.iter_mut() {
+            if let Some(name) = item.as_str() {
+                if name.chars().count() > max {
+                    *item = serde_json::Value::String(truncate_name(name, max));
+                }
+            }
+        }
+    }
+}
+
+// 设置中的禁抽名单
+pub fn denylist(settings: &serde_json::Value) -> Vec<String> {
+    settings
+        .get("denylist")
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(|item| item.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_default()
+}
+
+// 排除禁抽名单中的人，并在 enforceUniqueNames 开启时去除重名
+pub fn apply_denylist_and_unique(participants: Vec<String>, settings: &serde_json::Value) -> Vec<String> {
+    let denied: std::collections::HashSet<String> =
+        denylist(settings).iter().map(|name| history::normalize_name(name)).collect();
+    let enforce_unique = settings
+        .get("enforceUniqueNames")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let before = participants.len();
+    let mut seen = std::collections::HashSet::new();
+    let participants: Vec<String> = participants
+        .into_iter()
+        .filter(|name| {
+            let normalized = history::normalize_name(name);
+            !denied.contains(&normalized) && (!enforce_unique || seen.insert(normalized))
+        })
+        .collect();
+
+    if participants.len() != before {
+        log::info!("禁抽名单与重名规则排除了 {} 人", before - participants.len());
+    }
+    participants
+}
+
+// 中奖次数上限配置
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WinCaps {
+    pub max_wins: u64,
+    pub period_days: u64,
+}
+
+impl WinCaps {
+    pub fn from_settings(settings: &serde_json::Value) -> Self {
+        WinCaps {
+            max_wins: settings.get("maxWinsPerPerson").and_then(|v| v.as_u64()).unwrap_or(0),
+            period_days: settings.get("maxWinsPeriodDays").and_then(|v| v.as_u64()).unwrap_or(0),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.max_wins > 0
+    }
+
+    // 统计窗口的起始时间，None 表示统计全部历史；天数大到超出可表示的时间范围时同样统计全部历史
+    pub fn window_start(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        if self.period_days == 0 {
+            return None;
+        }
+        let period = chrono::Duration::try_days(i64::try_from(self.period_days).ok()?)?;
+        chrono::Utc::now().checked_sub_signed(period)
+    }
+}
+
+// 已达到上限的参与者
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CappedParticipant {
+    pub name: String,
+    pub wins: usize,
+}
+
+// 按上限拆分参与者：返回 (可参与者, 已达上限者)
+pub fn apply_win_caps(
+    participants: &[String],
+    caps: WinCaps,
+) -> Result<(Vec<String>, Vec<CappedParticipant>), String> {
+    if !caps.enabled() {
+        return Ok((participants.to_vec(), vec![]));
+    }
+
+    let counts = history::win_counts_since(caps.window_start())?;
+    let mut eligible = Vec::new();
+    let mut capped = Vec::new();
+
+    for name in participants {
+        let wins = counts.get(&history::normalize_name(name)).copied().unwrap_or(0);
+        if wins as u64 >= caps.max_wins {
+            capped.push(CappedParticipant { name: name.clone(), wins });
+        } else {
+            eligible.push(name.clone());
+        }
+    }
+
+    if !capped.is_empty() {
+        log::info!("{} 人已达到中奖次数上限，已排除", capped.len());
+    }
+    Ok((eligible, capped))
+}
+
+// 列出当前已达到中奖次数上限的参与者
+#[tauri::command]
+pub async fn check_win_caps(
+    app_handle: tauri::AppHandle,
+    participants: Vec<String>,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("检查中奖次数上限，参与者 {} 人", participants.len());
+    crate::app_lock::ensure_unlocked()?;
+
+    let caps = WinCaps::from_settings(&settings::current_settings(&app_handle)?);
+    let (eligible, capped) = apply_win_caps(&participants, caps)?;
+
+    Ok(serde_json::json!({
+        "enabled": caps.enabled(),
+        "maxWinsPerPerson": caps.max_wins,
+        "maxWinsPeriodDays": caps.period_days,
+        "capped": capped,
+        "eligible": eligible
+    }))
+}
+
+// 找出超过长度上限的姓名，供导入前提示
+#[tauri::command]
+pub async fn find_overlong_names(
+    app_handle: tauri::AppHandle,
+    participants: Vec<String>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let max = max_name_length(&settings::current_settings(&app_handle)?);
+
+    Ok(participants
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| {
+            let length = name.chars().count();
+            (length > max).then(|| {
+                serde_json::json!({
+                    "index": i,
+                    "preview": name.chars().take(50).collect::<String>(),
+                    "length": length,
+                    "maxLength": max
+                })
+            })
+        })
+        .collect())
+}
+
+// 规则文件中的设置项
+const RULE_KEYS: &[&str] = &["denylist", "maxWinsPerPerson", "maxWinsPeriodDays", "enforceUniqueNames"];
+
+// 导出禁抽名单、中奖次数上限与重名规则到一个文件
+#[tauri::command]
+pub async fn export_rules(app_handle: tauri::AppHandle, dest_path: String) -> Result<(), AppError> {
+    log::info!("导出抽奖规则: {}", dest_path);
+    crate::app_lock::ensure_unlocked()?;
+
+    let current = settings::current_settings(&app_handle)?;
+    let mut rules = serde_json::json!({
+        "type": "rules",
+        "version": 1,
+        "exportedAt": chrono::Utc::now().to_rfc3339()
+    });
+    let defaults = settings::default_settings();
+    for key in RULE_KEYS {
+        let value = current.get(*key).or_else(|| defaults.get(*key)).cloned();
+        rules[*key] = value.unwrap_or(serde_json::Value::Null);
+    }
+
+    let content = serde_json::to_string_pretty(&rules).map_err(|e| format!("序列化规则失败: {}", e))?;
+    storage::write_atomic(std::path::Path::new(&dest_path), content.as_bytes()).map_err(|e| {
+        let error = format!("写入规则文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    export::record_export(&dest_path, "rules", None, None);
+    Ok(())
+}
+
+// 导入规则文件
+//
+// merge 为 true 时禁抽名单取并集（保留现有顺序，新名字追加在后），上限与重名规则使用导入的值；
+// 为 false 时全部替换为导入的值。文件中缺少的项保持不变，内容全部校验通过后才会写入。
+#[tauri::command]
+pub async fn import_rules(
+    app_handle: tauri::AppHandle,
+    src_path: String,
+    merge: bool,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("导入抽奖规则: {} (合并: {})", src_path, merge);
+    crate::app_lock::ensure_unlocked()?;
+
+    let content = std::fs::read_to_string(&src_path).map_err(|e| {
+        let error = format!("读取规则文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    let rules: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("规则文件格式无效: {}", e))?;
+    if rules.get("type").and_then(|v| v.as_str()) != Some("rules") {
+        return Err(AppError::InvalidInput("不是有效的规则文件".to_string()));
+    }
+
+    let mut incoming = serde_json::Map::new();
+    for key in RULE_KEYS {
+        if let Some(value) = rules.get(*key).filter(|v| !v.is_null()) {
+            incoming.insert(key.to_string(), value.clone());
+        }
+    }
+    settings::validate_settings(&serde_json::Value::Object(incoming.clone())).map_err(AppError::InvalidInput)?;
+
+    let current = settings::current_settings(&app_handle)?;
+    let mut added = 0;
+    if let Some(serde_json::Value::Array(incoming_names)) = incoming.get("denylist").cloned() {
+        let mut names = if merge { denylist(&current) } else { vec![] };
+        let mut known: std::collections::HashSet<String> =
+            names.iter().map(|name| history::normalize_name(name)).collect();
+        for name in incoming_names.iter().filter_map(|v| v.as_str()) {
+            if !name.trim().is_empty() && known.insert(history::normalize_name(name)) {
+                names.push(name.trim().to_string());
+                added += 1;
+            }
+        }
+        incoming.insert("denylist".to_string(), serde_json::json!(names));
+    }
+
+    settings::update_settings(&app_handle, incoming)?;
+
+    let updated = settings::current_settings(&app_handle)?;
+    let caps = WinCaps::from_settings(&updated);
+    log::info!("规则导入完成，禁抽名单新增 {} 人", added);
+    Ok(serde_json::json!({
+        "merge": merge,
+        "denylistCount": denylist(&updated).len(),
+        "denylistAdded": added,
+        "maxWinsPerPerson": caps.max_wins,
+        "maxWinsPeriodDays": caps.period_days,
+        "enforceUniqueNames": updated.get("enforceUniqueNames").and_then(|v| v.as_bool()).unwrap_or(false)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_start_covers_period() {
+        let caps = WinCaps::from_settings(&serde_json::json!({ "maxWinsPerPerson": 1, "maxWinsPeriodDays": 7 }));
+        let start = caps.window_start().unwrap();
+        let days = (chrono::Utc::now() - start).num_days();
+        assert_eq!(days, 7);
+        assert!(WinCaps::from_settings(&serde_json::json!({ "maxWinsPerPerson": 1 })).window_start().is_none());
+    }
+
+    #[test]
+    fn huge_period_counts_all_history() {
+        for period_days in [u64::MAX, i64::MAX as u64, 1 << 40, 100_000_000] {
+            let caps = WinCaps::from_settings(&serde_json::json!({ "maxWinsPerPerson": 1, "maxWinsPeriodDays": period_days }));
+            assert!(caps.window_start().is_none(), "{}", period_days);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+// 定时抽取
+//
+// schedule_draw 按 cron 表达式（分 时 日 月 周，可在最前面加秒，按本地时间计算）添加定时抽取，
+// 保存在 coredata/schedules.json 中。后台任务每隔一段时间检查到期的定时抽取：从名单中不重复抽取
+// （与 draw_without_replacement 相同，适合每天轮流点名值日生），结果保存到历史记录，
+// 随后向前端发送 scheduled-draw 事件并显示系统通知。
+// 程序未运行或后台任务暂停期间错过的抽取，超过 MISSED_RUN_GRACE 后不再补抽。
+
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::{background, draw, history, notification, roster};
+
+// 发给前端的事件名，定时抽取完成后发送
+const SCHEDULED_DRAW_EVENT: &str = "scheduled-draw";
+
+// 后台任务检查到期时间的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// 到期后多久之内仍会补抽，超过后跳过本次
+const MISSED_RUN_GRACE: chrono::Duration = chrono::Duration::minutes(10);
+
+// 串行化对 schedules.json 的读改写
+static SCHEDULES_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub id: String,
+    pub cron_expr: String,
+    // 名单名称或 id
+    pub group_id: String,
+    pub count: usize,
+    #[serde(default)]
+    pub created_time: String,
+    // 最近一次到期的时间（无论抽取是否成功），下一次从该时间之后计算
+    #[serde(default)]
+    pub last_run: Option<String>,
+    // 最近一次抽取失败的原因，成功后清除
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+fn schedules_path() -> Result<std::path::PathBuf, String> {
+    Ok(history::coredata_dir()?.join("schedules.json"))
+}
+
+fn load_schedules() -> Result<Vec<Schedule>, String> {
+    let Some(value) = crate::storage::read_json_or_recover(&schedules_path()?)? else {
+        return Ok(vec![]);
+    };
+    serde_json::from_value(value).map_err(|e| format!("解析定时抽取失败: {}", e))
+}
+
+fn save_schedules(schedules: &[Schedule]) -> Result<(), String> {
+    let path = schedules_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建数据目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(schedules).map_err(|e| format!("序列化定时抽取失败: {}", e))?;
+    crate::storage::write_atomic(&path, content.as_bytes()).map_err(|e| format!("保存定时抽取失败: {}", e))
+}
+
+fn parse_cron(cron_expr: &str) -> Result<croner::Cron, String> {
+    croner::Cron::from_str(cron_expr.trim()).map_err(|e| format!("cron 表达式无效 {}: {}", cron_expr, e))
+}
+
+fn parse_time(value: &str) -> Option<chrono::DateTime<chrono::Local>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Local))
+}
+
+// 定时抽取在 after 之后的下一次到期时间（本地时间）
+fn next_run_after(schedule: &Schedule, after: &chrono::DateTime<chrono::Local>) -> Option<chrono::DateTime<chrono::Local>> {
+    parse_cron(&schedule.cron_expr)
+        .ok()
+        .and_then(|cron| cron.find_next_occurrence(after, false).ok())
+}
+
+// 下一次到期时间：从上次到期（或创建）之后计算
+fn next_run(schedule: &Schedule) -> Option<chrono::DateTime<chrono::Local>> {
+    let after = schedule
+        .last_run
+        .as_deref()
+        .or(Some(schedule.created_time.as_str()))
+        .and_then(parse_time)
+        .unwrap_or_else(chrono::Local::now);
+    next_run_after(schedule, &after)
+}
+
+// 返回给前端的定时抽取，附带 nextRun
+fn schedule_json(schedule: &Schedule) -> serde_json::Value {
+    let mut value = serde_json::to_value(schedule).unwrap_or_else(|_| serde_json::json!({}));
+    value["nextRun"] = serde_json::json!(next_run(schedule).map(|time| time.to_rfc3339()));
+    value
+}
+
+// 执行一次定时抽取：抽取、保存到历史记录、通知前端，返回新任务的 id
+async fn run_schedule(app_handle: &tauri::AppHandle, schedule: &Schedule) -> Result<String, AppError> {
+    let roster = roster::resolve_roster(&schedule.group_id)?;
+    let result = draw::run_draw_without_replacement(app_handle.clone(), roster.id.clone(), schedule.count).await?;
+    let winners: Vec<String> = result
+        .get("winners")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+
+    let task_id = history::new_task_id();
+    let task_data = serde_json::json!({
+        "id": task_id,
+        "name": format!("定时抽取-{}", roster.name),
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "results": winners,
+        "participants": roster.member_names(),
+        "total_count": winners.len(),
+        "group_name": roster.name,
+        "schedule_id": schedule.id,
+        "seed": result.get("seed"),
+        "rng": result.get("rng"),
+        "edit_protected": false,
+        "edit_password": ""
+    });
+    let index_lock = app_handle.state::<history::HistoryIndexLock>();
+    crate::store_history_task(app_handle.clone(), index_lock, task_data).await?;
+
+    log::info!("定时抽取 {} 完成: {} 抽中 {:?}", schedule.id, roster.name, winners);
+    let event = serde_json::json!({
+        "scheduleId": schedule.id,
+        "taskId": task_id,
+        "groupName": roster.name,
+        "winners": winners
+    });
+    if let Err(e) = app_handle.emit(SCHEDULED_DRAW_EVENT, event) {
+        log::error!("发送 {} 事件失败: {}", SCHEDULED_DRAW_EVENT, e);
+    }
+    notification::notify_draw_result(app_handle, "定时抽取", &roster.name, &winners);
+    Ok(task_id)
+}
+
+// 取出到期的定时抽取并记录本次到期时间；错过太久的只记录、不抽取
+fn take_due(now: &chrono::DateTime<chrono::Local>) -> Result<Vec<Schedule>, String> {
+    let _guard = SCHEDULES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut schedules = load_schedules()?;
+    let mut due = Vec::new();
+    let mut changed = false;
+
+    for schedule in schedules.iter_mut() {
+        let Some(next) = next_run(schedule) else {
+            continue;
+        };
+        if next > *now {
+            continue;
+        }
+        // 错过多次时只看最近一次
+        let run_at = parse_cron(&schedule.cron_expr)
+            .ok()
+            .and_then(|cron| cron.find_previous_occurrence(now, true).ok())
+            .filter(|previous| *previous >= next)
+            .unwrap_or(next);
+
+        schedule.last_run = Some(run_at.to_rfc3339());
+        changed = true;
+        if *now - run_at > MISSED_RUN_GRACE {
+            log::warn!("定时抽取 {} 已错过 {}，跳过本次", schedule.id, run_at);
+        } else {
+            due.push(schedule.clone());
+        }
+    }
+
+    if changed {
+        save_schedules(&schedules)?;
+    }
+    Ok(due)
+}
+
+// 记录抽取结果（失败原因），定时抽取在此期间被删除时忽略
+fn record_result(schedule_id: &str, error: Option<String>) -> Result<(), String> {
+    let _guard = SCHEDULES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut schedules = load_schedules()?;
+    if let Some(schedule) = schedules.iter_mut().find(|s| s.id == schedule_id) {
+        schedule.last_error = error;
+        save_schedules(&schedules)?;
+    }
+    Ok(())
+}
+
+// 在 setup 中启动定时抽取的后台任务
+pub fn spawn(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if !background::enabled() {
+                continue;
+            }
+
+            let due = match take_due(&chrono::Local::now()) {
+                Ok(due) => due,
+                Err(e) => {
+                    log::error!("读取定时抽取失败: {}", e);
+                    continue;
+                }
+            };
+            for schedule in due {
+                let error = match run_schedule(&app_handle, &schedule).await {
+                    Ok(_) => None,
+                    Err(e) => {
+                        log::error!("定时抽取 {} 失败: {}", schedule.id, e);
+                        Some(e.to_string())
+                    }
+                };
+                if let Err(e) = record_result(&schedule.id, error) {
+                    log::error!("{}", e);
+                }
+            }
+        }
+    });
+}
+
+// 添加定时抽取，返回保存的定时抽取（含 nextRun）
+//
+// cron_expr 为 5 段（分 时 日 月 周）或 6 段（秒 分 时 日 月 周）的 cron 表达式，按本地时间计算，
+// 例如 "0 8 * * 1-5" 表示工作日早上 8 点；group_id 为名单名称或 id。
+#[tauri::command]
+pub async fn schedule_draw(cron_expr: String, group_id: String, count: usize) -> Result<serde_json::Value, AppError> {
+    log::info!("添加定时抽取: {} 名单 {} 抽取 {} 人", cron_expr, group_id, count);
+    crate::app_lock::ensure_unlocked()?;
+
+    let cron = parse_cron(&cron_expr).map_err(AppError::InvalidInput)?;
+    if count == 0 {
+        return Err(AppError::InvalidInput("抽取人数必须大于 0".to_string()));
+    }
+    let roster = roster::resolve_roster(&group_id)?;
+    draw::check_count(roster.member_names().len(), count)?;
+
+    let now = chrono::Local::now();
+    if cron.find_next_occurrence(&now, false).is_err() {
+        return Err(AppError::InvalidInput(format!("cron 表达式 {} 没有未来的执行时间", cron_expr)));
+    }
+
+    let schedule = Schedule {
+        id: format!("schedule-{}", chrono::Utc::now().timestamp_millis()),
+        cron_expr: cron_expr.trim().to_string(),
+        group_id: roster.id,
+        count,
+        created_time: now.to_rfc3339(),
+        last_run: None,
+        last_error: None,
+    };
+
+    let _guard = SCHEDULES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut schedules = load_schedules()?;
+    schedules.push(schedule.clone());
+    save_schedules(&schedules)?;
+
+    log::info!("定时抽取已添加: {}", schedule.id);
+    Ok(schedule_json(&schedule))
+}
+
+// 全部定时抽取（含 nextRun）
+#[tauri::command]
+pub async fn list_schedules() -> Result<Vec<serde_json::Value>, AppError> {
+    crate::app_lock::ensure_unlocked()?;
+    let _guard = SCHEDULES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(load_schedules()?.iter().map(schedule_json).collect())
+}
+
+// 删除定时抽取
+#[tauri::command]
+pub async fn cancel_schedule(schedule_id: String) -> Result<(), AppError> {
+    log::info!("删除定时抽取: {}", schedule_id);
+    crate::app_lock::ensure_unlocked()?;
+
+    let _guard = SCHEDULES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut schedules = load_schedules()?;
+    let before = schedules.len();
+    schedules.retain(|s| s.id != schedule_id);
+    if schedules.len() == before {
+        return Err(AppError::NotFound(format!("定时抽取不存在: {}", schedule_id)));
+    }
+    Ok(save_schedules(&schedules)?)
+}
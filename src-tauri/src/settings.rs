@@ -0,0 +1,637 @@
+// 应用设置相关逻辑
+//
+// 设置面板中的滑块等控件可能在一秒内多次调用 save_settings，
+// 这里把频繁的保存合并成一次落盘：每次保存只更新内存中的待写入值，
+// 在一段静默期后才真正写入文件，且始终以最后一次的值为准。
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::storage;
+
+// 最后一次保存后等待多久再写入磁盘
+pub const SETTINGS_QUIET_PERIOD: Duration = Duration::from_millis(500);
+
+// 待写入的设置
+#[derive(Default)]
+struct PendingSettings {
+    path: Option<PathBuf>,
+    value: Option<serde_json::Value>,
+    last_update: Option<Instant>,
+}
+
+// 设置写入合并器，通过 app.manage 注册为全局状态
+#[derive(Default)]
+pub struct SettingsWriter {
+    pending: Mutex<PendingSettings>,
+}
+
+impl SettingsWriter {
+    // 记录一次新的保存请求，返回后由调用方在静默期后调用 flush_if_quiet
+    pub fn schedule(&self, path: PathBuf, value: serde_json::Value) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.path = Some(path);
+        pending.value = Some(value);
+        pending.last_update = Some(Instant::now());
+    }
+
+    // 获取尚未落盘的最新设置（加载设置时优先返回它，保证最新值生效）
+    pub fn pending_value(&self) -> Option<serde_json::Value> {
+        let pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.value.clone()
+    }
+
+    // 仅当距最后一次保存已超过静默期时写入；期间若有新的保存，交给新的定时任务处理。返回写入的设置
+    pub fn flush_if_quiet(&self) -> Result<Option<serde_json::Value>, String> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        match pending.last_update {
+            Some(last) if last.elapsed() >= SETTINGS_QUIET_PERIOD => Self::write_pending(&mut pending),
+            _ => Ok(None),
+        }
+    }
+
+    // 立即写入待保存的设置（例如关闭设置面板或退出程序时），返回写入的设置
+    pub fn flush(&self) -> Result<Option<serde_json::Value>, String> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        Self::write_pending(&mut pending)
+    }
+
+    // 写入时持有锁，避免旧值覆盖新值；写入失败时保留待写入值以便下次重试
+    fn write_pending(pending: &mut PendingSettings) -> Result<Option<serde_json::Value>, String> {
+        let (Some(path), Some(value)) = (pending.path.clone(), pending.value.as_ref()) else {
+            return Ok(None);
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        let settings_str = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+        let attempts = storage::retry_attempts(value);
+        storage::with_retry(attempts, || storage::write_atomic(&path, settings_str.as_bytes())).map_err(|e| {
+            let error = format!("写入设置文件失败: {}", e);
+            log::error!("{}", error);
+            error
+        })?;
+
+        pending.last_update = None;
+        log::info!("设置已写入磁盘: {:?}", path);
+        Ok(pending.value.take())
+    }
+}
+
+// 当前的设置格式版本（settingsVersion），新增或调整设置项时递增，并在 MIGRATIONS 中加入对应的迁移步骤
+pub const SETTINGS_VERSION: u64 = 2;
+
+// 后端使用的设置项；前端自己的设置项原样保存在 extra 中
+//
+// 每个字段都有默认值，缺少的设置项在读取时补齐。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub settings_version: u64,
+    pub theme: String,
+    pub auto_save: bool,
+    pub sound_enabled: bool,
+    pub max_wins_per_person: u64,
+    pub max_wins_period_days: u64,
+    pub draw_sound: String,
+    pub win_sound: String,
+    pub max_name_length: u64,
+    pub file_retry_attempts: u64,
+    pub history_retention: u64,
+    pub history_retention_days: u64,
+    pub log_max_bytes: u64,
+    pub log_max_files: u64,
+    pub log_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kiosk_monitor: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone_offset_minutes: Option<i64>,
+    pub denylist: Vec<String>,
+    pub enforce_unique_names: bool,
+    pub encryption_enabled: bool,
+    pub update_manifest_url: String,
+    pub close_to_tray: bool,
+    pub draw_notifications: bool,
+    pub auto_backup_interval_minutes: u64,
+    pub auto_backup_interval_days: u64,
+    pub auto_backup_keep: u64,
+    pub rng_mode: String,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            settings_version: SETTINGS_VERSION,
+            theme: "light".to_string(),
+            auto_save: true,
+            sound_enabled: true,
+            max_wins_per_person: 0,
+            max_wins_period_days: 0,
+            draw_sound: String::new(),
+            win_sound: String::new(),
+            max_name_length: 200,
+            file_retry_attempts: 3,
+            history_retention: crate::history::DEFAULT_HISTORY_RETENTION as u64,
+            history_retention_days: crate::history::DEFAULT_HISTORY_RETENTION_DAYS,
+            log_max_bytes: crate::logging::DEFAULT_LOG_MAX_BYTES,
+            log_max_files: crate::logging::DEFAULT_LOG_MAX_FILES,
+            log_level: crate::logging::DEFAULT_LOG_LEVEL.to_string(),
+            kiosk_monitor: None,
+            timezone_offset_minutes: None,
+            denylist: vec![],
+            enforce_unique_names: false,
+            encryption_enabled: false,
+            update_manifest_url: crate::update::DEFAULT_UPDATE_MANIFEST_URL.to_string(),
+            close_to_tray: false,
+            draw_notifications: true,
+            auto_backup_interval_minutes: 0,
+            auto_backup_interval_days: 0,
+            auto_backup_keep: crate::auto_backup::DEFAULT_AUTO_BACKUP_KEEP,
+            rng_mode: crate::draw::RNG_ALGORITHM.to_string(),
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+impl Settings {
+    // 解析设置，内容无效时返回全部问题
+    pub fn from_value(value: serde_json::Value) -> Result<Settings, Vec<SettingIssue>> {
+        let issues = setting_issues(&value);
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+        serde_json::from_value(value).map_err(|e| {
+            vec![SettingIssue {
+                key: String::new(),
+                message: e.to_string(),
+            }]
+        })
+    }
+
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_else(|_| serde_json::json!({}))
+    }
+}
+
+// 默认设置
+pub fn default_settings() -> serde_json::Value {
+    Settings::default().to_value()
+}
+
+// validate_settings 能识别的设置项，与 Settings 的字段对应
+const KNOWN_SETTING_KEYS: &[&str] = &[
+    "settingsVersion",
+    "theme",
+    "autoSave",
+    "soundEnabled",
+    "maxWinsPerPerson",
+    "maxWinsPeriodDays",
+    "drawSound",
+    "winSound",
+    "maxNameLength",
+    "fileRetryAttempts",
+    "historyRetention",
+    "historyRetentionDays",
+    "logMaxBytes",
+    "logMaxFiles",
+    "logLevel",
+    "kioskMonitor",
+    "timezoneOffsetMinutes",
+    "denylist",
+    "enforceUniqueNames",
+    "encryptionEnabled",
+    "updateManifestUrl",
+    "closeToTray",
+    "drawNotifications",
+    "autoBackupIntervalMinutes",
+    "autoBackupIntervalDays",
+    "autoBackupKeep",
+    "rngMode",
+];
+
+// 设置校验发现的一个问题，key 为空表示整体问题
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SettingIssue {
+    pub key: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SettingIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.key.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.key, self.message)
+        }
+    }
+}
+
+// 迁移步骤：(目标版本, 迁移函数)，按版本从低到高排列；settingsVersion 低于目标版本的设置依次执行
+type Migration = fn(&mut serde_json::Map<String, serde_json::Value>);
+const MIGRATIONS: &[(u64, Migration)] = &[(2, migrate_to_v2)];
+
+// 版本 2：历史索引改为数据库保存后不再需要默认只保留 100 条，仍是旧默认值的改为不限制
+fn migrate_to_v2(obj: &mut serde_json::Map<String, serde_json::Value>) {
+    if obj.get("historyRetention").and_then(|v| v.as_u64()) == Some(100) {
+        obj.insert("historyRetention".to_string(), serde_json::json!(crate::history::DEFAULT_HISTORY_RETENTION));
+    }
+}
+
+// 设置文件的解码结果
+pub struct DecodedSettings {
+    pub text: String,
+    // 检测到的编码名称
+    pub encoding: &'static str,
+    pub had_bom: bool,
+    // 原文件不是无 BOM 的 UTF-8，需要转码重写
+    pub transcoded: bool,
+}
+
+// 解码设置文件内容：优先按 BOM 判断（UTF-8/UTF-16），其次尝试 UTF-8，最后用 chardetng 猜测编码
+//
+// 在不同系统上手动编辑后，settings.json 可能被保存为 UTF-16 或带 BOM 的 UTF-8。
+pub fn decode_settings_bytes(bytes: &[u8]) -> DecodedSettings {
+    if let Some((encoding, bom_length)) = encoding_rs::Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return DecodedSettings {
+            text: text.into_owned(),
+            encoding: encoding.name(),
+            had_bom: true,
+            transcoded: true,
+        };
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return DecodedSettings {
+            text: text.to_string(),
+            encoding: encoding_rs::UTF_8.name(),
+            had_bom: false,
+            transcoded: false,
+        };
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    DecodedSettings {
+        text: text.into_owned(),
+        encoding: encoding.name(),
+        had_bom: false,
+        transcoded: true,
+    }
+}
+
+// 读取并解码设置文件，再迁移到当前版本；编码不是无 BOM 的 UTF-8 或发生了迁移时重写文件
+//
+// 内容完全无法解析时把文件改名为 settings.json.corrupt 备份，并返回默认设置。
+fn read_settings_file(path: &Path) -> Result<serde_json::Value, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let decoded = decode_settings_bytes(&bytes);
+    let value: serde_json::Value = match serde_json::from_str(&decoded.text) {
+        Ok(value) => value,
+        Err(e) => {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".corrupt");
+            log::warn!("设置文件已损坏（{}），备份到 {:?} 后使用默认设置", e, backup);
+            std::fs::rename(path, &backup).map_err(|e| format!("备份损坏的设置文件失败: {}", e))?;
+            return Ok(default_settings());
+        }
+    };
+
+    let (value, migrated) = migrate_settings(value);
+    if decoded.transcoded || migrated {
+        let settings_str = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+        match storage::write_atomic(path, settings_str.as_bytes()) {
+            Ok(()) if decoded.transcoded => {
+                log::info!("设置文件已从 {} 转换为 UTF-8: {:?}", decoded.encoding, path)
+            }
+            Ok(()) => log::info!("设置文件已迁移到版本 {}: {:?}", SETTINGS_VERSION, path),
+            Err(e) => log::error!("重写设置文件失败: {}", e),
+        }
+    }
+
+    Ok(value)
+}
+
+// 把设置迁移到当前版本：依次执行 MIGRATIONS 中的迁移步骤，再把类型错误的值恢复为默认值（没有默认值的直接移除）、
+// 补齐缺少的设置项并更新 settingsVersion。返回迁移后的设置以及是否有改动
+pub fn migrate_settings(settings: serde_json::Value) -> (serde_json::Value, bool) {
+    let serde_json::Value::Object(mut obj) = settings else {
+        log::warn!("设置不是 JSON 对象，恢复为默认设置");
+        return (default_settings(), true);
+    };
+    let original = obj.clone();
+    let from_version = obj.get("settingsVersion").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    for (version, migrate) in MIGRATIONS {
+        if from_version < *version {
+            migrate(&mut obj);
+            log::info!("设置已从版本 {} 迁移到版本 {}", from_version, version);
+        }
+    }
+
+    let defaults = default_settings().as_object().cloned().unwrap_or_default();
+    for issue in setting_issues(&serde_json::Value::Object(obj.clone())) {
+        log::warn!("设置项 {} 无效，恢复为默认值: {}", issue.key, issue.message);
+        match defaults.get(&issue.key) {
+            Some(default) => obj.insert(issue.key, default.clone()),
+            None => obj.remove(&issue.key),
+        };
+    }
+    obj.insert("settingsVersion".to_string(), serde_json::json!(SETTINGS_VERSION));
+
+    // 经过 Settings 规范化，补齐缺少的设置项
+    let settings = match serde_json::from_value::<Settings>(serde_json::Value::Object(obj)) {
+        Ok(settings) => settings.to_value(),
+        Err(e) => {
+            log::warn!("设置无法解析（{}），恢复为默认设置", e);
+            default_settings()
+        }
+    };
+    let changed = settings.as_object() != Some(&original);
+    (settings, changed)
+}
+
+// 单个设置项的问题：类型错误或取值不允许；未知的设置项不做检查
+fn setting_issue(key: &str, value: &serde_json::Value) -> Option<String> {
+    if !KNOWN_SETTING_KEYS.contains(&key) {
+        return None;
+    }
+
+    // 只含这一项的对象按 Settings 解析，其余字段取默认值，得到的错误即为这一项的问题
+    let mut single = serde_json::Map::new();
+    single.insert(key.to_string(), value.clone());
+    let parsed = match serde_json::from_value::<Settings>(serde_json::Value::Object(single)) {
+        Ok(parsed) => parsed,
+        Err(e) => return Some(format!("类型无效（{}），当前值: {}", e, value)),
+    };
+
+    match key {
+        "updateManifestUrl"
+            if !(parsed.update_manifest_url.starts_with("https://")
+                || parsed.update_manifest_url.starts_with("http://")) =>
+        {
+            Some(format!("必须是 http:// 或 https:// 开头的地址，当前值: {}", value))
+        }
+        "rngMode" if parsed.rng_mode != crate::draw::RNG_ALGORITHM && parsed.rng_mode != crate::draw::RNG_OS => Some(
+            format!("必须是 {} 或 {}，当前值: {}", crate::draw::RNG_ALGORITHM, crate::draw::RNG_OS, value),
+        ),
+        "logLevel" if crate::logging::parse_level(&parsed.log_level).is_none() => Some(format!(
+            "必须是 {} 之一，当前值: {}",
+            crate::logging::LOG_LEVELS.join("、"),
+            value
+        )),
+        _ => None,
+    }
+}
+
+// 列出设置中的全部问题：必须是对象，已知设置项的类型与取值必须正确
+pub fn setting_issues(settings: &serde_json::Value) -> Vec<SettingIssue> {
+    let Some(obj) = settings.as_object() else {
+        return vec![SettingIssue {
+            key: String::new(),
+            message: "设置必须是 JSON 对象".to_string(),
+        }];
+    };
+
+    obj.iter()
+        .filter_map(|(key, value)| {
+            setting_issue(key, value).map(|message| SettingIssue {
+                key: key.clone(),
+                message,
+            })
+        })
+        .collect()
+}
+
+// 把设置问题合并成一条错误信息
+pub fn describe_issues(issues: &[SettingIssue]) -> String {
+    let details: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+    format!("设置无效（{} 项）: {}", issues.len(), details.join("；"))
+}
+
+// 校验设置内容，有问题时返回列出全部问题的错误
+pub fn validate_settings(settings: &serde_json::Value) -> Result<(), String> {
+    let issues = setting_issues(settings);
+    if issues.is_empty() {
+        return Ok(());
+    }
+    Err(describe_issues(&issues))
+}
+
+// 当前生效的设置：优先返回尚未落盘的值，其次是磁盘上的文件，最后是默认设置（均已迁移到当前版本）
+pub fn current_settings(app_handle: &tauri::AppHandle) -> Result<serde_json::Value, String> {
+    if let Some(pending) = app_handle.state::<SettingsWriter>().pending_value() {
+        return Ok(migrate_settings(pending).0);
+    }
+
+    let settings_path = settings_path(app_handle)?;
+    if !settings_path.exists() {
+        return Ok(default_settings());
+    }
+
+    read_settings_file(&settings_path)
+}
+
+// 修改单个设置项并立即写入
+pub fn update_setting(
+    app_handle: &tauri::AppHandle,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    let mut values = serde_json::Map::new();
+    values.insert(key.to_string(), value);
+    update_settings(app_handle, values)
+}
+
+// 一次修改多个设置项并立即写入
+pub fn update_settings(
+    app_handle: &tauri::AppHandle,
+    values: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    let mut current = current_settings(app_handle)?;
+    let obj = current.as_object_mut().ok_or("设置必须是 JSON 对象")?;
+    obj.extend(values);
+
+    let writer = app_handle.state::<SettingsWriter>();
+    writer.schedule(settings_path(app_handle)?, current);
+    if let Some(written) = writer.flush()? {
+        emit_settings_changed(app_handle, &written);
+    }
+    Ok(())
+}
+
+// 设置写入磁盘后广播给所有窗口的事件，载荷为新的完整设置
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+// 通知所有窗口设置已变更（例如主题），让其他窗口无需重新加载即可生效
+pub fn emit_settings_changed(app_handle: &tauri::AppHandle, settings: &serde_json::Value) {
+    if let Err(e) = app_handle.emit(SETTINGS_CHANGED_EVENT, settings) {
+        log::error!("发送设置变更事件失败: {}", e);
+    }
+}
+
+// 设置文件路径
+pub fn settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app_handle.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(config_dir.join("settings.json"))
+}
+
+// 应用启动早期（还没有 AppHandle，例如初始化日志时）的设置文件路径，规则与 app_config_dir 一致
+fn early_settings_path(identifier: &str) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let config_dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let config_dir = std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"));
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    config_dir.map(|dir| dir.join(identifier).join("settings.json"))
+}
+
+// 启动早期读取设置，文件不存在或无法读取时返回默认设置
+pub fn early_settings(identifier: &str) -> serde_json::Value {
+    early_settings_path(identifier)
+        .filter(|path| path.exists())
+        .and_then(|path| read_settings_file(&path).ok())
+        .unwrap_or_else(default_settings)
+}
+
+// 立即写入尚未落盘的设置
+#[tauri::command]
+pub async fn flush_settings(
+    app_handle: tauri::AppHandle,
+    writer: tauri::State<'_, SettingsWriter>,
+) -> Result<(), AppError> {
+    log::info!("立即写入设置");
+    if let Some(written) = writer.flush()? {
+        emit_settings_changed(&app_handle, &written);
+    }
+    Ok(())
+}
+
+// 诊断设置文件：报告检测到的编码、能否解析、每个无效设置项的问题、无法识别的设置项以及是否需要迁移（只读，不修改文件）
+#[tauri::command]
+pub async fn diagnose_settings(app_handle: tauri::AppHandle) -> Result<serde_json::Value, AppError> {
+    log::info!("诊断设置文件");
+
+    let path = settings_path(&app_handle)?;
+    if !path.exists() {
+        return Ok(serde_json::json!({
+            "path": path,
+            "exists": false,
+            "encoding": null,
+            "hadBom": false,
+            "needsConversion": false,
+            "valid": false,
+            "error": null,
+            "issues": [],
+            "unknownKeys": [],
+            "settingsVersion": null,
+            "needsMigration": false
+        }));
+    }
+
+    let bytes = std::fs::read(&path).map_err(|e| {
+        let error = format!("读取设置文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+    let decoded = decode_settings_bytes(&bytes);
+
+    let (valid, error, issues, unknown_keys, version) = match serde_json::from_str::<serde_json::Value>(&decoded.text) {
+        Ok(value) => {
+            let unknown_keys: Vec<String> = value
+                .as_object()
+                .map(|obj| {
+                    obj.keys()
+                        .filter(|k| !KNOWN_SETTING_KEYS.contains(&k.as_str()))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            let version = value.get("settingsVersion").and_then(|v| v.as_u64());
+            match validate_settings(&value) {
+                Ok(()) => (true, None, vec![], unknown_keys, version),
+                Err(e) => (false, Some(e), setting_issues(&value), unknown_keys, version),
+            }
+        }
+        Err(e) => (false, Some(format!("JSON 解析失败: {}", e)), vec![], vec![], None),
+    };
+
+    Ok(serde_json::json!({
+        "path": path,
+        "exists": true,
+        "encoding": decoded.encoding,
+        "hadBom": decoded.had_bom,
+        "needsConversion": decoded.transcoded,
+        "valid": valid,
+        "error": error,
+        "issues": issues,
+        "unknownKeys": unknown_keys,
+        "settingsVersion": version,
+        "needsMigration": version.is_none_or(|version| version < SETTINGS_VERSION)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TempDir;
+
+    fn read_json(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn missing_key_is_filled_in() {
+        let dir = TempDir::new("settings-missing");
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, r##"{"theme":"dark","autoSave":"yes","customColor":"#ff0000"}"##).unwrap();
+
+        let settings = read_settings_file(&path).unwrap();
+        let defaults = default_settings();
+        assert_eq!(settings["theme"], "dark");
+        assert_eq!(settings["autoSave"], defaults["autoSave"], "类型错误的值应恢复为默认值");
+        assert_eq!(settings["soundEnabled"], defaults["soundEnabled"]);
+        assert_eq!(settings["logLevel"], defaults["logLevel"]);
+        assert_eq!(settings["customColor"], "#ff0000", "前端自己的设置项应原样保留");
+        assert_eq!(settings["settingsVersion"], SETTINGS_VERSION);
+        // 迁移后的设置写回文件
+        assert_eq!(read_json(&path), settings);
+    }
+
+    #[test]
+    fn corrupt_file_is_recovered() {
+        let dir = TempDir::new("settings-corrupt");
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, b"{\"theme\": \"dark\",").unwrap();
+
+        assert_eq!(read_settings_file(&path).unwrap(), default_settings());
+        assert_eq!(std::fs::read(dir.path().join("settings.json.corrupt")).unwrap(), b"{\"theme\": \"dark\",");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn utf16_file_is_transcoded() {
+        let dir = TempDir::new("settings-utf16");
+        let path = dir.path().join("settings.json");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(r#"{"theme":"深色"}"#.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        std::fs::write(&path, bytes).unwrap();
+
+        assert_eq!(read_settings_file(&path).unwrap()["theme"], "深色");
+        assert_eq!(read_json(&path)["theme"], "深色");
+    }
+}
@@ -0,0 +1,138 @@
+// 音效
+//
+// 自定义音效文件保存在 coredata/sounds/ 下，设置中的 drawSound / winSound
+// 记录对应的文件名。导入时通过文件头（magic bytes）校验格式，并限制文件大小。
+// 内置音效（spin、win、lose 等）随程序打包在资源目录的 sounds/ 下，由 play_sound 在后台线程中播放。
+
+use std::io::Read;
+use std::path::PathBuf;
+
+use tauri::Manager;
+
+use crate::error::AppError;
+use crate::{history, settings};
+
+// 内置音效支持的扩展名，按顺序查找
+const BUNDLED_SOUND_EXTENSIONS: &[&str] = &["wav", "ogg", "mp3"];
+
+// 单个音效文件的大小上限
+const MAX_SOUND_SIZE: u64 = 10 * 1024 * 1024;
+
+// 音效目录 coredata/sounds
+pub fn sounds_dir() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join("sounds"))
+}
+
+// 根据文件头识别音频格式，返回扩展名
+fn detect_audio_format(header: &[u8]) -> Option<&'static str> {
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+        return Some("wav");
+    }
+    if header.len() >= 4 && &header[0..4] == b"OggS" {
+        return Some("ogg");
+    }
+    // MP3：ID3 标签或 MPEG 帧同步字
+    if header.len() >= 3 && &header[0..3] == b"ID3" {
+        return Some("mp3");
+    }
+    if header.len() >= 2 && header[0] == 0xFF && (header[1] & 0xE0) == 0xE0 {
+        return Some("mp3");
+    }
+    None
+}
+
+// 导入自定义音效，返回保存后的文件名（用于 drawSound / winSound 设置）
+#[tauri::command]
+pub async fn import_sound(file_path: String, kind: String) -> Result<String, AppError> {
+    log::info!("导入音效: {} ({})", file_path, kind);
+    crate::app_lock::ensure_unlocked()?;
+
+    if kind != "draw" && kind != "win" {
+        return Err(AppError::InvalidInput(format!("不支持的音效类型: {}，应为 draw 或 win", kind)));
+    }
+
+    let metadata = std::fs::metadata(&file_path).map_err(|e| format!("读取音效文件失败: {}", e))?;
+    if !metadata.is_file() {
+        return Err(AppError::InvalidInput(format!("不是有效的文件: {}", file_path)));
+    }
+    if metadata.len() > MAX_SOUND_SIZE {
+        return Err(AppError::InvalidInput(format!(
+            "音效文件过大: {:.1} MB，最大允许 {} MB",
+            metadata.len() as f64 / 1024.0 / 1024.0,
+            MAX_SOUND_SIZE / 1024 / 1024
+        )));
+    }
+
+    let mut header = [0u8; 12];
+    let read = std::fs::File::open(&file_path)
+        .and_then(|mut f| f.read(&mut header))
+        .map_err(|e| format!("读取音效文件失败: {}", e))?;
+    let ext = detect_audio_format(&header[..read])
+        .ok_or("不支持的音频格式，仅支持 WAV、MP3、OGG")?;
+
+    let dir = sounds_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建音效目录失败: {}", e))?;
+
+    let stored_name = format!("{}_{}.{}", kind, chrono::Local::now().format("%Y%m%d%H%M%S"), ext);
+    std::fs::copy(&file_path, dir.join(&stored_name)).map_err(|e| {
+        let error = format!("复制音效文件失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    log::info!("音效已导入: {}", stored_name);
+    Ok(stored_name)
+}
+
+// 查找内置音效文件：资源目录 sounds/<name>.<扩展名>
+fn bundled_sound_path(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, AppError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(AppError::InvalidInput(format!("无效的音效名称: {}", name)));
+    }
+    let dir = app_handle
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("获取资源目录失败: {}", e))?
+        .join("sounds");
+    BUNDLED_SOUND_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| AppError::NotFound(format!("音效不存在: {}", name)))
+}
+
+// 播放内置音效，soundEnabled 关闭时不播放；volume 取 0.0–1.0，缺省为 1.0
+//
+// 解码在命令内完成，便于返回格式错误；播放在后台线程中进行，命令不等待播放结束。
+#[tauri::command]
+pub async fn play_sound(app_handle: tauri::AppHandle, name: String, volume: Option<f32>) -> Result<(), AppError> {
+    let current_settings = settings::current_settings(&app_handle)?;
+    if !current_settings.get("soundEnabled").and_then(|v| v.as_bool()).unwrap_or(true) {
+        return Ok(());
+    }
+
+    let path = bundled_sound_path(&app_handle, &name)?;
+    let volume = volume.filter(|v| v.is_finite()).unwrap_or(1.0).clamp(0.0, 1.0);
+    let file = std::fs::File::open(&path).map_err(|e| AppError::io("读取音效文件失败", e))?;
+    let source = rodio::Decoder::new(std::io::BufReader::new(file))
+        .map_err(|e| AppError::Parse(format!("解码音效失败 {}: {}", name, e)))?;
+
+    std::thread::spawn(move || {
+        let (_stream, handle) = match rodio::OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                log::error!("打开音频输出设备失败: {}", e);
+                return;
+            }
+        };
+        match rodio::Sink::try_new(&handle) {
+            Ok(sink) => {
+                sink.set_volume(volume);
+                sink.append(source);
+                sink.sleep_until_end();
+            }
+            Err(e) => log::error!("创建音频播放失败: {}", e),
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,348 @@
+// 历史记录统计分析
+//
+// 各项统计都基于 history 模块的索引与任务文件读取，姓名按规范化形式比较。
+
+use chrono::{Datelike, Timelike};
+
+use crate::error::AppError;
+use crate::{history, roster, settings};
+
+// 基尼系数：0 表示中奖次数完全平均，越接近 1 越集中在少数人身上
+pub fn gini_coefficient(values: &[usize]) -> f64 {
+    let n = values.len();
+    let total: usize = values.iter().sum();
+    if n == 0 || total == 0 {
+        return 0.0;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| (i as f64 + 1.0) * x as f64)
+        .sum();
+
+    let n = n as f64;
+    (2.0 * weighted) / (n * total as f64) - (n + 1.0) / n
+}
+
+// 针对某个名单的公平性统计：成员中奖次数、从未中奖的成员以及基尼系数
+#[tauri::command]
+pub async fn roster_stats(roster_id: String, since: Option<String>) -> Result<serde_json::Value, AppError> {
+    log::info!("统计名单中奖情况: {} (起始: {:?})", roster_id, since);
+    crate::app_lock::ensure_unlocked()?;
+
+    let roster = roster::load_roster(&roster_id)?;
+    let since = history::parse_since(since.as_deref())?;
+    let counts = history::win_counts_since(since)?;
+
+    let mut members = Vec::new();
+    let mut never_won = Vec::new();
+    let mut values = Vec::new();
+    for name in roster.member_names() {
+        let wins = counts.get(&history::normalize_name(&name)).copied().unwrap_or(0);
+        if wins == 0 {
+            never_won.push(name.clone());
+        }
+        values.push(wins);
+        members.push(serde_json::json!({ "name": name, "wins": wins }));
+    }
+
+    Ok(serde_json::json!({
+        "rosterId": roster.id,
+        "rosterName": roster.name,
+        "since": since.map(|dt| dt.to_rfc3339()),
+        "memberCount": members.len(),
+        "totalWins": values.iter().sum::<usize>(),
+        "members": members,
+        "neverWon": never_won,
+        "gini": gini_coefficient(&values)
+    }))
+}
+
+// 名单中在指定时间窗口内从未中奖的成员，保持名单原有顺序
+#[tauri::command]
+pub async fn never_picked(roster_id: String, since: Option<String>) -> Result<Vec<String>, AppError> {
+    log::info!("查询从未中奖的成员: {} (起始: {:?})", roster_id, since);
+    crate::app_lock::ensure_unlocked()?;
+
+    let roster = roster::load_roster(&roster_id)?;
+    let since = history::parse_since(since.as_deref())?;
+    let counts = history::win_counts_since(since)?;
+
+    Ok(roster
+        .member_names()
+        .into_iter()
+        .filter(|name| !counts.contains_key(&history::normalize_name(name)))
+        .collect())
+}
+
+// 按一天中的小时与星期几统计使用情况（基于索引中的时间戳）
+//
+// 时间换算到设置中的 timezoneOffsetMinutes（相对 UTC 的分钟数），未配置时使用系统本地时区。
+#[tauri::command]
+pub async fn usage_by_hour(app_handle: tauri::AppHandle) -> Result<serde_json::Value, AppError> {
+    log::info!("统计使用时间分布");
+    crate::app_lock::ensure_unlocked()?;
+
+    let offset = settings::current_settings(&app_handle)?
+        .get("timezoneOffsetMinutes")
+        .and_then(|v| v.as_i64())
+        .and_then(|minutes| chrono::FixedOffset::east_opt((minutes * 60) as i32));
+
+    let mut hours = [0usize; 24];
+    // 周一为 0，周日为 6
+    let mut weekdays = [0usize; 7];
+    let mut total = 0;
+
+    for item in history::load_history_index()? {
+        let Some(ts) = history::parse_timestamp(&item) else {
+            continue;
+        };
+        let (hour, weekday) = match offset {
+            Some(offset) => {
+                let local = ts.with_timezone(&offset);
+                (local.hour(), local.weekday())
+            }
+            None => {
+                let local = ts.with_timezone(&chrono::Local);
+                (local.hour(), local.weekday())
+            }
+        };
+        hours[hour as usize] += 1;
+        weekdays[weekday.num_days_from_monday() as usize] += 1;
+        total += 1;
+    }
+
+    Ok(serde_json::json!({
+        "total": total,
+        "timezone": offset.map(|o| o.to_string()).unwrap_or_else(|| "local".to_string()),
+        "hours": hours,
+        "weekdays": weekdays
+    }))
+}
+
+// 任务参与者的规范化集合（任务未记录参与者时为 None）
+fn participant_set(task_data: &serde_json::Value) -> Option<std::collections::BTreeSet<String>> {
+    let participants = task_data.get("participants")?.as_array()?;
+    Some(
+        participants
+            .iter()
+            .filter_map(|p| p.as_str())
+            .map(history::normalize_name)
+            .collect(),
+    )
+}
+
+// 比较两次抽取的中奖结果
+#[tauri::command]
+pub async fn compare_tasks(task_id_a: String, task_id_b: String) -> Result<serde_json::Value, AppError> {
+    log::info!("比较历史任务: {} / {}", task_id_a, task_id_b);
+    crate::app_lock::ensure_unlocked()?;
+
+    let task_a = history::find_task(&task_id_a)?.ok_or_else(|| AppError::NotFound(format!("任务 A 不存在: {}", task_id_a)))?;
+    let task_b = history::find_task(&task_id_b)?.ok_or_else(|| AppError::NotFound(format!("任务 B 不存在: {}", task_id_b)))?;
+
+    let winners_a = history::task_winners(&task_a);
+    let winners_b = history::task_winners(&task_b);
+    let set_a: std::collections::HashSet<String> = winners_a.iter().map(|w| history::normalize_name(w)).collect();
+    let set_b: std::collections::HashSet<String> = winners_b.iter().map(|w| history::normalize_name(w)).collect();
+
+    // 保持各自的抽取顺序输出
+    let only_a: Vec<&String> = winners_a.iter().filter(|w| !set_b.contains(&history::normalize_name(w))).collect();
+    let only_b: Vec<&String> = winners_b.iter().filter(|w| !set_a.contains(&history::normalize_name(w))).collect();
+    let both: Vec<&String> = winners_a.iter().filter(|w| set_b.contains(&history::normalize_name(w))).collect();
+
+    // 两个任务都记录了参与者时才能判断参与者池是否一致
+    let pools_match = match (participant_set(&task_a), participant_set(&task_b)) {
+        (Some(a), Some(b)) => Some(a == b),
+        _ => None,
+    };
+
+    Ok(serde_json::json!({
+        "taskA": { "id": task_id_a, "name": task_a.get("name"), "timestamp": task_a.get("timestamp") },
+        "taskB": { "id": task_id_b, "name": task_b.get("name"), "timestamp": task_b.get("timestamp") },
+        "onlyInA": only_a,
+        "onlyInB": only_b,
+        "inBoth": both,
+        "poolsMatch": pools_match
+    }))
+}
+
+// 某个小组的中奖排行榜
+//
+// 参与者池由该小组的历史任务推导：任务记录的参与者与中奖者的并集，因此没中过奖的人也会以 0 次出现。
+// 排名采用并列名次（1, 1, 3），返回名次不超过 top_n 的所有人，处在边界上的并列者会全部包含；top_n 为 0 时返回全部。
+#[tauri::command]
+pub async fn group_leaderboard(
+    group_name: String,
+    top_n: usize,
+    since: Option<String>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    log::info!("统计小组排行榜: {} (前 {} 名, 起始: {:?})", group_name, top_n, since);
+    crate::app_lock::ensure_unlocked()?;
+
+    let since = history::parse_since(since.as_deref())?;
+
+    // 规范化姓名 -> (首次出现时的原始姓名, 中奖次数)
+    let mut tally: std::collections::HashMap<String, (String, usize)> = std::collections::HashMap::new();
+    for index_item in history::load_history_index()? {
+        if index_item.get("groupName").and_then(|v| v.as_str()) != Some(group_name.as_str()) {
+            continue;
+        }
+        if let Some(since) = since {
+            match history::parse_timestamp(&index_item) {
+                Some(ts) if ts >= since => {}
+                _ => continue,
+            }
+        }
+        let Some(task_data) = history::load_task_data(&index_item) else {
+            continue;
+        };
+
+        let participants = task_data
+            .get("participants")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|p| p.as_str()).map(|p| p.to_string()).collect())
+            .unwrap_or_else(Vec::new);
+        for name in participants {
+            tally
+                .entry(history::normalize_name(&name))
+                .or_insert_with(|| (name.trim().to_string(), 0));
+        }
+        for winner in history::task_winners(&task_data) {
+            tally
+                .entry(history::normalize_name(&winner))
+                .or_insert_with(|| (winner.trim().to_string(), 0))
+                .1 += 1;
+        }
+    }
+
+    let mut entries: Vec<(String, usize)> = tally.into_values().collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut leaderboard = Vec::new();
+    let mut rank = 0;
+    let mut previous_wins = None;
+    for (position, (name, wins)) in entries.into_iter().enumerate() {
+        if previous_wins != Some(wins) {
+            rank = position + 1;
+            previous_wins = Some(wins);
+        }
+        if top_n > 0 && rank > top_n {
+            break;
+        }
+        leaderboard.push(serde_json::json!({ "rank": rank, "name": name, "wins": wins }));
+    }
+
+    Ok(leaderboard)
+}
+
+// 全部历史中每个人的中奖次数，可限定某个小组
+//
+// 姓名按规范化形式合并，返回 [{ name, count, lastWonTimestamp }]，按次数从多到少排序，次数相同按姓名排序。
+#[tauri::command]
+pub async fn get_win_frequency(group: Option<String>) -> Result<Vec<serde_json::Value>, AppError> {
+    log::info!("统计中奖频率 (小组: {:?})", group);
+    crate::app_lock::ensure_unlocked()?;
+
+    // 规范化姓名 -> (首次出现时的原始姓名, 中奖次数, 最近一次中奖时间)
+    let mut tally: std::collections::HashMap<String, (String, usize, Option<chrono::DateTime<chrono::Utc>>)> =
+        std::collections::HashMap::new();
+    for index_item in history::load_history_index()? {
+        if let Some(group) = &group {
+            if index_item.get("groupName").and_then(|v| v.as_str()) != Some(group.as_str()) {
+                continue;
+            }
+        }
+        let Some(task_data) = history::load_task_data(&index_item) else {
+            continue;
+        };
+
+        let timestamp = history::parse_timestamp(&index_item);
+        for winner in history::task_winners(&task_data) {
+            let entry = tally
+                .entry(history::normalize_name(&winner))
+                .or_insert_with(|| (winner.trim().to_string(), 0, None));
+            entry.1 += 1;
+            if timestamp > entry.2 {
+                entry.2 = timestamp;
+            }
+        }
+    }
+
+    let mut entries: Vec<_> = tally.into_values().collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(entries
+        .into_iter()
+        .map(|(name, count, last_won)| {
+            serde_json::json!({
+                "name": name,
+                "count": count,
+                "lastWonTimestamp": last_won.map(|ts| ts.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+            })
+        })
+        .collect())
+}
+
+// 多轮抽取后预计有多少不同的人被抽中过
+//
+// 每轮从 pool_size 人中不放回抽取 draws_per_round 人，各轮之间相互独立（上一轮中奖者下一轮仍可被抽中）。
+// 某人 r 轮都未被抽中的概率 q = (1 - k/n)^r，期望人数 E = n(1 - q)；方差按任意两人同时未被抽中的概率
+// ((n-k)(n-k-1) / (n(n-1)))^r 精确计算，confidenceBand 为正态近似的 95% 区间。
+// roundsUntilAllLikely 为所有人都被抽中过的概率（泊松近似 e^(-nq)）首次达到 50% 所需的轮数。
+#[tauri::command]
+pub async fn expected_unique_winners(
+    pool_size: usize,
+    draws_per_round: usize,
+    rounds: usize,
+) -> Result<serde_json::Value, AppError> {
+    log::info!("估算多轮抽取的不同中奖人数: {} 人, 每轮 {} 人, {} 轮", pool_size, draws_per_round, rounds);
+    crate::app_lock::ensure_unlocked()?;
+
+    if pool_size == 0 {
+        return Err(AppError::InvalidInput("参与人数必须大于0".to_string()));
+    }
+    if draws_per_round == 0 || draws_per_round > pool_size {
+        return Err(AppError::InvalidInput(format!("每轮抽取人数必须在 1 到 {} 之间", pool_size)));
+    }
+
+    let n = pool_size as f64;
+    let k = draws_per_round as f64;
+    let r = rounds.min(i32::MAX as usize) as i32;
+
+    let miss_one = (1.0 - k / n).powi(r);
+    let miss_pair = if pool_size > 1 {
+        ((n - k) * (n - k - 1.0) / (n * (n - 1.0))).max(0.0).powi(r)
+    } else {
+        miss_one
+    };
+
+    let expected = n * (1.0 - miss_one);
+    // 未被抽中人数 X 的方差：E[X²] - E[X]² = nq + n(n-1)q₂ - (nq)²
+    let variance = (n * miss_one + n * (n - 1.0) * miss_pair - (n * miss_one).powi(2)).max(0.0);
+    let std_dev = variance.sqrt();
+    let lower_bound = if rounds == 0 { 0.0 } else { k };
+    let low = (expected - 1.96 * std_dev).max(lower_bound);
+    let high = (expected + 1.96 * std_dev).min(n);
+
+    let rounds_until_all_likely = if draws_per_round == pool_size {
+        1
+    } else {
+        ((std::f64::consts::LN_2 / n).ln() / (1.0 - k / n).ln()).ceil().max(1.0) as usize
+    };
+
+    Ok(serde_json::json!({
+        "poolSize": pool_size,
+        "drawsPerRound": draws_per_round,
+        "rounds": rounds,
+        "expected": expected,
+        "stdDev": std_dev,
+        "confidenceBand": { "level": 0.95, "low": low, "high": high },
+        "expectedCoverage": expected / n,
+        "probabilityAllPicked": (-n * miss_one).exp(),
+        "roundsUntilAllLikely": rounds_until_all_likely
+    }))
+}
@@ -0,0 +1,450 @@
+// 文件存储辅助函数
+
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+
+
+// 原子写入临时文件的序号
+static TEMP_FILE_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// 程序目录只读时改用的数据基础目录（其下的 coredata 为实际数据目录）
+static DATA_ROOT_FALLBACK: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+// 未配置 fileRetryAttempts 时的默认尝试次数
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+
+// 允许配置的最大尝试次数
+const MAX_RETRY_ATTEMPTS: u32 = 10;
+
+// 首次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+// 从设置中读取写入文件的尝试次数（fileRetryAttempts），限制在 1 到 10 之间
+pub fn retry_attempts(settings: &serde_json::Value) -> u32 {
+    settings
+        .get("fileRetryAttempts")
+        .and_then(|v| v.as_u64())
+        .map(|n| n.clamp(1, MAX_RETRY_ATTEMPTS as u64) as u32)
+        .unwrap_or(DEFAULT_RETRY_ATTEMPTS)
+}
+
+// 是否为值得重试的临时错误；权限不足等错误重试也不会成功
+fn is_transient(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    // Windows 上网络盘或同步盘（OneDrive 等）占用文件时返回
+    // ERROR_SHARING_VIOLATION (32) 或 ERROR_LOCK_VIOLATION (33)
+    #[cfg(windows)]
+    if matches!(error.raw_os_error(), Some(32) | Some(33)) {
+        return true;
+    }
+
+    matches!(
+        error.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+    )
+}
+
+// 对临时性 I/O 错误按指数退避重试，最多尝试 attempts 次
+pub fn with_retry<T>(attempts: u32, f: impl Fn() -> std::io::Result<T>) -> std::io::Result<T> {
+    let attempts = attempts.max(1);
+    let mut delay = RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                log::warn!("文件操作失败（第 {} 次尝试），{:?} 后重试: {}", attempt, delay, e);
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// 原子写入：先写入同目录下的临时文件并刷盘，再重命名覆盖目标文件，
+// 写入过程中崩溃只会留下临时文件，不会破坏原有内容
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    write_atomic_with(path, |file| file.write_all(contents))
+}
+
+// write_atomic 的实现，由 write 写入临时文件的内容
+fn write_atomic_with(path: &Path, write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>) -> std::io::Result<()> {
+    crate::watcher::note_own_write(path);
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(parent)?;
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // 同一进程内可能并发写入同一文件，临时文件名带上进程 ID 与递增序号避免互相覆盖
+    let sequence = TEMP_FILE_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let temp_path = parent.join(format!(".{}.{}.{}.tmp", file_name, std::process::id(), sequence));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        write(&mut file)?;
+        file.sync_all()?;
+        drop(file);
+        // Windows 上 std::fs::rename 使用 MOVEFILE_REPLACE_EXISTING，可以覆盖已存在的文件
+        std::fs::rename(&temp_path, path)?;
+        sync_dir(parent)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// 重命名后刷新所在目录，保证断电后新的目录项也已落盘（Windows 不支持打开目录，rename 本身已足够）
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+// 读取 JSON 文件；内容损坏时把原文件改名为 <文件名>.corrupt 备份并返回 None
+pub fn read_json_or_recover(path: &Path) -> Result<Option<serde_json::Value>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".corrupt");
+            log::warn!("文件已损坏（{}），备份到 {:?} 后重新开始", e, backup);
+            std::fs::rename(path, &backup).map_err(|e| format!("备份损坏文件失败: {}", e))?;
+            Ok(None)
+        }
+    }
+}
+
+// 文件 API（save_json_file 等）允许访问的数据基础目录下的顶层目录
+pub const DATA_FILE_ROOTS: &[&str] = &["coredata"];
+
+// 文件 API 不能访问的 coredata 下的文件与目录：应用锁与加密配置、账本、历史索引数据库以及任务文件目录，
+// 这些只能通过对应的命令读写。名称按小写比较，history.db、history.json 同时包含它们的附属文件（如 -journal、.migrated）。
+pub const PROTECTED_DATA_FILES: &[&str] = &["app_lock.json", "encryption.json", "ledger.jsonl"];
+const PROTECTED_DATA_PREFIXES: &[&str] = &["history.db", "history.json"];
+const PROTECTED_DATA_DIRS: &[&str] = &["history"];
+
+// 数据根目录下的相对路径是否指向受保护的文件或目录
+fn is_protected_data_path(relative: &Path) -> bool {
+    let mut names = relative.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_string_lossy().to_lowercase()),
+        _ => None,
+    });
+    if names.next().as_deref() != Some("coredata") {
+        return false;
+    }
+    let Some(name) = names.next() else {
+        return false;
+    };
+    let is_last = names.next().is_none();
+    PROTECTED_DATA_DIRS.contains(&name.as_str())
+        || (is_last
+            && (PROTECTED_DATA_FILES.contains(&name.as_str())
+                || PROTECTED_DATA_PREFIXES.iter().any(|prefix| name.starts_with(prefix))))
+}
+
+// path 最深的已存在上级目录（或其本身）的规范路径
+fn canonical_existing(path: &Path, user_path: &str) -> Result<PathBuf, AppError> {
+    let mut existing = path;
+    while !existing.exists() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => break,
+        }
+    }
+    existing
+        .canonicalize()
+        .map_err(|e| AppError::io(&format!("解析路径失败 {}", user_path), e))
+}
+
+// 把前端传入的相对路径解析到 base 目录下，拒绝任何可能逃出 base 的路径
+//
+// 绝对路径、盘符以及包含 .. 的路径直接拒绝；解析后再对最深的已存在上级目录取规范路径，
+// 防止通过符号链接指向 base 之外。目标文件本身可以不存在。被拒绝时返回 AppError::PathNotAllowed。
+pub fn resolve_safe_path(base: &Path, user_path: &str) -> Result<PathBuf, AppError> {
+    let relative = Path::new(user_path.trim());
+    for component in relative.components() {
+        match component {
+            Component::Normal(_) | Component::CurDir => {}
+            Component::ParentDir => return Err(AppError::PathNotAllowed(format!("路径不能包含 ..: {}", user_path))),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(AppError::PathNotAllowed(format!("不允许使用绝对路径: {}", user_path)))
+            }
+        }
+    }
+
+    let full_path = base.join(relative);
+    let canonical_base = base
+        .canonicalize()
+        .map_err(|e| AppError::io(&format!("解析数据目录失败 {:?}", base), e))?;
+    if !canonical_existing(&full_path, user_path)?.starts_with(&canonical_base) {
+        return Err(AppError::PathNotAllowed(format!("路径超出数据目录: {}", user_path)));
+    }
+
+    Ok(full_path)
+}
+
+// 解析文件 API（save_json_file 等）的路径：只能位于数据基础目录下 DATA_FILE_ROOTS 中的目录内，且不能是受保护的文件
+pub fn resolve_data_path(user_path: &str) -> Result<PathBuf, AppError> {
+    if is_protected_data_path(Path::new(user_path.trim())) {
+        return Err(AppError::PathNotAllowed(format!("该文件只能通过对应的功能访问: {}", user_path)));
+    }
+    let root = Path::new(user_path.trim()).components().find_map(|component| match component {
+        Component::Normal(name) => name.to_str(),
+        _ => None,
+    });
+    let Some(root) = root.filter(|root| DATA_FILE_ROOTS.contains(root)) else {
+        return Err(AppError::PathNotAllowed(format!(
+            "只能访问 {} 目录下的文件: {}",
+            DATA_FILE_ROOTS.join("、"),
+            user_path
+        )));
+    };
+
+    let base = data_base_dir()?;
+    let full_path = resolve_safe_path(&base, user_path)?;
+    // 顶层目录本身是指向别处的符号链接时同样拒绝
+    let root_dir = base.join(root);
+    if root_dir.exists() {
+        let canonical_root = root_dir
+            .canonicalize()
+            .map_err(|e| AppError::io("解析数据目录失败", e))?;
+        if !canonical_existing(&full_path, user_path)?.starts_with(&canonical_root) {
+            return Err(AppError::PathNotAllowed(format!("路径超出数据目录: {}", user_path)));
+        }
+    }
+
+    Ok(full_path)
+}
+
+// 检查目录是否可写：创建目录并写入、删除一个探测文件
+pub fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".write_probe.{}", std::process::id()));
+    std::fs::write(&probe, b"probe")?;
+    std::fs::remove_file(&probe)
+}
+
+// 数据基础目录：默认为当前工作目录，只读时为启动时选定的用户目录
+pub fn data_base_dir() -> Result<PathBuf, String> {
+    if let Some(fallback) = DATA_ROOT_FALLBACK.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return Ok(fallback.clone());
+    }
+    std::env::current_dir().map_err(|e| e.to_string())
+}
+
+// 启动时检查 coredata 是否可写；只读时改用应用的本地数据目录，并发送 data-readonly-fallback 事件
+//
+// 学校等受管控的电脑上程序目录经常是只读的，此时所有保存都会失败。
+pub fn init_data_root(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let primary = std::env::current_dir().map_err(|e| e.to_string())?;
+    let probe_error = match probe_writable(&primary.join("coredata")) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+    log::warn!("数据目录不可写: {:?} ({})", primary.join("coredata"), probe_error);
+
+    let fallback = app_handle.path().app_local_data_dir().map_err(|e| e.to_string())?;
+    probe_writable(&fallback.join("coredata")).map_err(|e| {
+        let error = format!("备用数据目录也不可写 {:?}: {}", fallback, e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    *DATA_ROOT_FALLBACK.write().unwrap_or_else(|e| e.into_inner()) = Some(fallback.clone());
+    log::warn!("已改用备用数据目录: {:?}", fallback.join("coredata"));
+
+    let payload = serde_json::json!({
+        "primary": primary.join("coredata"),
+        "fallback": fallback.join("coredata"),
+        "error": probe_error.to_string()
+    });
+    if let Err(e) = app_handle.emit("data-readonly-fallback", payload) {
+        log::error!("发送数据目录切换事件失败: {}", e);
+    }
+    Ok(())
+}
+
+// 当前实际使用的数据目录 coredata
+#[tauri::command]
+pub async fn get_writable_root() -> Result<String, AppError> {
+    Ok(crate::history::coredata_dir()?.to_string_lossy().to_string())
+}
+
+// 测试用的临时目录
+#[cfg(test)]
+pub mod test_support {
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+
+    static TEMP_DIR_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+    // 数据基础目录是全局状态，使用它的测试依次执行
+    static DATA_ROOT_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    // 创建一个空的临时目录，drop 时删除
+    pub struct TempDir {
+        path: PathBuf,
+    }
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let sequence = TEMP_DIR_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!("randompeople-{}-{}-{}", name, std::process::id(), sequence));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).expect("创建临时目录失败");
+            TempDir { path }
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    // 把数据基础目录切换到新的临时目录，持有期间其他使用数据目录的测试等待，drop 时恢复
+    pub struct TempDataRoot {
+        dir: TempDir,
+        _guard: MutexGuard<'static, ()>,
+    }
+
+    impl TempDataRoot {
+        pub fn new(name: &str) -> Self {
+            let guard = DATA_ROOT_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let dir = TempDir::new(name);
+            *super::DATA_ROOT_FALLBACK.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.path().to_path_buf());
+            TempDataRoot { dir, _guard: guard }
+        }
+
+        // 数据目录 coredata
+        pub fn coredata(&self) -> PathBuf {
+            self.dir.path().join("coredata")
+        }
+    }
+
+    impl Drop for TempDataRoot {
+        fn drop(&mut self) {
+            *super::DATA_ROOT_FALLBACK.write().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{TempDataRoot, TempDir};
+    use super::*;
+
+    #[test]
+    fn safe_path_rejects_escapes() {
+        let base = TempDir::new("safe-path");
+        for user_path in ["../secret.txt", "subdir/../../secret.txt", "/etc/passwd"] {
+            assert!(
+                matches!(resolve_safe_path(base.path(), user_path), Err(AppError::PathNotAllowed(_))),
+                "{} 应被拒绝",
+                user_path
+            );
+        }
+        let resolved = resolve_safe_path(base.path(), "subdir/ok.json").unwrap();
+        assert_eq!(resolved, base.path().join("subdir/ok.json"));
+    }
+
+    #[test]
+    fn data_path_stays_inside_coredata() {
+        let root = TempDataRoot::new("data-path");
+        for user_path in ["../secret.txt", "coredata/../secret.txt", "/etc/passwd", "subdir/ok.json"] {
+            assert!(
+                matches!(resolve_data_path(user_path), Err(AppError::PathNotAllowed(_))),
+                "{} 应被拒绝",
+                user_path
+            );
+        }
+        let resolved = resolve_data_path("coredata/subdir/ok.json").unwrap();
+        assert_eq!(resolved, root.coredata().join("subdir/ok.json"));
+    }
+
+    #[test]
+    fn data_path_rejects_protected_files() {
+        let _root = TempDataRoot::new("protected-path");
+        for user_path in ["coredata/app_lock.json", "coredata/history.db-journal", "coredata/history/task.json"] {
+            assert!(
+                matches!(resolve_data_path(user_path), Err(AppError::PathNotAllowed(_))),
+                "{} 应被拒绝",
+                user_path
+            );
+        }
+    }
+
+    // 目录中除 keep 以外的文件名
+    fn other_files(dir: &Path, keep: &str) -> Vec<String> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name != keep)
+            .collect()
+    }
+
+    #[test]
+    fn write_atomic_overwrites_existing_file() {
+        let dir = TempDir::new("write-atomic");
+        let path = dir.path().join("data.json");
+        write_atomic(&path, b"{\"version\":1}").unwrap();
+        write_atomic(&path, b"{\"version\":2}").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"version\":2}");
+        assert!(other_files(dir.path(), "data.json").is_empty());
+    }
+
+    #[test]
+    fn write_atomic_failure_keeps_original() {
+        let dir = TempDir::new("write-atomic-failure");
+        let path = dir.path().join("data.json");
+        write_atomic(&path, b"original").unwrap();
+
+        // 写入一半时失败，相当于重命名之前崩溃
+        let result = write_atomic_with(&path, |file| {
+            file.write_all(b"partial")?;
+            Err(std::io::Error::other("模拟写入失败"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "original");
+        assert!(other_files(dir.path(), "data.json").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn safe_path_rejects_symlink_escape() {
+        let base = TempDir::new("symlink-base");
+        let outside = TempDir::new("symlink-outside");
+        std::os::unix::fs::symlink(outside.path(), base.path().join("link")).unwrap();
+        assert!(matches!(
+            resolve_safe_path(base.path(), "link/secret.txt"),
+            Err(AppError::PathNotAllowed(_))
+        ));
+    }
+}
@@ -0,0 +1,120 @@
+// 系统托盘
+//
+// 托盘菜单提供 显示/隐藏、打开历史记录、退出；左键单击托盘图标切换主窗口的显示。
+// 启用 closeToTray 后，关闭主窗口只会隐藏到托盘，需要从托盘菜单退出程序。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::settings;
+
+// 设置中记录“关闭时最小化到托盘”的键
+const CLOSE_TO_TRAY_KEY: &str = "closeToTray";
+
+// 托盘菜单点击“打开历史记录”时发给主窗口的事件
+const OPEN_HISTORY_EVENT: &str = "tray-open-history";
+
+// 当前是否关闭到托盘，启动时从设置读取
+static CLOSE_TO_TRAY: AtomicBool = AtomicBool::new(false);
+
+fn show_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+// 切换主窗口的显示：可见时隐藏，隐藏或最小化时显示并聚焦
+fn toggle_main_window(app_handle: &tauri::AppHandle) {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return;
+    };
+    let visible = window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false);
+    if visible {
+        let _ = window.hide();
+    } else {
+        show_main_window(app_handle);
+    }
+}
+
+fn handle_menu_event(app_handle: &tauri::AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "toggle" => toggle_main_window(app_handle),
+        "history" => {
+            show_main_window(app_handle);
+            if let Err(e) = app_handle.emit_to("main", OPEN_HISTORY_EVENT, ()) {
+                log::error!("发送打开历史记录事件失败: {}", e);
+            }
+        }
+        "quit" => {
+            log::info!("从托盘菜单退出程序");
+            app_handle.exit(0);
+        }
+        _ => {}
+    }
+}
+
+// 在 setup 中创建托盘图标，并从设置中读取 closeToTray
+pub fn init_tray(app: &tauri::App) -> tauri::Result<()> {
+    let close_to_tray = settings::current_settings(app.handle())
+        .ok()
+        .and_then(|s| s.get(CLOSE_TO_TRAY_KEY).and_then(|v| v.as_bool()))
+        .unwrap_or(false);
+    CLOSE_TO_TRAY.store(close_to_tray, Ordering::Relaxed);
+
+    let toggle = MenuItem::with_id(app, "toggle", "显示/隐藏", true, None::<&str>)?;
+    let history = MenuItem::with_id(app, "history", "打开历史记录", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&toggle, &history, &separator, &quit])?;
+
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip("StarRandom")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                toggle_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    log::info!("托盘图标已创建，关闭到托盘: {}", close_to_tray);
+    Ok(())
+}
+
+// 主窗口关闭时，启用了 closeToTray 则改为隐藏到托盘
+pub fn handle_window_event(window: &tauri::Window, event: &tauri::WindowEvent) {
+    if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+        if window.label() == "main" && CLOSE_TO_TRAY.load(Ordering::Relaxed) {
+            api.prevent_close();
+            if let Err(e) = window.hide() {
+                log::error!("隐藏主窗口失败: {}", e);
+            }
+        }
+    }
+}
+
+// 设置关闭主窗口时是否最小化到托盘，并保存到设置中
+#[tauri::command]
+pub async fn set_close_to_tray(app_handle: tauri::AppHandle, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置关闭到托盘: {}", enabled);
+
+    settings::update_setting(&app_handle, CLOSE_TO_TRAY_KEY, serde_json::json!(enabled))?;
+    CLOSE_TO_TRAY.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
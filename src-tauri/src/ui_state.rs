@@ -0,0 +1,31 @@
+// 界面布局状态
+//
+// 面板尺寸、列宽、折叠状态等界面细节单独保存在 coredata/ui_state.json，
+// 不混入用户设置，避免频繁的界面变动污染可迁移的设置档案。
+
+use std::path::PathBuf;
+
+use crate::error::AppError;
+use crate::{history, storage};
+
+fn ui_state_path() -> Result<PathBuf, String> {
+    Ok(history::coredata_dir()?.join("ui_state.json"))
+}
+
+// 保存界面状态
+#[tauri::command]
+pub async fn save_ui_state(state: serde_json::Value) -> Result<(), AppError> {
+    let content = serde_json::to_string_pretty(&state).map_err(|e| e.to_string())?;
+    storage::write_atomic(&ui_state_path()?, content.as_bytes()).map_err(|e| {
+        let error = AppError::io("保存界面状态失败", e);
+        log::error!("{}", error);
+        error
+    })
+}
+
+// 加载界面状态，文件不存在或已损坏时返回空对象
+#[tauri::command]
+pub async fn load_ui_state() -> Result<serde_json::Value, AppError> {
+    Ok(storage::read_json_or_recover(&ui_state_path()?)?
+        .unwrap_or_else(|| serde_json::json!({})))
+}
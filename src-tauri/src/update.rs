@@ -0,0 +1,116 @@
+// 检查更新
+//
+// 从设置 updateManifestUrl 指向的版本清单获取最新版本，与编译进程序的 APP_VERSION 按语义化版本比较。
+// 清单格式：{ "version": "v1.0.8", "download_url"?: "...", "notes"?: "..." }，
+// 也兼容 Tauri 更新器的 latest.json（url 在 platforms.<平台>.url 中）。
+
+use std::time::Duration;
+
+use crate::error::AppError;
+use crate::settings;
+
+// 未配置 updateManifestUrl 时使用的版本清单地址
+pub const DEFAULT_UPDATE_MANIFEST_URL: &str =
+    "https://github.com/Vistaminc/RandomPeople/releases/latest/download/latest.json";
+
+// 请求版本清单的超时时间
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 清单中没有下载地址时返回的发布页
+const RELEASES_PAGE_URL: &str = "https://github.com/Vistaminc/RandomPeople/releases/latest";
+
+// 解析 v1.0.7 / 1.0.7 形式的版本号
+fn parse_version(version: &str) -> Result<semver::Version, String> {
+    let trimmed = version.trim();
+    let trimmed = trimmed.strip_prefix(['v', 'V']).unwrap_or(trimmed);
+    semver::Version::parse(trimmed).map_err(|e| format!("无效的版本号 {}: {}", version, e))
+}
+
+// 清单中的下载地址：优先 download_url / url，其次 Tauri 更新器格式中当前平台的 url
+fn manifest_download_url(manifest: &serde_json::Value) -> Option<String> {
+    let direct = ["download_url", "downloadUrl", "url"]
+        .iter()
+        .find_map(|key| manifest.get(*key).and_then(|v| v.as_str()));
+    if let Some(url) = direct {
+        return Some(url.to_string());
+    }
+
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    manifest
+        .get("platforms")
+        .and_then(|p| p.get(&platform))
+        .and_then(|p| p.get("url"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+async fn fetch_manifest(url: &str) -> Result<serde_json::Value, String> {
+    let client = reqwest::Client::builder()
+        .timeout(UPDATE_CHECK_TIMEOUT)
+        .user_agent(format!("StarRandom/{}", crate::APP_VERSION))
+        .build()
+        .map_err(|e| format!("创建网络请求失败: {}", e))?;
+
+    let response = client.get(url).send().await.map_err(|e| {
+        if e.is_timeout() {
+            format!("检查更新超时（{} 秒）: {}", UPDATE_CHECK_TIMEOUT.as_secs(), url)
+        } else {
+            format!("无法连接更新服务器: {}", e)
+        }
+    })?;
+    if !response.status().is_success() {
+        return Err(format!("更新服务器返回错误状态: {}", response.status()));
+    }
+    response.json().await.map_err(|e| {
+        if e.is_timeout() {
+            format!("检查更新超时（{} 秒）: {}", UPDATE_CHECK_TIMEOUT.as_secs(), url)
+        } else {
+            format!("解析版本清单失败: {}", e)
+        }
+    })
+}
+
+// 检查是否有新版本，返回 { update_available, latest_version, download_url, notes }
+#[tauri::command]
+pub async fn check_for_update(app_handle: tauri::AppHandle) -> Result<serde_json::Value, AppError> {
+    let current_settings = settings::current_settings(&app_handle)?;
+    let url = current_settings
+        .get("updateManifestUrl")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .unwrap_or(DEFAULT_UPDATE_MANIFEST_URL)
+        .to_string();
+    log::info!("检查更新: {}", url);
+
+    let manifest = fetch_manifest(&url).await.map_err(|e| {
+        log::error!("{}", e);
+        e
+    })?;
+
+    let latest_version = manifest
+        .get("version")
+        .and_then(|v| v.as_str())
+        .ok_or("版本清单缺少 version")?
+        .to_string();
+    let update_available = parse_version(&latest_version)? > parse_version(crate::APP_VERSION)?;
+    let download_url = manifest_download_url(&manifest).unwrap_or_else(|| RELEASES_PAGE_URL.to_string());
+    let notes = manifest
+        .get("notes")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    log::info!(
+        "当前版本 {}，最新版本 {}，{}",
+        crate::APP_VERSION,
+        latest_version,
+        if update_available { "有可用更新" } else { "已是最新版本" }
+    );
+    Ok(serde_json::json!({
+        "update_available": update_available,
+        "latest_version": latest_version,
+        "download_url": download_url,
+        "notes": notes
+    }))
+}
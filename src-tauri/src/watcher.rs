@@ -0,0 +1,190 @@
+// 监视 coredata 下历史数据的外部修改
+//
+// 手动修改、拷入任务文件或通过网络盘同步时，运行中的程序无法得知变化。
+// 这里监视 coredata/history/（递归）与历史索引数据库 coredata/history.db，合并短时间内的连续事件后
+// 向前端发送 history-changed 事件。程序自身刚写入或删除的文件（通过 note_own_write 登记）不会触发事件，
+// 避免与 save_history_task 等命令形成循环。网络盘上的监视不一定可靠，可以用 stop_watching 关闭。
+// 后台任务暂停期间（见 background.rs）不发送事件，变化的路径先记下，恢复时合并为一次 history-changed。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use notify::Watcher;
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::{background, history, history_db};
+
+// 发给前端的事件名
+const HISTORY_CHANGED_EVENT: &str = "history-changed";
+
+// 最后一次事件后等待多久再通知前端
+const DEBOUNCE_PERIOD: Duration = Duration::from_millis(500);
+
+// 程序自身写入后多长时间内的事件视为自身写入
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(2);
+
+// 当前的文件监视器，None 表示未在监视
+static WATCHER: Mutex<Option<notify::RecommendedWatcher>> = Mutex::new(None);
+
+// 后台任务暂停期间检测到的外部修改，恢复时一并通知
+static DEFERRED_CHANGES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// 程序最近写入或删除的文件及时间
+static OWN_WRITES: LazyLock<Mutex<HashMap<PathBuf, Instant>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 登记程序自身即将写入或删除的文件，随后一小段时间内该文件的事件会被忽略
+pub fn note_own_write(path: &Path) {
+    let mut own_writes = OWN_WRITES.lock().unwrap_or_else(|e| e.into_inner());
+    own_writes.retain(|_, time| time.elapsed() < OWN_WRITE_WINDOW);
+    own_writes.insert(path.to_path_buf(), Instant::now());
+}
+
+fn is_own_write(path: &Path) -> bool {
+    let own_writes = OWN_WRITES.lock().unwrap_or_else(|e| e.into_inner());
+    own_writes.get(path).is_some_and(|time| time.elapsed() < OWN_WRITE_WINDOW)
+}
+
+// write_atomic 的临时文件（.<文件名>.<进程>.<序号>.tmp）
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.') && n.ends_with(".tmp"))
+}
+
+// 事件是否来自外部对历史数据的修改；目录本身的事件（例如保存时新建年月目录）忽略，只看其中的文件
+fn is_external_change(event: &notify::Event, index_path: &Path, history_path: &Path) -> bool {
+    if event.kind.is_access() {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        (path == index_path || path.starts_with(history_path))
+            && !path.is_dir()
+            && !is_temp_file(path)
+            && !is_own_write(path)
+    })
+}
+
+// 接收文件事件，合并连续事件后发送 history-changed；监视器被释放后通道关闭，线程随之结束
+fn forward_events(
+    app_handle: tauri::AppHandle,
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    index_path: PathBuf,
+    history_path: PathBuf,
+) {
+    loop {
+        let event = match rx.recv() {
+            Ok(Ok(event)) => event,
+            Ok(Err(e)) => {
+                log::warn!("文件监视出错: {}", e);
+                continue;
+            }
+            Err(_) => break,
+        };
+        if !is_external_change(&event, &index_path, &history_path) {
+            continue;
+        }
+
+        let mut changed: Vec<PathBuf> = event.paths;
+        let mut disconnected = false;
+        loop {
+            match rx.recv_timeout(DEBOUNCE_PERIOD) {
+                Ok(Ok(event)) => {
+                    if is_external_change(&event, &index_path, &history_path) {
+                        changed.extend(event.paths);
+                    }
+                }
+                Ok(Err(e)) => log::warn!("文件监视出错: {}", e),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+        if disconnected {
+            break;
+        }
+
+        changed.retain(|path| path == &index_path || path.starts_with(&history_path));
+        if !background::enabled() {
+            log::info!("后台任务已暂停，推迟通知历史数据的外部修改: {:?}", changed);
+            DEFERRED_CHANGES.lock().unwrap_or_else(|e| e.into_inner()).extend(changed);
+            continue;
+        }
+        emit_changed(&app_handle, changed);
+    }
+    log::info!("文件监视线程已结束");
+}
+
+// 向前端发送 history-changed 事件，paths 为发生变化的文件
+fn emit_changed(app_handle: &tauri::AppHandle, mut changed: Vec<PathBuf>) {
+    changed.sort();
+    changed.dedup();
+    let paths: Vec<String> = changed.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    log::info!("检测到历史数据被外部修改: {:?}", paths);
+    if let Err(e) = app_handle.emit(HISTORY_CHANGED_EVENT, serde_json::json!({ "paths": paths })) {
+        log::error!("发送 history-changed 事件失败: {}", e);
+    }
+}
+
+// 后台任务恢复时发送暂停期间推迟的修改通知，合并为一次 history-changed
+pub fn flush_deferred(app_handle: &tauri::AppHandle) {
+    let changed = std::mem::take(&mut *DEFERRED_CHANGES.lock().unwrap_or_else(|e| e.into_inner()));
+    if !changed.is_empty() {
+        emit_changed(app_handle, changed);
+    }
+}
+
+// 开始监视历史数据；已在监视时直接返回
+pub fn start(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let mut watcher_slot = WATCHER.lock().unwrap_or_else(|e| e.into_inner());
+    if watcher_slot.is_some() {
+        return Ok(());
+    }
+
+    let coredata_path = history::coredata_dir()?;
+    let history_path = history::history_dir()?;
+    std::fs::create_dir_all(&history_path).map_err(|e| format!("创建历史目录失败: {}", e))?;
+    let index_path = history_db::db_path()?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| format!("创建文件监视器失败: {}", e))?;
+    // 数据库可能尚不存在，监视其所在的 coredata 目录（非递归）
+    watcher
+        .watch(&coredata_path, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| format!("监视数据目录失败: {}", e))?;
+    watcher
+        .watch(&history_path, notify::RecursiveMode::Recursive)
+        .map_err(|e| format!("监视历史目录失败: {}", e))?;
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || forward_events(app_handle, rx, index_path, history_path));
+
+    *watcher_slot = Some(watcher);
+    log::info!("已开始监视历史数据");
+    Ok(())
+}
+
+// 开始监视 coredata 下历史数据的外部修改
+#[tauri::command]
+pub async fn start_watching(app_handle: tauri::AppHandle) -> Result<(), AppError> {
+    log::info!("开始监视历史数据");
+    Ok(start(&app_handle)?)
+}
+
+// 停止监视历史数据
+#[tauri::command]
+pub async fn stop_watching() -> Result<(), AppError> {
+    let watcher = WATCHER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if watcher.is_some() {
+        log::info!("已停止监视历史数据");
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+// 窗口与显示器管理
+
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::settings;
+
+// 设置中记录固定显示器序号的键
+const KIOSK_MONITOR_KEY: &str = "kioskMonitor";
+
+// 把窗口移动到指定序号的显示器并铺满该显示器
+fn place_on_monitor(window: &tauri::WebviewWindow, monitor_index: usize) -> Result<(), String> {
+    let monitors = window
+        .available_monitors()
+        .map_err(|e| format!("获取显示器列表失败: {}", e))?;
+    let monitor = monitors.get(monitor_index).ok_or_else(|| {
+        format!("显示器序号 {} 超出范围，当前共 {} 个显示器", monitor_index, monitors.len())
+    })?;
+
+    if window.is_maximized().unwrap_or(false) {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+    window
+        .set_position(*monitor.position())
+        .map_err(|e| format!("移动窗口失败: {}", e))?;
+    window
+        .set_size(*monitor.size())
+        .map_err(|e| format!("调整窗口大小失败: {}", e))?;
+
+    log::info!(
+        "窗口已移动到显示器 {} ({:?})",
+        monitor_index,
+        monitor.name()
+    );
+    Ok(())
+}
+
+// 启动时恢复设置中固定的显示器
+pub fn restore_kiosk_monitor(app_handle: &tauri::AppHandle, window: &tauri::WebviewWindow) {
+    let Ok(current) = settings::current_settings(app_handle) else {
+        return;
+    };
+    if let Some(index) = current.get(KIOSK_MONITOR_KEY).and_then(|v| v.as_u64()) {
+        if let Err(e) = place_on_monitor(window, index as usize) {
+            log::warn!("恢复固定显示器失败: {}", e);
+        }
+    }
+}
+
+// 把主窗口固定到指定显示器，并保存到设置中供下次启动恢复
+#[tauri::command]
+pub async fn move_to_monitor(
+    app_handle: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    monitor_index: usize,
+) -> Result<(), AppError> {
+    log::info!("移动窗口到显示器: {}", monitor_index);
+
+    place_on_monitor(&window, monitor_index)?;
+    Ok(settings::update_setting(&app_handle, KIOSK_MONITOR_KEY, serde_json::json!(monitor_index))?)
+}
+
+// 投影窗口的标签，前端页面为 /projector
+pub const PROJECTOR_LABEL: &str = "projector";
+
+// 推送给投影窗口的结果事件
+const PROJECTOR_EVENT: &str = "projector-update";
+
+// 投影窗口使用的显示器：优先选择主窗口所在显示器以外的第一个显示器，只有一个显示器时使用主显示器
+fn projector_monitor(app_handle: &tauri::AppHandle) -> Result<Option<tauri::Monitor>, String> {
+    let monitors = app_handle
+        .available_monitors()
+        .map_err(|e| format!("获取显示器列表失败: {}", e))?;
+    let main_position = app_handle
+        .get_webview_window("main")
+        .and_then(|window| window.current_monitor().ok().flatten())
+        .map(|monitor| *monitor.position());
+
+    if let Some(secondary) = monitors
+        .iter()
+        .find(|monitor| Some(*monitor.position()) != main_position)
+    {
+        return Ok(Some(secondary.clone()));
+    }
+
+    let primary = app_handle
+        .primary_monitor()
+        .map_err(|e| format!("获取主显示器失败: {}", e))?;
+    Ok(primary.or_else(|| monitors.into_iter().next()))
+}
+
+// 打开全屏无边框的投影窗口，用于在大屏幕上只显示抽取结果；已打开时只把它切到前台
+//
+// 投影窗口是独立的窗口，关闭它不会影响主窗口。
+#[tauri::command]
+pub async fn open_projector_window(app_handle: tauri::AppHandle) -> Result<(), AppError> {
+    log::info!("打开投影窗口");
+
+    if let Some(window) = app_handle.get_webview_window(PROJECTOR_LABEL) {
+        window.show().map_err(|e| e.to_string())?;
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let window = tauri::WebviewWindowBuilder::new(
+        &app_handle,
+        PROJECTOR_LABEL,
+        tauri::WebviewUrl::App("projector".into()),
+    )
+    .title("StarRandom 投影")
+    .decorations(false)
+    .visible(false)
+    .build()
+    .map_err(|e| {
+        let error = format!("创建投影窗口失败: {}", e);
+        log::error!("{}", error);
+        error
+    })?;
+
+    // 先移动到目标显示器再全屏，否则会在主窗口所在的显示器上全屏
+    match projector_monitor(&app_handle)? {
+        Some(monitor) => {
+            window
+                .set_position(*monitor.position())
+                .map_err(|e| format!("移动投影窗口失败: {}", e))?;
+            log::info!("投影窗口使用显示器: {:?}", monitor.name());
+        }
+        None => log::warn!("未检测到显示器信息，投影窗口使用默认位置"),
+    }
+    window
+        .set_fullscreen(true)
+        .map_err(|e| format!("投影窗口全屏失败: {}", e))?;
+    window.show().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// 把抽取结果推送到投影窗口显示，payload 为 { title?, winners: [姓名] }
+#[tauri::command]
+pub async fn push_to_projector(app_handle: tauri::AppHandle, payload: serde_json::Value) -> Result<(), AppError> {
+    log::info!("推送结果到投影窗口");
+    crate::app_lock::ensure_unlocked()?;
+
+    if app_handle.get_webview_window(PROJECTOR_LABEL).is_none() {
+        return Err(AppError::NotFound("投影窗口未打开".to_string()));
+    }
+
+    app_handle
+        .emit_to(PROJECTOR_LABEL, PROJECTOR_EVENT, payload)
+        .map_err(|e| AppError::Internal(format!("发送投影事件失败: {}", e)))
+}
@@ -6,7 +6,7 @@ import { listen } from '@tauri-apps/api/event'
 import { ToastManager } from '@/components/ui/toast'
 import { useConfirmDialog } from '@/components/ui/confirm-dialog'
 import { useToast } from '@/hooks/useToast'
-import { testTauriConnection } from '@/lib/tauri'
+import { testTauriConnection, errorMessage } from '@/lib/tauri'
 import { getAllSettings } from '@/lib/officialStore'
 import { encryptPassword, verifyPassword, isPasswordEncrypted } from '@/lib/crypto'
 
@@ -2278,6 +2278,17 @@ export default function Home() {
     };
   }, []);
 
+  useEffect(() => {
+    // 托盘菜单中的“打开历史记录”
+    const unlisten = listen('tray-open-history', () => {
+      setShowHistoryDialog(true)
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
   return (
     <div className="min-h-screen bg-gray-900 text-white flex items-center justify-center">
       {/* 左侧点击区域 */}
@@ -3784,12 +3795,24 @@ export default function Home() {
                               <Button
                                 onClick={() => {
                                   // 如果任务设置了编辑保护，需要验证密码
-                                  if (selectedTask.edit_protected && selectedTask.edit_password) {
+                                  if (selectedTask.edit_protected) {
                                     showPasswordDialogFunc({
                                       title: '编辑保护验证',
                                       message: '该任务已设置编辑保护，请输入编辑密码：',
-                                      onConfirm: (password) => {
-                                        if (!verifyPassword(password, selectedTask.edit_password)) {
+                                      onConfirm: async (password) => {
+                                        // 从后端加载的任务不含密码，由后端校验；仅本地保存的任务在前端校验
+                                        let valid = false
+                                        if (selectedTask.edit_password) {
+                                          valid = verifyPassword(password, selectedTask.edit_password)
+                                        } else {
+                                          try {
+                                            const { verifyTaskProtectionInTauri } = await import('@/lib/tauri')
+                                            valid = await verifyTaskProtectionInTauri(selectedTask.id, password)
+                                          } catch (error) {
+                                            console.error('校验编辑密码失败:', error)
+                                          }
+                                        }
+                                        if (!valid) {
                                           showError('编辑密码不正确')
                                           return
                                         }
@@ -3989,7 +4012,7 @@ export default function Home() {
                             showSuccess('历史记录索引重建完成');
                           } catch (error) {
                             console.error('❌ 重建索引失败:', error);
-                            showError('重建索引失败: ' + error);
+                            showError('重建索引失败: ' + errorMessage(error));
                           }
                         }
                       });
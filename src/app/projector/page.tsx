@@ -0,0 +1,47 @@
+'use client'
+
+import React, { useEffect, useState } from 'react'
+import { listen } from '@tauri-apps/api/event'
+
+// 投影窗口推送内容：{ title?: 标题, winners: 中奖者姓名列表 }
+interface ProjectorPayload {
+  title?: string
+  winners?: string[]
+}
+
+// 投影窗口：全屏显示主窗口通过 push_to_projector 推送的抽取结果
+export default function ProjectorPage() {
+  const [payload, setPayload] = useState<ProjectorPayload>({})
+
+  useEffect(() => {
+    // 监听主窗口推送的结果
+    const unlisten = listen<ProjectorPayload>('projector-update', (event) => {
+      setPayload(event.payload ?? {})
+    })
+
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
+  const winners = payload.winners ?? []
+
+  return (
+    <div className="flex h-screen w-screen flex-col items-center justify-center bg-gray-950 text-white select-none">
+      {payload.title && (
+        <h1 className="mb-12 text-5xl font-bold text-blue-300">{payload.title}</h1>
+      )}
+      {winners.length > 0 ? (
+        <div className="flex max-w-[90vw] flex-wrap items-center justify-center gap-x-16 gap-y-10">
+          {winners.map((name, index) => (
+            <span key={`${name}-${index}`} className="text-7xl font-bold tracking-wide">
+              {name}
+            </span>
+          ))}
+        </div>
+      ) : (
+        <p className="text-3xl text-gray-500">等待抽取结果…</p>
+      )}
+    </div>
+  )
+}
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
 // Tauri后端命令接口
 const tauriCommands = {
@@ -23,10 +24,110 @@ const tauriCommands = {
   // 新增的历史记录管理命令
   saveHistoryTask: (taskData: any) => invoke<void>('save_history_task', { taskData }),
   getHistoryData: () => invoke<any[]>('get_history_data'),
+  getHistoryPage: (offset: number, limit: number, sort?: string) =>
+    invoke<{ items: any[]; total: number }>('get_history_page', { offset, limit, sort }),
+  queryHistoryPage: (params: any) =>
+    invoke<{ items: any[]; total: number }>('query_history_page', { params }),
+  searchHistory: (query?: string, group?: string, start?: string, end?: string, winner?: string) =>
+    invoke<any[]>('search_history', { query, group, start, end, winner }),
   getHistoryTask: (taskId: string) => invoke<any | null>('get_history_task', { taskId }),
   deleteHistoryTask: (taskId: string) => invoke<void>('delete_history_task', { taskId }),
-  clearHistoryData: () => invoke<void>('clear_history_data'),
-  getHistoryStats: () => invoke<any>('get_history_stats')
+  clearHistoryData: (backup = true) => invoke<string | null>('clear_history_data', { backup }),
+  beginBulkImport: () => invoke<void>('begin_bulk_import'),
+  endBulkImport: () => invoke<void>('end_bulk_import'),
+  setHistoryRetention: (maxRecords?: number, maxAgeDays?: number) =>
+    invoke<number>('set_history_retention', { maxRecords, maxAgeDays }),
+  createBackup: (targetPath: string) => invoke<number>('create_backup', { targetPath }),
+  restoreBackup: (archivePath: string) => invoke<any>('restore_backup', { archivePath }),
+  countHistoryFiles: () => invoke<number>('count_history_files'),
+  getHistoryStats: () => invoke<any>('get_history_stats'),
+  verifyTaskProtection: (taskId: string, password: string) => invoke<boolean>('verify_task_protection', { taskId, password }),
+  unlockHistoryTask: (taskId: string, password: string) => invoke<any>('unlock_history_task', { taskId, password }),
+  setTaskPassword: (taskId: string, password: string) => invoke<void>('set_task_password', { taskId, password }),
+
+  // 应用锁（主密码），锁定状态变化时发送 app-lock-changed 事件
+  setMasterPassword: (newPassword: string, currentPassword?: string) =>
+    invoke<void>('set_master_password', { currentPassword, newPassword }),
+  verifyMasterPassword: (password: string) => invoke<boolean>('verify_master_password', { password }),
+  lockApp: () => invoke<void>('lock_app'),
+  getAppLockStatus: () =>
+    invoke<{ enabled: boolean; locked: boolean; failedAttempts: number; retryAfterSeconds: number }>('get_app_lock_status'),
+
+  // 日志（保存在应用日志目录），级别：off、error、warn、info、debug、trace
+  setLogLevel: (level: string) => invoke<void>('set_log_level', { level }),
+  getRecentLogs: (lines = 200, levelFilter?: string) => invoke<string[]>('get_recent_logs', { lines, levelFilter }),
+  exportLogs: (zipPath: string) => invoke<number>('export_logs', { zipPath }),
+
+  // 系统通知
+  notifyResult: (title: string, body: string) => invoke<void>('notify_result', { title, body }),
+
+  // 定时抽取（完成后发送 scheduled-draw 事件），cronExpr 如 "0 8 * * 1-5"
+  scheduleDraw: (cronExpr: string, groupId: string, count: number) =>
+    invoke<any>('schedule_draw', { cronExpr, groupId, count }),
+  listSchedules: () => invoke<any[]>('list_schedules'),
+  cancelSchedule: (scheduleId: string) => invoke<void>('cancel_schedule', { scheduleId }),
+
+  // 名单管理命令（数据保存在 coredata/groups/）
+  listGroups: () => invoke<any[]>('list_groups'),
+  getGroup: (name: string) => invoke<any>('get_group', { name }),
+  createGroup: (name: string, members?: GroupMember[]) => invoke<any>('create_group', { name, members }),
+  renameGroup: (name: string, newName: string) => invoke<any>('rename_group', { name, newName }),
+  deleteGroup: (name: string) => invoke<void>('delete_group', { name }),
+  addMembers: (name: string, members: GroupMember[]) => invoke<any>('add_members', { name, members }),
+  removeMembers: (name: string, names: string[]) => invoke<any>('remove_members', { name, names })
+}
+
+// 名单成员，weight 缺省为 1
+export interface GroupMember {
+  name: string
+  weight?: number
+}
+
+// 抽取过程事件（由后端抽奖引擎发送），drawId 与抽取命令返回值中的 drawId 相同
+export interface DrawStartedEvent {
+  drawId: string
+  kind: string
+  pool: string[]
+  poolSize: number
+  count: number
+}
+
+export interface DrawCandidateEvent {
+  drawId: string
+  index: number
+  candidate: any
+  tier: string | null
+}
+
+export interface DrawStreamHandlers {
+  started?: (event: DrawStartedEvent) => void
+  candidate?: (event: DrawCandidateEvent) => void
+  finished?: (result: any) => void
+}
+
+// 监听 draw://started、draw://candidate、draw://finished，返回取消全部监听的函数
+export async function listenDrawEvents(handlers: DrawStreamHandlers): Promise<UnlistenFn> {
+  const unlisteners = await Promise.all([
+    listen<DrawStartedEvent>('draw://started', e => handlers.started?.(e.payload)),
+    listen<DrawCandidateEvent>('draw://candidate', e => handlers.candidate?.(e.payload)),
+    listen<any>('draw://finished', e => handlers.finished?.(e.payload))
+  ])
+  return () => unlisteners.forEach(unlisten => unlisten())
+}
+
+// 后端命令返回的结构化错误，code 为稳定的错误码（NOT_FOUND、PERMISSION_DENIED、IO、PARSE、INVALID_INPUT、PATH_NOT_ALLOWED、CONFLICT、INTERNAL）
+export interface AppError {
+  code: string
+  message: string
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error
+}
+
+// 取出可显示的错误提示：结构化错误返回 message，其他错误按字符串显示
+export function errorMessage(error: unknown): string {
+  return isAppError(error) ? error.message : String(error)
 }
 
 // 检查是否在Tauri环境中
@@ -50,6 +151,11 @@ export async function saveHistoryToTauri(task: any): Promise<void> {
   }
 }
 
+// 编辑保护密码由后端校验（任务数据中不再包含密码）
+export async function verifyTaskProtectionInTauri(taskId: string, password: string): Promise<boolean> {
+  return await tauriCommands.verifyTaskProtection(taskId, password);
+}
+
 export async function getHistoryFromTauri(): Promise<any[]> {
   try {
     console.log('🔧 使用新的Tauri命令获取历史记录...');